@@ -1,5 +1,3 @@
-use version_control_info;
-
 fn main() {
     let maybe_vcs_info = version_control_info::try_get!();
     match maybe_vcs_info.as_ref() {
@@ -12,5 +10,8 @@ fn main() {
     };
 
     let vcs_info = version_control_info::get!();
-    println!("I am definitely on commit {:.8}, or this would be a compile error", vcs_info.commit());
+    println!(
+        "I am definitely on commit {:.8}, or this would be a compile error",
+        vcs_info.commit()
+    );
 }
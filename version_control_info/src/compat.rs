@@ -0,0 +1,295 @@
+//! Conversions between [`Info`] and the conventions of other crates which embed
+//! version control info.
+//!
+//! This is intended for codebases which are migrating to or from this crate
+//! incrementally, so that the version control info can be translated at the
+//! boundaries between components, rather than being embedded twice.
+//!
+//! The other crates record less information than [`Info`], so the conversions are
+//! lossy:
+//!
+//! * An [`Info`] converted from another crate is always a git [`SpecificInfo`], which
+//!   was read from the [`Source::Repository`]. It is not [`verified`](Info::verified),
+//!   as the other crates don't record whether the commit was checked.
+//! * The `From` conversions can't borrow any extra data, so they leave out the branch
+//!   and the output of `git describe`, and assume a crate from a local
+//!   [`Origin::Path`], as the other crates read the repository which the crate is built
+//!   in. Use [`Vergen::to_info()`] or [`Built::to_info()`] to keep them, and to set the
+//!   origin.
+//! * The only extra data of an [`Info`] converted from another crate is the branch, the
+//!   full name of `HEAD` from `built`, and the output of `git describe`.
+//! * The hash algorithm of an [`Info`] converted from another crate is inferred from
+//!   the length of its commit hash, so a hash which isn't a full SHA-256 hash is
+//!   assumed to be a SHA-1 hash.
+//! * The output of `git describe` is taken from the git extra data. If it was not
+//!   collected, the first tag of an [`Info`] is used instead, as the closest equivalent.
+
+use crate::{
+    __private, Error, Info, Origin, Source, SpecificInfo,
+    git::{self, HashAlgorithm},
+};
+
+/// The git information which the `vergen` crate provides through the `VERGEN_GIT_*`
+/// environment variables.
+///
+/// The build crate can also set these variables with
+/// `version_control_info_build::emit_vergen_env()`, so that code which reads them can
+/// be migrated later.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use version_control_info::{Info, Origin, compat::Vergen, git};
+///
+/// const VERGEN: Vergen<'_> = Vergen {
+///     sha: env!("VERGEN_GIT_SHA"),
+///     branch: option_env!("VERGEN_GIT_BRANCH"),
+///     describe: option_env!("VERGEN_GIT_DESCRIBE"),
+/// };
+///
+/// let info = Info::from(VERGEN);
+/// assert_eq!(info.commit(), VERGEN.sha);
+///
+/// // keeps the branch and the output of `git describe`
+/// const EXTRA: git::ExtraData<'_> = VERGEN.extra_data();
+/// const INFO: Info<'_> = VERGEN.to_info(Some(&EXTRA), Origin::Path);
+/// ```
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Vergen<'a> {
+    /// The commit hash, from `VERGEN_GIT_SHA`.
+    pub sha: &'a str,
+    /// The branch name, from `VERGEN_GIT_BRANCH`.
+    pub branch: Option<&'a str>,
+    /// The output of `git describe`, from `VERGEN_GIT_DESCRIBE`.
+    pub describe: Option<&'a str>,
+}
+
+impl<'a> Vergen<'a> {
+    /// Returns the git extra data of the commit, which records its branch and the
+    /// output of `git describe`.
+    ///
+    /// This is passed to [`Vergen::to_info()`], which has nowhere to store extra data
+    /// of its own.
+    #[inline]
+    #[must_use]
+    pub const fn extra_data(&self) -> git::ExtraData<'a> {
+        let branch = match self.branch {
+            Some(branch) => branch,
+            None => "",
+        };
+        extra_data(self.sha, branch, None, self.describe)
+    }
+
+    /// Converts to an [`Info`] of a crate from the `origin`, with the `extra` data,
+    /// which is usually from [`Vergen::extra_data()`].
+    #[inline]
+    #[must_use]
+    pub const fn to_info(self, extra: Option<&'a git::ExtraData<'a>>, origin: Origin) -> Info<'a> {
+        from_commit(self.sha, extra, origin)
+    }
+}
+
+impl<'a> From<&'a Info<'a>> for Vergen<'a> {
+    #[inline]
+    fn from(info: &'a Info<'a>) -> Self {
+        Self {
+            sha: info.commit(),
+            branch: info.specific.branch(),
+            describe: describe(info),
+        }
+    }
+}
+
+impl<'a> From<Vergen<'a>> for Info<'a> {
+    #[inline]
+    fn from(vergen: Vergen<'a>) -> Self {
+        vergen.to_info(None, Origin::Path)
+    }
+}
+
+/// The git information which the `built` crate writes into its generated `built.rs` file.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use version_control_info::{Error, Info, Origin, compat::Built, git};
+///
+/// mod built_info {
+///     include!(concat!(env!("OUT_DIR"), "/built.rs"));
+/// }
+///
+/// const BUILT: Built<'_> = Built {
+///     commit_hash: built_info::GIT_COMMIT_HASH,
+///     head_ref: built_info::GIT_HEAD_REF,
+///     version: built_info::GIT_VERSION,
+/// };
+///
+/// let info = Info::try_from(BUILT);
+///
+/// // keeps the branch and the output of `git describe`
+/// const EXTRA: git::ExtraData<'_> = BUILT.extra_data();
+/// const INFO: Result<Info<'_>, Error> = BUILT.to_info(Some(&EXTRA), Origin::Path);
+/// ```
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Built<'a> {
+    /// The commit hash, from `GIT_COMMIT_HASH`.
+    pub commit_hash: Option<&'a str>,
+    /// The full name of the checked out ref, e.g. `refs/heads/main`, from `GIT_HEAD_REF`.
+    ///
+    /// This is `None` if `HEAD` is detached.
+    pub head_ref: Option<&'a str>,
+    /// The output of `git describe`, from `GIT_VERSION`.
+    pub version: Option<&'a str>,
+}
+
+impl<'a> Built<'a> {
+    /// Returns the git extra data of the commit, which records its branch, the full
+    /// name of `HEAD`, and the output of `git describe`.
+    ///
+    /// This is passed to [`Built::to_info()`], which has nowhere to store extra data
+    /// of its own.
+    #[inline]
+    #[must_use]
+    pub const fn extra_data(&self) -> git::ExtraData<'a> {
+        const BRANCH_PREFIX: &str = "refs/heads/";
+
+        let commit_hash = match self.commit_hash {
+            Some(commit_hash) => commit_hash,
+            None => "",
+        };
+        // like `git rev-parse --abbrev-ref HEAD`, a detached `HEAD` is on the branch `HEAD`
+        let branch = match self.head_ref {
+            Some(head_ref) if __private::starts_with(head_ref, BRANCH_PREFIX) => {
+                __private::substr(head_ref, BRANCH_PREFIX.len(), head_ref.len())
+            }
+            Some(head_ref) => head_ref,
+            None => "HEAD",
+        };
+        extra_data(commit_hash, branch, self.head_ref, self.version)
+    }
+
+    /// Converts to an [`Info`] of a crate from the `origin`, with the `extra` data,
+    /// which is usually from [`Built::extra_data()`].
+    ///
+    /// Fails with [`Error::NoVersionControl`] if `built` could not find a commit.
+    #[inline]
+    pub const fn to_info(
+        self,
+        extra: Option<&'a git::ExtraData<'a>>,
+        origin: Origin,
+    ) -> Result<Info<'a>, Error> {
+        match self.commit_hash {
+            Some(commit_hash) => Ok(from_commit(commit_hash, extra, origin)),
+            None => Err(Error::NoVersionControl),
+        }
+    }
+}
+
+impl<'a> From<&'a Info<'a>> for Built<'a> {
+    #[inline]
+    fn from(info: &'a Info<'a>) -> Self {
+        let head_ref = match info.specific {
+            SpecificInfo::Git {
+                extra: Some(extra), ..
+            } => extra.head_ref,
+            _ => None,
+        };
+        Self {
+            commit_hash: Some(info.commit()),
+            head_ref,
+            version: describe(info),
+        }
+    }
+}
+
+impl<'a> TryFrom<Built<'a>> for Info<'a> {
+    type Error = Error;
+
+    /// Fails with [`Error::NoVersionControl`] if `built` could not find a commit.
+    #[inline]
+    fn try_from(built: Built<'a>) -> Result<Self, Self::Error> {
+        built.to_info(None, Origin::Path)
+    }
+}
+
+fn describe<'a>(info: &'a Info<'a>) -> Option<&'a str> {
+    match info.specific {
+        SpecificInfo::Git {
+            extra: Some(extra), ..
+        } if extra.describe.is_some() => extra.describe,
+        _ => info.tags().and_then(|tags| tags.first().copied()),
+    }
+}
+
+#[inline]
+const fn from_commit<'a>(
+    commit_hash: &'a str,
+    extra: Option<&'a git::ExtraData<'a>>,
+    origin: Origin,
+) -> Info<'a> {
+    Info {
+        specific: SpecificInfo::Git {
+            commit_hash,
+            hash_algorithm: match HashAlgorithm::from_hash(commit_hash) {
+                Some(hash_algorithm) => hash_algorithm,
+                None => HashAlgorithm::Sha1,
+            },
+            extra,
+        },
+        source: Source::Repository,
+        origin,
+        host: None,
+        verified: false,
+        channel: None,
+    }
+}
+
+// The extra data which the other crates record. The rest of it is unknown.
+const fn extra_data<'a>(
+    commit_hash: &'a str,
+    branch: &'a str,
+    head_ref: Option<&'a str>,
+    describe: Option<&'a str>,
+) -> git::ExtraData<'a> {
+    // the length which `git rev-parse --short` uses for most repositories
+    const SHORT_HASH_LEN: usize = 7;
+
+    let short_hash = match commit_hash.len() < SHORT_HASH_LEN {
+        true => commit_hash,
+        false => __private::substr(commit_hash, 0, SHORT_HASH_LEN),
+    };
+    git::ExtraData {
+        branch,
+        head_ref,
+        tags: None,
+        superproject_commit: None,
+        subtree_split: None,
+        dirty: None,
+        commit_timestamp: None,
+        author: None,
+        committer: None,
+        trailers: None,
+        subject: None,
+        index_tree: None,
+        describe,
+        short_hash,
+        remote_url: None,
+        upstream: None,
+        signature: None,
+        tag_signatures: None,
+        tag_targets: None,
+        nearest_tag: None,
+        tag_version: None,
+        shallow: false,
+        submodules: None,
+        notes: None,
+        commit_count: None,
+        tree_hash: None,
+        parents: None,
+        root_commits: None,
+        sparse_checkout: None,
+        dirty_files: None,
+        exact_tag: None,
+        compressed: None,
+    }
+}
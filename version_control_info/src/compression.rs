@@ -0,0 +1,82 @@
+//! Payloads which the build script compressed, to reduce the size of the binary.
+//!
+//! Some of the extra data which can be recorded, such as the submodules, notes and
+//! dirty files of a git repository, can be large. When the `compression_threshold`
+//! generate option is set in the build script, those which are at least that many
+//! bytes long are compressed with DEFLATE, and embedded as a [`Deflated`] byte slice
+//! instead. They are only decompressed when they are read, which needs the
+//! `compression` feature.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use version_control_info::SpecificInfo;
+//!
+//! let info = version_control_info::get!();
+//! if let SpecificInfo::Git { extra: Some(extra), .. } = info.specific {
+//!     let notes = match extra.compressed.and_then(|compressed| compressed.notes()) {
+//!         Some(notes) => notes.ok(),
+//!         None => extra.notes.map(String::from),
+//!     };
+//!     println!("{}", notes.unwrap_or_default());
+//! }
+//! ```
+
+#[cfg(feature = "compression")]
+use alloc::{string::String, vec::Vec};
+use core::{error::Error as ErrorTrait, fmt};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Data which was compressed with DEFLATE, in the raw format without a zlib or gzip
+/// header.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Deflated<'a> {
+    /// The compressed data.
+    pub bytes: &'a [u8],
+    /// The length of the data before it was compressed.
+    pub len: usize,
+}
+
+#[cfg(feature = "compression")]
+impl Deflated<'_> {
+    /// Decompresses the data.
+    ///
+    /// This fails if the data is not valid DEFLATE, or if it decompresses to a
+    /// different length than [`len`](Self::len).
+    pub fn inflate(&self) -> Result<Vec<u8>, InflateError> {
+        match miniz_oxide::inflate::decompress_to_vec_with_limit(self.bytes, self.len) {
+            Ok(data) if data.len() == self.len => Ok(data),
+            _ => Err(InflateError::new()),
+        }
+    }
+
+    /// Decompresses the data, which must be UTF-8.
+    pub fn inflate_str(&self) -> Result<String, InflateError> {
+        String::from_utf8(self.inflate()?).map_err(|_| InflateError::new())
+    }
+}
+
+/// The error which is returned when a [`Deflated`] payload can't be decompressed.
+///
+/// Payloads are compressed by the build script, so this is only returned if they were
+/// modified after they were generated.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct InflateError(());
+
+#[cfg(feature = "compression")]
+impl InflateError {
+    #[inline]
+    pub(crate) const fn new() -> Self {
+        Self(())
+    }
+}
+
+impl fmt::Display for InflateError {
+    fn fmt(&self, fmtr: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmtr.write_str("the compressed payload is invalid")
+    }
+}
+
+impl ErrorTrait for InflateError {}
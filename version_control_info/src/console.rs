@@ -0,0 +1,160 @@
+//! Rendering of version control info for terminals.
+//!
+//! This is intended for the verbose version output of command line programs, and for
+//! banners printed by build tools. Use [`Info::console()`] to create a [`Render`].
+
+use crate::{DisplayOptions, Info, Origin, Source};
+use core::fmt;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const CYAN: &str = "\x1b[36m";
+
+/// Renders version control info for display in a terminal.
+///
+/// By default, ANSI escape codes are used to add color and emphasis to the output.
+/// These should be disabled with [`Render::color()`] when the output is not a terminal.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use std::io::{IsTerminal, stdout};
+///
+/// const INFO: version_control_info::Info<'_> = version_control_info::get!();
+/// println!("{}", INFO.console().color(stdout().is_terminal()));
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Render<'a> {
+    info: &'a Info<'a>,
+    color: bool,
+    options: DisplayOptions<'a>,
+}
+
+impl<'a> Render<'a> {
+    /// Creates a new `Render` for the `info`, with color enabled.
+    #[inline]
+    #[must_use]
+    pub const fn new(info: &'a Info<'a>) -> Self {
+        Self {
+            info,
+            color: true,
+            options: DisplayOptions::new(),
+        }
+    }
+
+    /// Sets whether ANSI escape codes should be used in the output.
+    #[inline]
+    #[must_use]
+    pub const fn color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Sets the options used to display the info, e.g. to translate the labels.
+    #[inline]
+    #[must_use]
+    pub const fn options(mut self, options: DisplayOptions<'a>) -> Self {
+        self.options = options;
+        self
+    }
+
+    fn write_styled(
+        &self,
+        fmtr: &mut fmt::Formatter<'_>,
+        style: &str,
+        value: &dyn fmt::Display,
+    ) -> fmt::Result {
+        if self.color {
+            write!(fmtr, "{}{}{}", style, value, RESET)
+        } else {
+            write!(fmtr, "{}", value)
+        }
+    }
+
+    fn write_list(
+        &self,
+        fmtr: &mut fmt::Formatter<'_>,
+        label: &str,
+        style: &str,
+        items: &[&str],
+    ) -> fmt::Result {
+        if items.is_empty() {
+            return Ok(());
+        }
+
+        write!(fmtr, "\n{}: ", label)?;
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                fmtr.write_str(", ")?;
+            }
+            self.write_styled(fmtr, style, item)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Render<'_> {
+    fn fmt(&self, fmtr: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (info, labels) = (self.info, &self.options.labels);
+
+        write!(fmtr, "{}: ", labels.commit)?;
+        self.write_styled(fmtr, BOLD, &info.commit())?;
+        write!(fmtr, " ({}", info.specific.kind())?;
+        // a clean working tree is the usual case, so only a dirty one is called out
+        if info.dirty() == Some(true) {
+            fmtr.write_str(", ")?;
+            self.write_styled(fmtr, RED, &labels.dirty)?;
+        }
+        fmtr.write_str(")")?;
+
+        if let Some(branch) = info.specific.branch() {
+            write!(fmtr, "\n{}: ", labels.branch)?;
+            self.write_styled(fmtr, CYAN, &branch)?;
+        }
+
+        if let Some(tags) = info.tags() {
+            self.write_list(fmtr, labels.tags, GREEN, tags)?;
+        }
+
+        let source = match info.source {
+            Source::Repository => labels.repository,
+            Source::CargoVcsInfoFile => ".cargo_vcs_info.json",
+            Source::VendorManifest => labels.vendor_manifest,
+            Source::GitArchive => ".git-commit-id",
+            Source::SnapshotFile => ".vcs_info_snapshot.json",
+            Source::Environment => labels.environment,
+            Source::GitHubActions => "GitHub Actions",
+            Source::GitLabCi => "GitLab CI",
+            Source::Ci => "CI",
+            Source::Unknown => labels.unknown,
+        };
+        let origin = match info.origin {
+            Origin::Registry => labels.registry,
+            Origin::Git => "git",
+            Origin::Path => labels.path,
+            Origin::Vendored => labels.vendored,
+            Origin::Patched => labels.patched,
+        };
+        fmtr.write_str("\n")?;
+        self.write_styled(
+            fmtr,
+            DIM,
+            &format_args!(
+                "{}: {}, {}: {}",
+                labels.source, source, labels.origin, origin
+            ),
+        )
+    }
+}
+
+impl<'a> Info<'a> {
+    /// Returns a [`Render`] which displays this info for a terminal.
+    #[inline]
+    #[must_use]
+    pub const fn console(&'a self) -> Render<'a> {
+        Render::new(self)
+    }
+}
@@ -0,0 +1,87 @@
+//! Checks that the embedded version control info has not been changed since it was
+//! generated.
+//!
+//! When the `integrity_checksum` generate option is enabled in the build script, a
+//! checksum of the version control info is written alongside it, which can be read
+//! with the [`integrity_checksum!()`] macro. The [`debug_assert_integrity!()`] macro
+//! checks the info from [`try_get!()`] against it in debug builds, which catches
+//! generated files which have been edited by hand, or stale incremental artifacts.
+//!
+//! The checksum is a 64-bit [FNV-1a] hash of every field of the [`Info`], including its
+//! extra data. It is not a cryptographic signature, so it does not protect against a
+//! deliberate attacker who can rewrite both generated files.
+//!
+//! [`integrity_checksum!()`]: crate::integrity_checksum
+//! [`debug_assert_integrity!()`]: crate::debug_assert_integrity
+//! [`try_get!()`]: crate::try_get
+//! [FNV-1a]: https://en.wikipedia.org/wiki/Fowler%E2%80%93Noll%E2%80%93Vo_hash_function
+
+use crate::Info;
+use core::hash::{Hash, Hasher};
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// Computes the checksum of the given `Info`.
+#[inline]
+#[must_use]
+pub fn checksum(info: &Info<'_>) -> u64 {
+    let mut hasher = Fnv1a(FNV_OFFSET_BASIS);
+    info.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Returns `true` if the checksum of the given `Info` matches `expected`.
+///
+/// If there is no expected checksum, e.g. because the `integrity_checksum` option
+/// was not enabled in the build script, then this always returns `true`.
+#[inline]
+#[must_use]
+pub fn verify(info: &Info<'_>, expected: Option<u64>) -> bool {
+    match expected {
+        Some(expected) => checksum(info) == expected,
+        None => true,
+    }
+}
+
+// FNV-1a, which hashes integers as little-endian `u64`s, so that the checksum is the
+// same as the one which the build script computed, even if the target has a different
+// pointer width or byte order than the build machine. This must be the same as the
+// hasher of the build crate.
+struct Fnv1a(u64);
+
+impl Hasher for Fnv1a {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write_u64(u64::from(i));
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write_u64(u64::from(i));
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_u64(i as i64 as u64);
+    }
+}
@@ -0,0 +1,594 @@
+use crate::Info;
+#[cfg(feature = "alloc")]
+use crate::{Channel, Origin, RepoHost, Source, VcsKind};
+#[cfg(feature = "alloc")]
+use alloc::{string::String, vec::Vec};
+#[cfg(feature = "alloc")]
+use core::error::Error as ErrorTrait;
+use core::fmt::{self, Write};
+#[cfg(feature = "alloc")]
+use core::str::CharIndices;
+
+impl Info<'_> {
+    /// The version of the JSON object which is written by [`Info::write_json()`].
+    ///
+    /// The version is written to the `schema` key, and is incremented whenever the
+    /// meaning of the object changes. Version 1 had no `schema` key, version 2 added
+    /// it, and version 3 added the `verified`, `channel`, `dirty` and `timestamp` keys.
+    pub const JSON_SCHEMA_VERSION: u32 = 3;
+
+    /// Writes the version control info as a compact JSON object, e.g.
+    ///
+    /// ```json
+    /// {"schema":3,"kind":"git","commit":"aac20b6e7e543e6dd4118b246c77225e3a3a1302","branch":"main","tags":["v1.0.0"],"source":"Repository","origin":"Path","host":"GitHub","verified":true,"channel":"stable","dirty":false,"timestamp":1700000000}
+    /// ```
+    ///
+    /// This is the same as the JSON sidecar which the build script writes when the
+    /// `json` generate option is enabled, byte-for-byte, so the output can be compared
+    /// with the string from the [`get_json!()`] macro. It can be read back with
+    /// [`parse_versioned_json()`].
+    ///
+    /// [`get_json!()`]: crate::get_json
+    /// [`parse_versioned_json()`]: crate::parse_versioned_json
+    pub fn write_json<W: ?Sized + Write>(&self, out: &mut W) -> fmt::Result {
+        write!(out, "{{\"schema\":{},\"kind\":", Self::JSON_SCHEMA_VERSION)?;
+        write_string(out, self.specific.kind().as_str())?;
+        out.write_str(",\"commit\":")?;
+        write_string(out, self.commit())?;
+        out.write_str(",\"branch\":")?;
+        match self.specific.branch() {
+            Some(branch) => write_string(out, branch)?,
+            None => out.write_str("null")?,
+        }
+        out.write_str(",\"tags\":")?;
+        match self.tags() {
+            Some(tags) => {
+                out.write_char('[')?;
+                for (i, tag) in tags.iter().enumerate() {
+                    if i > 0 {
+                        out.write_char(',')?;
+                    }
+                    write_string(out, tag)?;
+                }
+                out.write_char(']')?;
+            }
+            None => out.write_str("null")?,
+        }
+        out.write_str(",\"source\":")?;
+        write_string(out, self.source.name())?;
+        out.write_str(",\"origin\":")?;
+        write_string(out, self.origin.name())?;
+        out.write_str(",\"host\":")?;
+        match self.host {
+            Some(host) => write_string(out, host.name())?,
+            None => out.write_str("null")?,
+        }
+        write!(out, ",\"verified\":{},\"channel\":", self.verified)?;
+        match self.channel {
+            Some(channel) => write_string(out, channel.as_str())?,
+            None => out.write_str("null")?,
+        }
+        out.write_str(",\"dirty\":")?;
+        match self.dirty() {
+            Some(dirty) => write!(out, "{}", dirty)?,
+            None => out.write_str("null")?,
+        }
+        out.write_str(",\"timestamp\":")?;
+        match self.timestamp() {
+            Some(timestamp) => write!(out, "{}", timestamp)?,
+            None => out.write_str("null")?,
+        }
+        out.write_char('}')
+    }
+
+    /// Returns the version control info as a compact JSON object.
+    ///
+    /// See [`Info::write_json()`] for more details.
+    #[cfg(feature = "alloc")]
+    #[must_use]
+    pub fn to_json(&self) -> alloc::string::String {
+        let mut json = alloc::string::String::new();
+        let _ = self.write_json(&mut json);
+        json
+    }
+}
+
+// This must escape strings in the same way as the build crate.
+fn write_string<W: ?Sized + Write>(out: &mut W, value: &str) -> fmt::Result {
+    out.write_char('"')?;
+    for ch in value.chars() {
+        match ch {
+            '"' => out.write_str("\\\"")?,
+            '\\' => out.write_str("\\\\")?,
+            '\n' => out.write_str("\\n")?,
+            '\r' => out.write_str("\\r")?,
+            '\t' => out.write_str("\\t")?,
+            '\u{0}'..='\u{1f}' => write!(out, "\\u{:04x}", ch as u32)?,
+            _ => out.write_char(ch)?,
+        }
+    }
+    out.write_char('"')
+}
+
+/// Version control info which was read from JSON by [`parse_versioned_json()`].
+#[cfg(feature = "alloc")]
+#[non_exhaustive]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct JsonInfo {
+    /// The schema version of the JSON which this was read from, which is at most
+    /// [`Info::JSON_SCHEMA_VERSION`].
+    pub schema_version: u32,
+    /// The kind of version control system.
+    pub kind: VcsKind,
+    /// The full commit hash, or the equivalent revision identifier.
+    pub commit: String,
+    /// The branch, if the version control system has one.
+    pub branch: Option<String>,
+    /// The tags associated with the commit, if they were collected.
+    pub tags: Option<Vec<String>>,
+    /// The source which the version control info was read from.
+    pub source: Source,
+    /// Where the crate's sources were obtained from when it was built.
+    pub origin: Origin,
+    /// The service which hosts the upstream repository, if it is known.
+    pub host: Option<RepoHost>,
+    /// Whether the commit was known to exist in a repository when the crate was built.
+    ///
+    /// This is `None` if the JSON was written before schema version 3.
+    pub verified: Option<bool>,
+    /// The release channel of the build, if it was classified.
+    pub channel: Option<Channel>,
+    /// Whether the working tree had uncommitted changes, if it was checked.
+    pub dirty: Option<bool>,
+    /// The time at which the commit was made, in seconds since the Unix epoch, if it
+    /// was recorded.
+    pub timestamp: Option<i64>,
+}
+
+/// An error which occurred while parsing JSON with [`parse_versioned_json()`].
+#[cfg(feature = "alloc")]
+#[non_exhaustive]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum JsonError {
+    /// The JSON was written by a newer version of this crate, with a schema version
+    /// greater than [`Info::JSON_SCHEMA_VERSION`].
+    UnsupportedVersion {
+        /// The schema version of the JSON.
+        version: u64,
+    },
+    /// The JSON is malformed, or is not an object of the expected shape.
+    Syntax {
+        /// The byte offset at which the error was found.
+        offset: usize,
+    },
+    /// A required key is missing from the object.
+    MissingField {
+        /// The name of the key.
+        field: &'static str,
+    },
+    /// A key has a value which is not valid for it, such as an unknown kind.
+    InvalidValue {
+        /// The name of the key.
+        field: &'static str,
+    },
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for JsonError {
+    fn fmt(&self, fmtr: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::UnsupportedVersion { version } => write!(
+                fmtr,
+                "unsupported JSON schema version {} (the latest supported version is {})",
+                version,
+                Info::JSON_SCHEMA_VERSION
+            ),
+            Self::Syntax { offset } => write!(fmtr, "invalid JSON at offset {}", offset),
+            Self::MissingField { field } => write!(fmtr, "missing JSON key `{}`", field),
+            Self::InvalidValue { field } => write!(fmtr, "invalid value for JSON key `{}`", field),
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl ErrorTrait for JsonError {}
+
+/// Parses version control info from JSON written by [`Info::write_json()`], or by the
+/// build script's JSON sidecar, from any version of this crate up to this one.
+///
+/// This allows a collector to aggregate the info from binaries which were built with
+/// different versions of this crate. JSON with a newer schema version than
+/// [`Info::JSON_SCHEMA_VERSION`] is rejected, and unknown keys are ignored.
+///
+/// # Example
+///
+/// ```rust
+/// use version_control_info::{VcsKind, parse_versioned_json};
+///
+/// // written before the schema was versioned
+/// let json = r#"{"kind":"git","commit":"aac20b6e7e543e6dd4118b246c77225e3a3a1302","branch":"main","tags":null,"source":"Repository","origin":"Path","host":null}"#;
+/// let info = parse_versioned_json(json).unwrap();
+/// assert_eq!(info.schema_version, 1);
+/// assert_eq!(info.kind, VcsKind::Git);
+/// assert_eq!(info.branch.as_deref(), Some("main"));
+///
+/// assert!(parse_versioned_json(r#"{"schema":999}"#).is_err());
+/// ```
+#[cfg(feature = "alloc")]
+pub fn parse_versioned_json(json: &str) -> Result<JsonInfo, JsonError> {
+    const KINDS: [VcsKind; 12] = [
+        VcsKind::Git,
+        VcsKind::Mercurial,
+        VcsKind::Jujutsu,
+        VcsKind::Pijul,
+        VcsKind::Fossil,
+        VcsKind::Subversion,
+        VcsKind::Bazaar,
+        VcsKind::Darcs,
+        VcsKind::Sapling,
+        VcsKind::Perforce,
+        VcsKind::PlasticScm,
+        VcsKind::AndroidRepo,
+    ];
+    const SOURCES: [Source; 10] = [
+        Source::Repository,
+        Source::CargoVcsInfoFile,
+        Source::VendorManifest,
+        Source::GitArchive,
+        Source::SnapshotFile,
+        Source::Environment,
+        Source::GitHubActions,
+        Source::GitLabCi,
+        Source::Ci,
+        Source::Unknown,
+    ];
+    const ORIGINS: [Origin; 5] = [
+        Origin::Registry,
+        Origin::Git,
+        Origin::Path,
+        Origin::Vendored,
+        Origin::Patched,
+    ];
+    const CHANNELS: [Channel; 4] = [
+        Channel::Stable,
+        Channel::Beta,
+        Channel::Nightly,
+        Channel::Dev,
+    ];
+    const HOSTS: [RepoHost; 6] = [
+        RepoHost::GitHub,
+        RepoHost::GitLab,
+        RepoHost::Bitbucket,
+        RepoHost::Gitea,
+        RepoHost::AzureDevOps,
+        RepoHost::Other,
+    ];
+
+    let mut parser = Parser::new(json);
+    let entries = parser.object()?;
+    parser.finish()?;
+
+    let mut schema = None;
+    let (mut kind, mut commit, mut branch, mut tags) = (None, None, None, None);
+    let (mut source, mut origin, mut host) = (None, None, None);
+    let (mut verified, mut channel, mut dirty, mut timestamp) = (None, None, None, None);
+    for (key, value) in entries {
+        match &*key {
+            "schema" => schema = Some(value),
+            "kind" => kind = Some(value),
+            "commit" => commit = Some(value),
+            "branch" => branch = Some(value),
+            "tags" => tags = Some(value),
+            "source" => source = Some(value),
+            "origin" => origin = Some(value),
+            "host" => host = Some(value),
+            "verified" => verified = Some(value),
+            "channel" => channel = Some(value),
+            "dirty" => dirty = Some(value),
+            "timestamp" => timestamp = Some(value),
+            // keys which were added by a later version of the same schema
+            _ => {}
+        }
+    }
+
+    // the schema is checked first, as a newer schema may not have the other keys
+    let schema_version = match schema {
+        None => 1,
+        Some(Value::Number(version @ 0..)) if version <= i64::from(Info::JSON_SCHEMA_VERSION) => {
+            version as u32
+        }
+        Some(Value::Number(version @ 0..)) => {
+            return Err(JsonError::UnsupportedVersion {
+                version: version as u64,
+            });
+        }
+        Some(_) => return Err(JsonError::InvalidValue { field: "schema" }),
+    };
+
+    fn required(value: Option<Value>, field: &'static str) -> Result<Value, JsonError> {
+        value.ok_or(JsonError::MissingField { field })
+    }
+
+    fn string(value: Value, field: &'static str) -> Result<Option<String>, JsonError> {
+        match value {
+            Value::Null => Ok(None),
+            Value::String(string) => Ok(Some(string)),
+            _ => Err(JsonError::InvalidValue { field }),
+        }
+    }
+
+    fn named<T: Copy, const N: usize>(
+        value: Value,
+        field: &'static str,
+        variants: [T; N],
+        name: fn(&T) -> &'static str,
+    ) -> Result<Option<T>, JsonError> {
+        match string(value, field)? {
+            Some(string) => variants
+                .into_iter()
+                .find(|variant| name(variant) == string)
+                .map(Some)
+                .ok_or(JsonError::InvalidValue { field }),
+            None => Ok(None),
+        }
+    }
+
+    // the keys which were added by version 3 are missing from earlier versions
+    fn bool(value: Option<Value>, field: &'static str) -> Result<Option<bool>, JsonError> {
+        match value {
+            None | Some(Value::Null) => Ok(None),
+            Some(Value::Bool(value)) => Ok(Some(value)),
+            Some(_) => Err(JsonError::InvalidValue { field }),
+        }
+    }
+
+    let invalid = |field| JsonError::InvalidValue { field };
+    Ok(JsonInfo {
+        schema_version,
+        kind: named(required(kind, "kind")?, "kind", KINDS, VcsKind::as_str)?
+            .ok_or(invalid("kind"))?,
+        commit: string(required(commit, "commit")?, "commit")?.ok_or(invalid("commit"))?,
+        branch: string(required(branch, "branch")?, "branch")?,
+        tags: match required(tags, "tags")? {
+            Value::Null => None,
+            Value::Strings(tags) => Some(tags),
+            _ => return Err(invalid("tags")),
+        },
+        source: named(required(source, "source")?, "source", SOURCES, Source::name)?
+            .ok_or(invalid("source"))?,
+        origin: named(required(origin, "origin")?, "origin", ORIGINS, Origin::name)?
+            .ok_or(invalid("origin"))?,
+        host: named(required(host, "host")?, "host", HOSTS, RepoHost::name)?,
+        verified: bool(verified, "verified")?,
+        channel: match channel {
+            Some(channel) => named(channel, "channel", CHANNELS, Channel::as_str)?,
+            None => None,
+        },
+        dirty: bool(dirty, "dirty")?,
+        timestamp: match timestamp {
+            None | Some(Value::Null) => None,
+            Some(Value::Number(timestamp)) => Some(timestamp),
+            Some(_) => return Err(invalid("timestamp")),
+        },
+    })
+}
+
+// The values which can appear in the JSON objects which are parsed. Arrays may only
+// contain strings, and numbers may only be integers.
+#[cfg(feature = "alloc")]
+pub(crate) enum Value {
+    Null,
+    Bool(bool),
+    Number(i64),
+    String(String),
+    Strings(Vec<String>),
+    Object(Vec<(String, Value)>),
+}
+
+// The JSON may come from an untrusted source, so objects can only be nested this deeply,
+// rather than until the stack overflows.
+#[cfg(feature = "alloc")]
+const MAX_DEPTH: usize = 16;
+
+#[cfg(feature = "alloc")]
+pub(crate) struct Parser<'a> {
+    json: &'a str,
+    pos: usize,
+    depth: usize,
+}
+
+#[cfg(feature = "alloc")]
+impl<'a> Parser<'a> {
+    pub(crate) fn new(json: &'a str) -> Self {
+        Self {
+            json,
+            pos: 0,
+            depth: 0,
+        }
+    }
+
+    // Fails unless only whitespace is left.
+    pub(crate) fn finish(mut self) -> Result<(), JsonError> {
+        self.skip_whitespace();
+        match self.pos == self.json.len() {
+            true => Ok(()),
+            false => Err(self.error()),
+        }
+    }
+
+    fn error(&self) -> JsonError {
+        JsonError::Syntax { offset: self.pos }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.json[self.pos..];
+        self.pos += rest.len() - rest.trim_start_matches([' ', '\t', '\n', '\r']).len();
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_whitespace();
+        self.json.as_bytes().get(self.pos).copied()
+    }
+
+    // Consumes `byte` if it is next, ignoring whitespace.
+    fn eat(&mut self, byte: u8) -> bool {
+        let is_next = self.peek() == Some(byte);
+        if is_next {
+            self.pos += 1;
+        }
+        is_next
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), JsonError> {
+        match self.eat(byte) {
+            true => Ok(()),
+            false => Err(self.error()),
+        }
+    }
+
+    fn eat_literal(&mut self, literal: &str) -> bool {
+        let is_next = self.json[self.pos..].starts_with(literal);
+        if is_next {
+            self.pos += literal.len();
+        }
+        is_next
+    }
+
+    pub(crate) fn object(&mut self) -> Result<Vec<(String, Value)>, JsonError> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error());
+        }
+        self.depth += 1;
+
+        let mut entries = Vec::new();
+        self.expect(b'{')?;
+        if !self.eat(b'}') {
+            loop {
+                let key = self.string()?;
+                self.expect(b':')?;
+                entries.push((key, self.value()?));
+                if !self.eat(b',') {
+                    break;
+                }
+            }
+            self.expect(b'}')?;
+        }
+
+        self.depth -= 1;
+        Ok(entries)
+    }
+
+    fn value(&mut self) -> Result<Value, JsonError> {
+        match self.peek() {
+            Some(b'"') => self.string().map(Value::String),
+            Some(b'{') => self.object().map(Value::Object),
+            Some(b'[') => {
+                self.pos += 1;
+                let mut strings = Vec::new();
+                if !self.eat(b']') {
+                    loop {
+                        strings.push(self.string()?);
+                        if !self.eat(b',') {
+                            break;
+                        }
+                    }
+                    self.expect(b']')?;
+                }
+                Ok(Value::Strings(strings))
+            }
+            Some(b'-' | b'0'..=b'9') => {
+                let sign = usize::from(self.json.as_bytes()[self.pos] == b'-');
+                let digits = sign
+                    + self.json[self.pos + sign..]
+                        .bytes()
+                        .take_while(u8::is_ascii_digit)
+                        .count();
+                let number = self.json[self.pos..self.pos + digits]
+                    .parse()
+                    .map_err(|_| self.error())?;
+                self.pos += digits;
+                Ok(Value::Number(number))
+            }
+            _ if self.eat_literal("null") => Ok(Value::Null),
+            _ if self.eat_literal("true") => Ok(Value::Bool(true)),
+            _ if self.eat_literal("false") => Ok(Value::Bool(false)),
+            _ => Err(self.error()),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, JsonError> {
+        self.expect(b'"')?;
+        let mut string = String::new();
+        let mut chars = self.json[self.pos..].char_indices();
+        while let Some((i, ch)) = chars.next() {
+            match ch {
+                '"' => {
+                    self.pos += i + 1;
+                    return Ok(string);
+                }
+                '\\' => {
+                    let unescaped = match chars.next().map(|(_, ch)| ch) {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => match unicode_escape(&mut chars) {
+                            Some(ch) => ch,
+                            None => {
+                                self.pos += i;
+                                return Err(self.error());
+                            }
+                        },
+                        _ => {
+                            self.pos += i;
+                            return Err(self.error());
+                        }
+                    };
+                    string.push(unescaped);
+                }
+                '\u{0}'..='\u{1f}' => {
+                    self.pos += i;
+                    return Err(self.error());
+                }
+                _ => string.push(ch),
+            }
+        }
+
+        self.pos = self.json.len();
+        Err(self.error())
+    }
+}
+
+// Decodes the digits of a `\u` escape. Characters outside the basic multilingual plane
+// are escaped as a surrogate pair, e.g. `\ud83d\ude00`.
+#[cfg(feature = "alloc")]
+fn unicode_escape(chars: &mut CharIndices<'_>) -> Option<char> {
+    fn unit(chars: &mut CharIndices<'_>) -> Option<u16> {
+        let hex = chars.as_str().get(..4)?;
+        if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        let unit = u16::from_str_radix(hex, 16).ok()?;
+        chars.nth(3);
+        Some(unit)
+    }
+
+    let first = unit(chars)?;
+    let second = match first {
+        0xd800..=0xdbff if chars.as_str().starts_with("\\u") => {
+            chars.nth(1);
+            Some(unit(chars)?)
+        }
+        _ => None,
+    };
+
+    let mut decoded = char::decode_utf16(Some(first).into_iter().chain(second));
+    match (decoded.next(), decoded.next()) {
+        (Some(Ok(ch)), None) => Some(ch),
+        _ => None,
+    }
+}
@@ -52,8 +52,22 @@
 //!
 //! ## Notes
 //!
-//! At the moment, this crate only supports `git` and `mercurial` repositories. Feel free to
-//! open a pull request to add support for other repositories.
+//! At the moment, this crate supports the following version control systems:
+//!
+//! * `git`
+//! * `mercurial`
+//! * `jj` (Jujutsu)
+//! * `pijul`
+//! * `fossil`
+//! * `svn` (Subversion)
+//! * `bzr` (Bazaar and Breezy)
+//! * `darcs`
+//! * `sl` (Sapling)
+//! * `p4` (Perforce)
+//! * `cm` (Plastic SCM)
+//! * `repo` (Android multi-repository clients)
+//!
+//! Feel free to open a pull request to add support for other repositories.
 //!
 //! Note that `cargo` has been built primarily with support for `git` - external repository
 //! dependencies must be specified as `git` repositories, and only `git` commit info is
@@ -64,11 +78,31 @@
 //! ## Features
 //!
 //! * `std`: Links to `std`. This feature is enabled by default.
-//! * `serde`: Implements the [`Serialize`] and [`Deseiralize`] traits on types in this crate.
+//! * `alloc`: Links to `alloc`, which lets the [`vcs_error!()`] macro format its messages,
+//!   and adds the [`parse`] module. This feature is enabled by default, as it is enabled
+//!   by the `std` feature.
+//! * `serde`: Implements the [`Serialize`] trait on types in this crate, and the
+//!   [`Deserialize`] trait on those which only borrow strings, as borrowed slices and
+//!   extra data can't be deserialized without allocating. This feature is disabled by
+//!   default.
+//! * `once_cell`: Makes the [`VcsInfoOnce`] type available without the `std` feature. A
+//!   [`critical-section`] implementation must be provided when using this feature.
 //!   This feature is disabled by default.
 //!
+//! * `console`: Adds the [`console`] module, which renders version control info with
+//!   color and emphasis for terminals. This feature is disabled by default.
+//! * `compat`: Adds the [`compat`] module, which converts version control info to and
+//!   from the conventions of the `vergen` and `built` crates. This feature is disabled
+//!   by default.
+//! * `report`: Adds the [`report`] module, which combines version control info with
+//!   the package and target into a common payload for collectors. This feature
+//!   requires `std`, and is disabled by default.
+//! * `compression`: Decompresses the payloads in the [`compression`] module which the
+//!   build script compressed. This feature requires `alloc`, and is disabled by default.
+//!
+//! [`critical-section`]: https://docs.rs/critical-section
 //! [`Serialize`]: https://docs.rs/serde/latest/serde/trait.Serialize.html
-//! [`Deseiralize`]: https://docs.rs/serde/latest/serde/trait.Deserialize.html
+//! [`Deserialize`]: https://docs.rs/serde/latest/serde/trait.Deserialize.html
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
@@ -76,11 +110,36 @@ use serde::{Deserialize, Serialize};
 #[cfg(feature = "std")]
 extern crate std as core;
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
 use core::{error::Error as ErrorTrait, fmt};
 
+#[cfg(feature = "compat")]
+pub mod compat;
+pub mod compression;
+#[cfg(feature = "console")]
+pub mod console;
+pub mod integrity;
+mod json;
+#[cfg(any(feature = "std", feature = "once_cell"))]
+mod once;
+#[cfg(feature = "alloc")]
+pub mod parse;
+#[cfg(feature = "report")]
+pub mod report;
+pub mod source_map;
+mod view;
+
+#[cfg(feature = "alloc")]
+pub use json::{JsonError, JsonInfo, parse_versioned_json};
+#[cfg(any(feature = "std", feature = "once_cell"))]
+pub use once::VcsInfoOnce;
+pub use view::VcsInfoView;
+
 /// Represents version control info for a crate.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct Info<'a> {
     /// Contains specific information about the version control system.
     pub specific: SpecificInfo<'a>,
@@ -88,6 +147,29 @@ pub struct Info<'a> {
     ///
     /// See the defintion of [`Source`] for more details.
     pub source: Source,
+    /// Records where the crate's sources were obtained from when it was built.
+    ///
+    /// See the definition of [`Origin`] for more details.
+    pub origin: Origin,
+    /// The service which hosts the upstream repository, if it is known.
+    ///
+    /// See the definition of [`RepoHost`] for more details.
+    pub host: Option<RepoHost>,
+    /// Whether the commit was known to exist in a repository when the crate was built.
+    ///
+    /// This is always `true` for info which the build script read from the
+    /// [`Source::Repository`], unless it was converted from the conventions of another
+    /// crate with the `compat` module, which doesn't know whether the commit was
+    /// checked. The commit of info from other sources, such as the
+    /// `.cargo_vcs_info.json` file, is only asserted by that source, unless the build
+    /// script was asked to verify it against a repository which contains the crate.
+    pub verified: bool,
+    /// The release channel of the build, e.g. [`Channel::Stable`] for a build of a
+    /// release tag.
+    ///
+    /// This is only classified if the build script was given rules for it, see
+    /// [`Channel`] for more details.
+    pub channel: Option<Channel>,
 }
 
 impl<'a> Info<'a> {
@@ -109,17 +191,50 @@ impl<'a> Info<'a> {
     pub const fn tags(&self) -> Option<&[&str]> {
         self.specific.tags()
     }
+
+    /// Returns the abbreviated hash of the current commit.
+    ///
+    /// See [`SpecificInfo::short_commit()`] for more details.
+    #[inline]
+    #[must_use]
+    pub const fn short_commit(&self) -> Option<&str> {
+        self.specific.short_commit()
+    }
+
+    /// Returns the time at which the current commit was made, in seconds since the
+    /// Unix epoch.
+    ///
+    /// See [`SpecificInfo::timestamp()`] for more details.
+    #[inline]
+    #[must_use]
+    pub const fn timestamp(&self) -> Option<i64> {
+        self.specific.timestamp()
+    }
+
+    /// Returns whether the working tree had uncommitted changes when the info was
+    /// detected.
+    ///
+    /// See [`SpecificInfo::dirty()`] for more details.
+    #[inline]
+    #[must_use]
+    pub const fn dirty(&self) -> Option<bool> {
+        self.specific.dirty()
+    }
 }
 
 /// Contains information which is specific to a version control program.
 #[non_exhaustive]
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub enum SpecificInfo<'a> {
     /// Contains information about a git repository.
     Git {
         /// The full commit hash.
         commit_hash: &'a str,
+        /// The hash algorithm of the repository, which decides the length of the
+        /// commit hash.
+        #[cfg_attr(feature = "serde", serde(default))]
+        hash_algorithm: git::HashAlgorithm,
         /// Extra metadata about the git repository, if available.
         ///
         /// See the definition of [`GitExtraData`] for more details.
@@ -134,6 +249,99 @@ pub enum SpecificInfo<'a> {
         /// See the definition of [`MercurialExtraData`] for more details.
         extra: Option<&'a mercurial::ExtraData<'a>>,
     },
+    /// Contains information about a Jujutsu repository.
+    Jujutsu {
+        /// The full commit id of the working copy commit.
+        commit_id: &'a str,
+        /// The full change id of the working copy commit.
+        change_id: &'a str,
+        /// Extra metadata about the Jujutsu repository.
+        ///
+        /// See the definition of [`jujutsu::ExtraData`] for more details.
+        extra: Option<&'a jujutsu::ExtraData<'a>>,
+    },
+    /// Contains information about a Pijul repository.
+    Pijul {
+        /// The state hash of the current channel.
+        state: &'a str,
+        /// Extra metadata about the Pijul repository.
+        ///
+        /// See the definition of [`pijul::ExtraData`] for more details.
+        extra: Option<&'a pijul::ExtraData<'a>>,
+    },
+    /// Contains information about a Fossil checkout.
+    Fossil {
+        /// The full hash of the checked out check-in.
+        checkout_hash: &'a str,
+        /// Extra metadata about the Fossil checkout.
+        ///
+        /// See the definition of [`fossil::ExtraData`] for more details.
+        extra: Option<&'a fossil::ExtraData<'a>>,
+    },
+    /// Contains information about a Subversion working copy.
+    Subversion {
+        /// The revision of the working copy.
+        revision: &'a str,
+        /// Extra metadata about the Subversion working copy.
+        ///
+        /// See the definition of [`subversion::ExtraData`] for more details.
+        extra: Option<&'a subversion::ExtraData<'a>>,
+    },
+    /// Contains information about a Bazaar (or Breezy) branch.
+    Bazaar {
+        /// The full revision id of the tree.
+        revision_id: &'a str,
+        /// Extra metadata about the Bazaar branch.
+        ///
+        /// See the definition of [`bazaar::ExtraData`] for more details.
+        extra: Option<&'a bazaar::ExtraData<'a>>,
+    },
+    /// Contains information about a Darcs repository.
+    Darcs {
+        /// The hash of the latest patch in the repository.
+        patch_hash: &'a str,
+        /// Extra metadata about the Darcs repository.
+        ///
+        /// See the definition of [`darcs::ExtraData`] for more details.
+        extra: Option<&'a darcs::ExtraData<'a>>,
+    },
+    /// Contains information about a Sapling repository.
+    Sapling {
+        /// The full hash of the working copy parent commit.
+        commit_hash: &'a str,
+        /// Extra metadata about the Sapling repository.
+        ///
+        /// See the definition of [`sapling::ExtraData`] for more details.
+        extra: Option<&'a sapling::ExtraData<'a>>,
+    },
+    /// Contains information about a Perforce (Helix Core) workspace.
+    Perforce {
+        /// The number of the most recent changelist synced to the workspace.
+        changelist: &'a str,
+        /// Extra metadata about the Perforce workspace.
+        ///
+        /// See the definition of [`perforce::ExtraData`] for more details.
+        extra: Option<&'a perforce::ExtraData<'a>>,
+    },
+    /// Contains information about a Plastic SCM workspace.
+    PlasticScm {
+        /// The number of the changeset loaded in the workspace.
+        changeset: &'a str,
+        /// Extra metadata about the Plastic SCM workspace.
+        ///
+        /// See the definition of [`plastic::ExtraData`] for more details.
+        extra: Option<&'a plastic::ExtraData<'a>>,
+    },
+    /// Contains information about a client checkout managed by the Android `repo` tool,
+    /// which aggregates many git repositories.
+    AndroidRepo {
+        /// The commit of the manifest repository which the client was synced from.
+        manifest_revision: &'a str,
+        /// Extra metadata about the `repo` client, including the commit of each project.
+        ///
+        /// See the definition of [`android_repo::ExtraData`] for more details.
+        extra: Option<&'a android_repo::ExtraData<'a>>,
+    },
 }
 
 impl<'a> SpecificInfo<'a> {
@@ -148,6 +356,18 @@ impl<'a> SpecificInfo<'a> {
             Self::Mercurial {
                 global_revision, ..
             } => global_revision,
+            Self::Jujutsu { commit_id, .. } => commit_id,
+            Self::Pijul { state, .. } => state,
+            Self::Fossil { checkout_hash, .. } => checkout_hash,
+            Self::Subversion { revision, .. } => revision,
+            Self::Bazaar { revision_id, .. } => revision_id,
+            Self::Darcs { patch_hash, .. } => patch_hash,
+            Self::Sapling { commit_hash, .. } => commit_hash,
+            Self::Perforce { changelist, .. } => changelist,
+            Self::PlasticScm { changeset, .. } => changeset,
+            Self::AndroidRepo {
+                manifest_revision, ..
+            } => manifest_revision,
         }
     }
 
@@ -160,176 +380,2049 @@ impl<'a> SpecificInfo<'a> {
     pub const fn tags(&self) -> Option<&[&str]> {
         match *self {
             Self::Git { extra, .. } => match extra {
-                Some(extra) => Some(extra.tags),
+                Some(extra) => extra.tags,
                 None => None,
             },
             Self::Mercurial { extra, .. } => match extra {
                 Some(extra) => Some(extra.tags),
                 None => None,
             },
+            Self::Jujutsu { extra, .. } => match extra {
+                Some(extra) => Some(extra.tags),
+                None => None,
+            },
+            Self::Pijul { .. } => None,
+            Self::Fossil { extra, .. } => match extra {
+                Some(extra) => Some(extra.tags),
+                None => None,
+            },
+            // Subversion tags are just copies in the repository, so they
+            // can't be associated with a revision.
+            Self::Subversion { .. } => None,
+            Self::Bazaar { .. } => None,
+            Self::Darcs { .. } => None,
+            Self::Sapling { .. } => None,
+            // Perforce labels can refer to individual file revisions, so they
+            // can't be associated with a changelist.
+            Self::Perforce { .. } => None,
+            Self::PlasticScm { .. } => None,
+            // each project has its own tags, so there are none for the whole client.
+            Self::AndroidRepo { .. } => None,
         }
     }
-}
 
-/// Module containing types and functionality specific to git repositories.
-pub mod git {
-    /// Contains extra data about the git repository.
-    ///
-    /// # Note
+    /// Returns the abbreviated hash of the current commit, which is unambiguous in the
+    /// repository that it was detected from.
     ///
-    /// This will only be present if the dependency is specified as a git
-    /// dependency when used in the `Cargo.toml` file. When a dependency
-    /// is downloaded from [`crates.io`](https://crates.io), the git
-    /// history and repository metadata is not downloaded, and so this
-    /// information will not be available.
-    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-    #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
-    pub struct ExtraData<'a> {
-        /// The name of the branch.
-        pub branch: &'a str,
-        /// Tags associated with the current commit.
-        pub tags: &'a [&'a str],
+    /// * Returns `None` if the extra data is not available.
+    /// * Currently, this is only recorded for git repositories.
+    #[inline]
+    #[must_use]
+    pub const fn short_commit(&self) -> Option<&str> {
+        match *self {
+            Self::Git {
+                extra: Some(extra), ..
+            } => Some(extra.short_hash),
+            _ => None,
+        }
     }
-}
-
-#[doc(inline)]
-#[deprecated]
-pub use git::ExtraData as GitExtraData;
 
-/// Module containing types and functionality specific to mercurial repositories.
-pub mod mercurial {
-    /// Contains extra data about the Mercurial repository.
-    ///
-    /// # Notes
+    /// Returns the hash algorithm which the commit hash was made with.
     ///
-    /// At the moment, this will never be available when building the dependency
-    /// from `crates.io`.
-    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-    #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
-    pub struct ExtraData<'a> {
-        /// Local revision number.
-        pub local_revision: &'a str,
-        /// The branch of the current revision.
-        pub branch: &'a str,
-        /// The list of tags for the current revision.
-        pub tags: &'a [&'a str],
-        /// The list of bookmarks for the current revision.
-        pub bookmarks: &'a [&'a str],
+    /// * Returns `None` if the version control system is not git.
+    /// * The hashes of git repositories which were created with
+    ///   `git init --object-format=sha256` are 64 digits long, rather than 40.
+    #[inline]
+    #[must_use]
+    pub const fn hash_algorithm(&self) -> Option<git::HashAlgorithm> {
+        match *self {
+            Self::Git { hash_algorithm, .. } => Some(hash_algorithm),
+            _ => None,
+        }
     }
-}
-
-#[doc(inline)]
-#[deprecated]
-pub use mercurial::ExtraData as MercurialExtraData;
 
-/// The source from which the version control information was read.
-#[non_exhaustive]
-#[derive(Clone, Copy, Eq, Hash, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
-pub enum Source {
-    /// The version control info was read directly from the repository data.
-    Repository,
-    /// The version control information was read from the `.cargo_vcs_info.json` file.
+    /// Returns the time at which the current commit was made, in seconds since the
+    /// Unix epoch.
     ///
-    /// This is only present in packages published to `crates.io`. It is also only
-    /// present in repositories which use `git`, and is unreliable, as there is no
-    /// guarantee that this value corresponds to an actual commit in the repository.
+    /// For git, this is the committer date, rather than the author date, so it
+    /// reflects when a rebased or cherry-picked commit was made. Mercurial revisions
+    /// only have one date, which is kept when they are rebased.
     ///
-    /// If the `source` is set to this value, then the version control info
-    /// should be seen as potentially unreliable.
+    /// * Returns `None` if the extra data is not available, or the timestamp was not
+    ///   recorded.
+    /// * Currently, this is only recorded for git and Mercurial repositories.
+    #[inline]
+    #[must_use]
+    pub const fn timestamp(&self) -> Option<i64> {
+        match *self {
+            Self::Git {
+                extra: Some(extra), ..
+            } => extra.commit_timestamp,
+            Self::Mercurial {
+                extra: Some(extra), ..
+            } => extra.commit_timestamp,
+            _ => None,
+        }
+    }
+
+    /// Returns whether the working tree had uncommitted changes when the info was
+    /// detected.
     ///
-    /// For more info, see [The Cargo Book][book].
+    /// Untracked files make a git working tree dirty, but not a Mercurial one. See
+    /// [`git::ExtraData::dirty`] for when this may be out of date.
     ///
-    /// [book]: https://doc.rust-lang.org/cargo/commands/cargo-package.html#cargo_vcs_infojson-format.
-    CargoVcsInfoFile,
-}
+    /// * Returns `None` if the extra data is not available, or the working tree was
+    ///   not checked.
+    /// * Currently, this is only recorded for git and Mercurial repositories.
+    #[inline]
+    #[must_use]
+    pub const fn dirty(&self) -> Option<bool> {
+        match *self {
+            Self::Git {
+                extra: Some(extra), ..
+            } => extra.dirty,
+            Self::Mercurial {
+                extra: Some(extra), ..
+            } => Some(extra.dirty),
+            _ => None,
+        }
+    }
 
-impl fmt::Debug for Source {
+    /// Returns the kind of version control system which this info was read from.
     #[inline]
-    fn fmt(&self, fmtr: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let source = match *self {
-            Self::CargoVcsInfoFile => ".cargo_vcs_info.json",
-            Self::Repository => "Repository",
+    #[must_use]
+    pub const fn kind(&self) -> VcsKind {
+        match *self {
+            Self::Git { .. } => VcsKind::Git,
+            Self::Mercurial { .. } => VcsKind::Mercurial,
+            Self::Jujutsu { .. } => VcsKind::Jujutsu,
+            Self::Pijul { .. } => VcsKind::Pijul,
+            Self::Fossil { .. } => VcsKind::Fossil,
+            Self::Subversion { .. } => VcsKind::Subversion,
+            Self::Bazaar { .. } => VcsKind::Bazaar,
+            Self::Darcs { .. } => VcsKind::Darcs,
+            Self::Sapling { .. } => VcsKind::Sapling,
+            Self::Perforce { .. } => VcsKind::Perforce,
+            Self::PlasticScm { .. } => VcsKind::PlasticScm,
+            Self::AndroidRepo { .. } => VcsKind::AndroidRepo,
+        }
+    }
+
+    // Returns the closest equivalent to a git branch for the version control system.
+    // A branch which wasn't recorded, or was redacted, is empty.
+    pub(crate) fn branch(&self) -> Option<&'a str> {
+        let branch = match *self {
+            Self::Git { extra, .. } => extra.map(|extra| extra.branch),
+            Self::Mercurial { extra, .. } => extra.map(|extra| extra.branch),
+            Self::Pijul { extra, .. } => extra.map(|extra| extra.channel),
+            Self::Fossil { extra, .. } => extra.map(|extra| extra.branch),
+            Self::Bazaar { extra, .. } => extra.map(|extra| extra.nick),
+            Self::Perforce { extra, .. } => extra.and_then(|extra| extra.stream),
+            Self::PlasticScm { extra, .. } => extra.map(|extra| extra.branch),
+            Self::Jujutsu { .. }
+            | Self::Subversion { .. }
+            | Self::Darcs { .. }
+            | Self::Sapling { .. }
+            | Self::AndroidRepo { .. } => None,
         };
-        fmtr.write_str(source)
+        branch.filter(|branch| !branch.is_empty())
     }
 }
 
-/// An error representing that no version control information was found.
+/// The kind of version control system which version control info was read from.
 #[non_exhaustive]
-pub enum Error {
-    /// There was no version control information found in the project directory.
-    NoVersionControl,
-    /// The version control information was explicitly redacted in the build script.
-    Redacted,
-    /// An uncategorised error occurred.
-    Other {
-        /// The message associated with the error.
-        reason: &'static str,
-    },
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum VcsKind {
+    /// A git repository.
+    Git,
+    /// A Mercurial repository.
+    Mercurial,
+    /// A Jujutsu repository.
+    Jujutsu,
+    /// A Pijul repository.
+    Pijul,
+    /// A Fossil checkout.
+    Fossil,
+    /// A Subversion working copy.
+    Subversion,
+    /// A Bazaar or Breezy branch.
+    Bazaar,
+    /// A Darcs repository.
+    Darcs,
+    /// A Sapling repository.
+    Sapling,
+    /// A Perforce (Helix Core) workspace.
+    Perforce,
+    /// A Plastic SCM workspace.
+    PlasticScm,
+    /// A client checkout managed by the Android `repo` tool.
+    AndroidRepo,
 }
 
-impl fmt::Debug for Error {
+impl VcsKind {
+    /// Returns a short, lowercase name for the version control system, e.g. `git`.
     #[inline]
-    fn fmt(&self, fmtr: &mut fmt::Formatter<'_>) -> fmt::Result {
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
         match *self {
-            Self::NoVersionControl => fmtr.debug_struct("NoVersionControl").finish(),
-            Self::Redacted => fmtr.debug_struct("Redacted").finish(),
-            Self::Other { ref reason } => {
-                fmtr.debug_struct("Other").field("reason", reason).finish()
-            }
+            Self::Git => "git",
+            Self::Mercurial => "mercurial",
+            Self::Jujutsu => "jj",
+            Self::Pijul => "pijul",
+            Self::Fossil => "fossil",
+            Self::Subversion => "svn",
+            Self::Bazaar => "bzr",
+            Self::Darcs => "darcs",
+            Self::Sapling => "sl",
+            Self::Perforce => "p4",
+            Self::PlasticScm => "plastic",
+            Self::AndroidRepo => "repo",
         }
     }
 }
 
-impl fmt::Display for Error {
+impl fmt::Display for VcsKind {
     #[inline]
     fn fmt(&self, fmtr: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let message = match *self {
-            Self::NoVersionControl => "version control not found",
-            Self::Redacted => "version control information is redacted",
-            Self::Other { reason } => reason,
-        };
-        fmtr.write_str(message)
+        fmtr.write_str(self.as_str())
     }
 }
 
-impl ErrorTrait for Error {}
+/// Module containing types and functionality specific to git repositories.
+pub mod git {
+    use crate::compression::Deflated;
+    #[cfg(feature = "compression")]
+    use crate::compression::InflateError;
+    #[cfg(feature = "compression")]
+    use alloc::{string::String, vec::Vec};
+    use core::fmt;
+    #[cfg(feature = "serde")]
+    use serde::{Deserialize, Serialize};
 
-/// Retrieves the version control info.
-///
-/// If the `version_control_info_build::detect()` function has not been run in a build
-/// script, this macro will fail.
-///
-/// If the build-stage vcs detection has failed, then this will result in a compile error.
-/// If you need to handle failures gracefully, use the [`try_get!()`] macro.
-///
-/// # Example
-/// 
-/// ```rust,ignore
-/// # fn main() {
-/// use version_control_info::Info;
-/// const INFO: version_control_info::Info<'_> = version_control_info::get!();
-/// println!("commit = {}", INFO.commit());
-/// # }
-/// ```
-#[macro_export]
-macro_rules! get {
-    () => {
-        include!(concat!(
-            env!("OUT_DIR"),
-            "/version_control_info_get_generated.rs"
-        ))
-    };
-}
+    /// Contains extra data about the git repository.
+    ///
+    /// # Note
+    ///
+    /// This will only be present if the dependency is specified as a git
+    /// dependency when used in the `Cargo.toml` file. When a dependency
+    /// is downloaded from [`crates.io`](https://crates.io), the git
+    /// history and repository metadata is not downloaded, and so this
+    /// information will not be available.
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(Serialize))]
+    pub struct ExtraData<'a> {
+        /// The name of the branch.
+        ///
+        /// This is empty if the branch was not recorded, which can be requested in
+        /// the build script.
+        pub branch: &'a str,
+        /// The full name of the ref which `HEAD` points to, e.g. `refs/heads/main`.
+        ///
+        /// This is `None` if `HEAD` is detached, or if the branch was not recorded.
+        pub head_ref: Option<&'a str>,
+        /// Tags associated with the current commit.
+        ///
+        /// This will be `None` if tags were not collected, e.g. when using
+        /// `version_control_info_build::detect_quick()`.
+        pub tags: Option<&'a [&'a str]>,
+        /// The commit of the superproject, if the repository is a git submodule.
+        ///
+        /// This is the commit which the superproject has checked out, rather than
+        /// the commit of the submodule which it records.
+        pub superproject_commit: Option<&'a str>,
+        /// The commit which `HEAD` was split from with `git subtree split`, taken from
+        /// its `git-subtree-split:` trailer.
+        ///
+        /// This is only recorded if it was requested in the build script, and is
+        /// `None` if `HEAD` is not a split commit.
+        pub subtree_split: Option<&'a str>,
+        /// Whether the working tree had uncommitted changes, including untracked files
+        /// which are not ignored, when the version control info was detected.
+        ///
+        /// The build script is only run again when the index or `HEAD` changes, so
+        /// editing a file without staging it will not update this flag until the
+        /// next time that the build script is run.
+        ///
+        /// This is `None` if the working tree was not checked, which can be
+        /// requested in the build script, or if the info was not read from the
+        /// repository, e.g. when the commit was provided by the environment.
+        pub dirty: Option<bool>,
+        /// The committer date of the commit, in seconds since the Unix epoch.
+        ///
+        /// This is `None` if it was not recorded, or could not be read, e.g. when the
+        /// commit was provided by the environment.
+        pub commit_timestamp: Option<i64>,
+        /// The author of the commit.
+        ///
+        /// Identities contain personal information, so this is only recorded if it
+        /// was requested in the build script.
+        pub author: Option<Identity<'a>>,
+        /// The committer of the commit.
+        ///
+        /// Identities contain personal information, so this is only recorded if it
+        /// was requested in the build script.
+        pub committer: Option<Identity<'a>>,
+        /// The trailers at the end of the commit message, such as `Change-Id` or
+        /// `Signed-off-by`, in the order in which they appear.
+        ///
+        /// Trailers may contain personal information, so this is only recorded if it
+        /// was requested in the build script. It is `None` if they were not recorded.
+        pub trailers: Option<&'a [Trailer<'a>]>,
+        /// The subject of the commit message, i.e. its first line.
+        ///
+        /// This is only recorded if it was requested in the build script.
+        pub subject: Option<&'a str>,
+        /// The hash of the tree in the index, if it differed from the tree of the
+        /// commit, i.e. if some changes had been staged but not committed.
+        ///
+        /// This is only recorded if it was requested in the build script, and is `None`
+        /// if the index had merge conflicts, as it has no tree then. Unstaged changes
+        /// are not part of the tree, see [`dirty`](Self::dirty) for those.
+        pub index_tree: Option<&'a str>,
+        /// The output of `git describe --tags --always --dirty`, e.g. `v1.2.0-3-gabc1234`.
+        ///
+        /// This will be `None` if it was not collected, e.g. when using
+        /// `version_control_info_build::detect_quick()`.
+        pub describe: Option<&'a str>,
+        /// The abbreviated commit hash, e.g. `abc1234`.
+        ///
+        /// This is resolved by git, so it is long enough to be unambiguous in the
+        /// repository, apart from when using
+        /// `version_control_info_build::detect_quick()`, which takes a prefix of the
+        /// commit hash. Its minimum length can be configured in the build script.
+        pub short_hash: &'a str,
+        /// The fetch url of the `origin` remote, or of the remote which was configured
+        /// in the build script, e.g. `https://github.com/user/repo.git`.
+        ///
+        /// Any credentials in the url are removed. This will be `None` if the
+        /// repository has no such remote, or if it was not collected, e.g. when
+        /// using `version_control_info_build::detect_quick()`.
+        pub remote_url: Option<&'a str>,
+        /// How far `HEAD` had diverged from its upstream branch.
+        ///
+        /// This is only recorded if it was requested in the build script, and is
+        /// `None` if the branch has no upstream, e.g. when `HEAD` is detached.
+        pub upstream: Option<Upstream<'a>>,
+        /// Whether the commit was signed, and whether the signature was valid.
+        ///
+        /// This is only recorded if it was requested in the build script, and is
+        /// verified with the keys which git trusted on the machine that built it.
+        pub signature: Option<SignatureStatus>,
+        /// Whether each of the [`tags`](Self::tags) was signed, and whether the
+        /// signature was valid, in the same order as the tags.
+        ///
+        /// This is only recorded if it was requested in the build script.
+        pub tag_signatures: Option<&'a [TagSignature<'a>]>,
+        /// The commit which each of the [`tags`](Self::tags) pointed to, in the same
+        /// order as the tags. Tags which don't point to a commit, such as a tag of a
+        /// tree, are left out.
+        ///
+        /// This is only recorded if it was requested in the build script. Every tag
+        /// pointed to the built commit when it was recorded, so a tag whose commit is
+        /// different in the repository has been moved since the build.
+        pub tag_targets: Option<&'a [TagTarget<'a>]>,
+        /// The nearest tag which the commit is descended from, and the number of
+        /// commits since it.
+        ///
+        /// This is parsed from `git describe --tags --long`. It will be `None` if no
+        /// tag is reachable from the commit, or if it was not collected, e.g. when
+        /// using `version_control_info_build::detect_quick()`.
+        pub nearest_tag: Option<NearestTag<'a>>,
+        /// The most recent version tag which the commit is descended from, such as
+        /// `v1.4.2`, parsed as a semantic version.
+        ///
+        /// This is only recorded if it was requested in the build script. It is `None`
+        /// if there is no version tag, or if the most recent one is not a valid
+        /// version, such as `v1.4`.
+        pub tag_version: Option<TagVersion<'a>>,
+        /// Whether the repository was a shallow clone, e.g. a `--depth 1` checkout in
+        /// CI.
+        ///
+        /// The history of a shallow clone is truncated, so the fields which are derived
+        /// from it, such as [`describe`](Self::describe),
+        /// [`nearest_tag`](Self::nearest_tag) and [`upstream`](Self::upstream), may be
+        /// missing or wrong.
+        pub shallow: bool,
+        /// The submodules of the repository, including nested submodules, in the order
+        /// of their paths.
+        ///
+        /// This is only recorded if it was requested in the build script. Submodules
+        /// which were not initialized are not included, as their sources were not
+        /// part of the build. This is `None` if they were
+        /// [`compressed`](Self::compressed).
+        pub submodules: Option<&'a [Submodule<'a>]>,
+        /// The notes which were attached to the commit with `git notes`, such as
+        /// annotations which release tooling writes for a build.
+        ///
+        /// This is only recorded if it was requested in the build script. It is
+        /// `None` if the commit has no notes, or if they were
+        /// [`compressed`](Self::compressed).
+        pub notes: Option<&'a str>,
+        /// The number of commits in the history of the commit, including itself, as
+        /// counted by `git rev-list --count HEAD`.
+        ///
+        /// This increases with every commit on a branch, so it can be used as a build
+        /// number, but it may decrease if the history is rewritten, and two branches
+        /// may have the same count. The history of a [`shallow`](Self::shallow) clone
+        /// is truncated, so its count is too low.
+        ///
+        /// This is only recorded if it was requested in the build script.
+        pub commit_count: Option<u64>,
+        /// The hash of the tree of the commit, i.e. of the contents of the repository,
+        /// as resolved by `git rev-parse HEAD^{tree}`.
+        ///
+        /// Unlike the commit hash, this doesn't depend on the message, author or
+        /// history of the commit, so two builds with the same tree were built from the
+        /// same sources, apart from any uncommitted changes, see
+        /// [`dirty`](Self::dirty).
+        ///
+        /// This is `None` if it was turned off in the build script, or if the info was
+        /// read from a snapshot which didn't record it.
+        pub tree_hash: Option<&'a str>,
+        /// The full hashes of the parents of the commit, in the order in which they
+        /// were merged, so the first parent is the branch which was merged into.
+        ///
+        /// A root commit has no parents, and a merge commit has more than one, see
+        /// [`is_merge_commit()`](Self::is_merge_commit). This is `None` if it was
+        /// turned off in the build script, or if the info was read from a snapshot
+        /// which didn't record it.
+        pub parents: Option<&'a [&'a str]>,
+        /// The full hashes of the commits in the history which have no parents, in
+        /// sorted order.
+        ///
+        /// These stay the same for every clone of the repository, whatever its remote
+        /// url, so they can be used to group builds by repository. Most repositories
+        /// have a single root commit, but merging unrelated histories adds more. The
+        /// history of a [`shallow`](Self::shallow) clone is truncated, so its root
+        /// commits are the oldest commits which were fetched instead.
+        ///
+        /// This is only recorded if it was requested in the build script.
+        pub root_commits: Option<&'a [&'a str]>,
+        /// The sparse checkout of the working tree, if only some of the files of the
+        /// repository were checked out.
+        ///
+        /// Changes can't be made to files which aren't checked out, so they don't make
+        /// the repository [`dirty`](Self::dirty). This is `None` if the working tree
+        /// is not a sparse checkout, or if it was not checked, e.g. when using
+        /// `version_control_info_build::detect_quick()`.
+        pub sparse_checkout: Option<SparseCheckout>,
+        /// The paths of the files which made the working tree [`dirty`](Self::dirty),
+        /// relative to the root of the repository and in sorted order.
+        ///
+        /// This includes untracked files which are not ignored, and both the old and
+        /// the new path of a renamed file. It is only recorded if it was requested in
+        /// the build script, and has the same caveat as the dirty flag: files which
+        /// were edited without being staged may not be listed until the next time that
+        /// the build script is run. This is `None` if they were
+        /// [`compressed`](Self::compressed).
+        pub dirty_files: Option<&'a [&'a str]>,
+        /// The tag which the commit is exactly at, such as `refs/tags/v1.2.3`.
+        ///
+        /// Unlike [`tags`](Self::tags), this is a single tag, which is chosen the same
+        /// way as `git describe` chooses one, so annotated tags are preferred over
+        /// lightweight tags. This is only recorded if it was requested in the build
+        /// script, and is `None` if no tag points at the commit.
+        pub exact_tag: Option<ExactTag<'a>>,
+        /// The large payloads which the build script compressed, to reduce the size of
+        /// the binary.
+        ///
+        /// This is `None` if compression was not enabled in the build script, or if
+        /// none of the payloads were large enough to compress.
+        pub compressed: Option<Compressed<'a>>,
+    }
 
-/// Attempt to retrieve the version control info.
+    impl<'a> ExtraData<'a> {
+        /// Returns the value of the first trailer with the given key.
+        ///
+        /// Keys are compared case-insensitively, as git does. This returns `None` if
+        /// trailers were not recorded, or if there is no such trailer.
+        #[must_use]
+        pub const fn trailer(&self, key: &str) -> Option<&'a str> {
+            let trailers = match self.trailers {
+                Some(trailers) => trailers,
+                None => return None,
+            };
+
+            let mut i = 0;
+            while i < trailers.len() {
+                if trailers[i].key.eq_ignore_ascii_case(key) {
+                    return Some(trailers[i].value);
+                }
+                i += 1;
+            }
+            None
+        }
+
+        /// Returns the values of every trailer with the given key, in the order in
+        /// which they appear, e.g. the co-authors of a commit with several
+        /// `Co-authored-by` trailers.
+        ///
+        /// Keys are compared case-insensitively, like [`trailer()`](Self::trailer).
+        /// This is empty if trailers were not recorded.
+        pub fn trailer_values<'k>(
+            &self,
+            key: &'k str,
+        ) -> impl Iterator<Item = &'a str> + use<'a, 'k> {
+            self.trailers
+                .unwrap_or_default()
+                .iter()
+                .filter(move |trailer| trailer.key.eq_ignore_ascii_case(key))
+                .map(|trailer| trailer.value)
+        }
+
+        /// Returns `true` if the commit is a merge commit, i.e. if it has more than one
+        /// parent.
+        ///
+        /// This returns `false` if the parents were not recorded.
+        #[inline]
+        #[must_use]
+        pub const fn is_merge_commit(&self) -> bool {
+            matches!(self.parents, Some(parents) if parents.len() > 1)
+        }
+
+        /// Returns the Gerrit Change-Id of the commit, from its `Change-Id` trailer.
+        ///
+        /// A Change-Id identifies a change across every patch set which is uploaded
+        /// for it, so unlike the commit hash, it stays the same when a change is
+        /// amended or rebased. Values which aren't a valid Change-Id, i.e. `I`
+        /// followed by 40 hexadecimal digits, are ignored.
+        ///
+        /// # Example
+        ///
+        /// ```rust
+        /// use version_control_info::git::{ExtraData, Trailer};
+        ///
+        /// let extra = ExtraData {
+        ///     branch: "main",
+        ///     head_ref: Some("refs/heads/main"),
+        ///     tags: None,
+        ///     superproject_commit: None,
+        ///     subtree_split: None,
+        ///     dirty: None,
+        ///     commit_timestamp: None,
+        ///     author: None,
+        ///     committer: None,
+        ///     trailers: Some(&[Trailer {
+        ///         key: "Change-Id",
+        ///         value: "I8473b95934b5732ac55d26311a706c9c2bde9940",
+        ///     }]),
+        ///     subject: None,
+        ///     index_tree: None,
+        ///     describe: None,
+        ///     short_hash: "",
+        ///     remote_url: None,
+        ///     upstream: None,
+        ///     signature: None,
+        ///     tag_signatures: None,
+        ///     tag_targets: None,
+        ///     nearest_tag: None,
+        ///     tag_version: None,
+        ///     shallow: false,
+        ///     submodules: None,
+        ///     notes: None,
+        ///     commit_count: None,
+        ///     tree_hash: None,
+        ///     parents: None,
+        ///     root_commits: None,
+        ///     sparse_checkout: None,
+        ///     dirty_files: None,
+        ///     exact_tag: None,
+        ///     compressed: None,
+        /// };
+        /// assert_eq!(extra.change_id(), Some("I8473b95934b5732ac55d26311a706c9c2bde9940"));
+        ///
+        /// let url = extra.gerrit_change_url("https://review.example.com/").unwrap();
+        /// assert_eq!(
+        ///     url.to_string(),
+        ///     "https://review.example.com/q/I8473b95934b5732ac55d26311a706c9c2bde9940",
+        /// );
+        /// ```
+        #[must_use]
+        pub const fn change_id(&self) -> Option<&'a str> {
+            match self.trailer("Change-Id") {
+                Some(change_id) if is_valid_change_id(change_id) => Some(change_id),
+                _ => None,
+            }
+        }
+
+        /// Returns a link to the change on the Gerrit server at `base_url`, if the
+        /// commit has a Change-Id.
+        ///
+        /// The link searches for the Change-Id, which Gerrit redirects to the change
+        /// itself, so the project does not need to be known.
+        #[must_use]
+        pub const fn gerrit_change_url<'b>(
+            &self,
+            base_url: &'b str,
+        ) -> Option<GerritChangeUrl<'b, 'a>> {
+            match self.change_id() {
+                Some(change_id) => Some(GerritChangeUrl {
+                    base_url,
+                    change_id,
+                }),
+                None => None,
+            }
+        }
+    }
+
+    /// The hash algorithm which a git repository names its objects with, which is
+    /// chosen with `git init --object-format`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use version_control_info::git::HashAlgorithm;
+    ///
+    /// let hash = "aac20b6e7e543e6dd4118b246c77225e3a3a1302";
+    /// assert_eq!(HashAlgorithm::from_hash(hash), Some(HashAlgorithm::Sha1));
+    /// assert_eq!(HashAlgorithm::Sha256.hex_len(), 64);
+    /// assert_eq!(HashAlgorithm::from_hash("aac20b6"), None);
+    /// ```
+    #[non_exhaustive]
+    #[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+    pub enum HashAlgorithm {
+        /// SHA-1, which git uses by default.
+        #[default]
+        Sha1,
+        /// SHA-256.
+        Sha256,
+    }
+
+    impl HashAlgorithm {
+        /// Returns the number of hex digits in a full hash, i.e. 40 for SHA-1, and 64
+        /// for SHA-256.
+        #[inline]
+        #[must_use]
+        pub const fn hex_len(self) -> usize {
+            match self {
+                Self::Sha1 => 40,
+                Self::Sha256 => 64,
+            }
+        }
+
+        /// Returns the name of the algorithm, as git names it in the
+        /// `extensions.objectFormat` config.
+        #[inline]
+        #[must_use]
+        pub const fn as_str(self) -> &'static str {
+            match self {
+                Self::Sha1 => "sha1",
+                Self::Sha256 => "sha256",
+            }
+        }
+
+        /// Returns the algorithm of a full hash, by its length, or `None` if `hash` is
+        /// not a full hash in hex.
+        #[must_use]
+        pub const fn from_hash(hash: &str) -> Option<Self> {
+            let algorithm = match hash.len() {
+                40 => Self::Sha1,
+                64 => Self::Sha256,
+                _ => return None,
+            };
+
+            let bytes = hash.as_bytes();
+            let mut i = 0;
+            while i < bytes.len() {
+                if !bytes[i].is_ascii_hexdigit() {
+                    return None;
+                }
+                i += 1;
+            }
+            Some(algorithm)
+        }
+    }
+
+    impl fmt::Display for HashAlgorithm {
+        #[inline]
+        fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+            fmt.write_str(self.as_str())
+        }
+    }
+
+    /// A trailer at the end of a commit message, such as `Change-Id: I8473b959...`.
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+    pub struct Trailer<'a> {
+        /// The key of the trailer, e.g. `Change-Id`.
+        pub key: &'a str,
+        /// The value of the trailer.
+        pub value: &'a str,
+    }
+
+    /// A link to a change on a Gerrit server, which is returned by
+    /// [`ExtraData::gerrit_change_url()`].
+    ///
+    /// This is formatted with its [`Display`](fmt::Display) implementation, so that
+    /// it can be used without allocating.
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    pub struct GerritChangeUrl<'b, 'a> {
+        base_url: &'b str,
+        change_id: &'a str,
+    }
+
+    impl GerritChangeUrl<'_, '_> {
+        /// Returns the Change-Id which the link refers to.
+        #[inline]
+        #[must_use]
+        pub const fn change_id(&self) -> &str {
+            self.change_id
+        }
+    }
+
+    impl fmt::Display for GerritChangeUrl<'_, '_> {
+        #[inline]
+        fn fmt(&self, fmtr: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let base_url = self.base_url.trim_end_matches('/');
+            write!(fmtr, "{}/q/{}", base_url, self.change_id)
+        }
+    }
+
+    const fn is_valid_change_id(change_id: &str) -> bool {
+        let bytes = change_id.as_bytes();
+        if bytes.len() != 41 || bytes[0] != b'I' {
+            return false;
+        }
+
+        let mut i = 1;
+        while i < bytes.len() {
+            if !bytes[i].is_ascii_hexdigit() {
+                return false;
+            }
+            i += 1;
+        }
+        true
+    }
+
+    /// The name and email address of the author or committer of a commit.
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+    pub struct Identity<'a> {
+        /// The name, e.g. `Jane Doe`.
+        pub name: &'a str,
+        /// The email address, e.g. `jane@example.com`.
+        pub email: &'a str,
+    }
+
+    /// The upstream branch of `HEAD`, and how far `HEAD` had diverged from it.
+    ///
+    /// The counts are as of the last time that the upstream was fetched, as the
+    /// remote is not contacted during detection.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use version_control_info::git::Upstream;
+    ///
+    /// let upstream = Upstream {
+    ///     name: "origin/main",
+    ///     remote: Some("origin"),
+    ///     ahead: 0,
+    ///     behind: 12,
+    /// };
+    /// if upstream.behind > 0 {
+    ///     println!("you are {} commits behind {}", upstream.behind, upstream.name);
+    /// }
+    /// ```
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+    pub struct Upstream<'a> {
+        /// The short name of the upstream branch, e.g. `origin/main`.
+        pub name: &'a str,
+        /// The remote which the upstream branch belongs to, e.g. `origin`, or a remote
+        /// for a fork.
+        ///
+        /// This is `None` if the upstream is another local branch, or if the info was
+        /// read from a snapshot which didn't record it.
+        pub remote: Option<&'a str>,
+        /// The number of commits in `HEAD` which are not in the upstream.
+        pub ahead: u32,
+        /// The number of commits in the upstream which are not in `HEAD`.
+        pub behind: u32,
+    }
+
+    /// The result of verifying the signature of a commit or a tag.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// use version_control_info::{SpecificInfo, git::SignatureStatus};
+    ///
+    /// let info = version_control_info::get!();
+    /// if let SpecificInfo::Git { extra: Some(extra), .. } = info.specific {
+    ///     assert_eq!(extra.signature, Some(SignatureStatus::GoodSignature));
+    /// }
+    /// ```
+    #[non_exhaustive]
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+    pub enum SignatureStatus {
+        /// There is no signature.
+        Unsigned,
+        /// The signature is valid, and was made by a trusted key.
+        GoodSignature,
+        /// The signature is invalid, or was made by a key which has expired or has
+        /// been revoked.
+        BadSignature,
+        /// The signature could not be checked, as the key is missing, or is not
+        /// trusted.
+        UnknownKey,
+    }
+
+    impl SignatureStatus {
+        /// Returns `true` if the signature is [`GoodSignature`](Self::GoodSignature).
+        #[inline]
+        #[must_use]
+        pub const fn is_good(&self) -> bool {
+            matches!(*self, Self::GoodSignature)
+        }
+    }
+
+    /// The result of verifying the signature of a tag.
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+    pub struct TagSignature<'a> {
+        /// The name of the tag, e.g. `v1.0.0`.
+        pub tag: &'a str,
+        /// The result of verifying the signature of the tag.
+        pub status: SignatureStatus,
+    }
+
+    /// A submodule of the repository, and the commit which was checked out in it.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// use version_control_info::SpecificInfo;
+    ///
+    /// let info = version_control_info::get!();
+    /// if let SpecificInfo::Git { extra: Some(extra), .. } = info.specific {
+    ///     for submodule in extra.submodules.unwrap_or_default() {
+    ///         println!("{} @ {}", submodule.path, submodule.commit);
+    ///     }
+    /// }
+    /// ```
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+    pub struct Submodule<'a> {
+        /// The path of the submodule, relative to the root of the repository, e.g.
+        /// `vendor/lib`.
+        pub path: &'a str,
+        /// The full hash of the commit which was checked out in the submodule.
+        pub commit: &'a str,
+        /// Whether the checked out commit differs from the commit which the repository
+        /// records for the submodule.
+        ///
+        /// Uncommitted changes to the files of a submodule are not included, as they
+        /// make the repository itself [`dirty`](ExtraData::dirty).
+        pub dirty: bool,
+    }
+
+    /// A working tree which only contains some of the files of its repository, as set
+    /// up by `git sparse-checkout`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// use version_control_info::SpecificInfo;
+    ///
+    /// let info = version_control_info::get!();
+    /// if let SpecificInfo::Git { extra: Some(extra), .. } = info.specific {
+    ///     if extra.sparse_checkout.is_some_and(|sparse| !sparse.crate_included) {
+    ///         println!("built from a partial checkout of the crate");
+    ///     }
+    /// }
+    /// ```
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+    pub struct SparseCheckout {
+        /// Whether the sparse checkout is in cone mode, i.e. whether it contains whole
+        /// directories, rather than the files which match a list of patterns.
+        pub cone: bool,
+        /// Whether every file in the directory of the crate was checked out.
+        ///
+        /// If some were not, then files which the crate uses, such as data files, may
+        /// have been missing from the build.
+        pub crate_included: bool,
+    }
+
+    /// The payloads of [`ExtraData`] which were compressed by the build script, as they
+    /// were at least as long as its `compression_threshold`.
+    ///
+    /// Each payload which is compressed here is `None` in the extra data itself. They
+    /// are decompressed each time that they are read, so the results should be kept
+    /// if they are read more than once.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// use version_control_info::SpecificInfo;
+    ///
+    /// let info = version_control_info::get!();
+    /// if let SpecificInfo::Git { extra: Some(extra), .. } = info.specific {
+    ///     if let Some(Ok(submodules)) = extra.compressed.and_then(|c| c.submodules()) {
+    ///         for submodule in submodules.iter() {
+    ///             println!("{} @ {}", submodule.path, submodule.commit);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+    pub struct Compressed<'a> {
+        /// The compressed [`submodules`](ExtraData::submodules).
+        #[cfg_attr(feature = "serde", serde(borrow))]
+        pub submodules: Option<Deflated<'a>>,
+        /// The compressed [`notes`](ExtraData::notes).
+        #[cfg_attr(feature = "serde", serde(borrow))]
+        pub notes: Option<Deflated<'a>>,
+        /// The compressed [`dirty_files`](ExtraData::dirty_files).
+        #[cfg_attr(feature = "serde", serde(borrow))]
+        pub dirty_files: Option<Deflated<'a>>,
+    }
+
+    #[cfg(feature = "compression")]
+    impl Compressed<'_> {
+        /// Decompresses the submodules, if they were compressed.
+        pub fn submodules(&self) -> Option<Result<InflatedSubmodules, InflateError>> {
+            let submodules = match self.submodules?.inflate_str() {
+                Ok(submodules) => InflatedSubmodules(submodules),
+                Err(e) => return Some(Err(e)),
+            };
+            let is_valid = submodules.entries().all(|entry| entry.is_some());
+            match is_valid {
+                true => Some(Ok(submodules)),
+                false => Some(Err(InflateError::new())),
+            }
+        }
+
+        /// Decompresses the notes, if they were compressed.
+        pub fn notes(&self) -> Option<Result<String, InflateError>> {
+            self.notes.map(|notes| notes.inflate_str())
+        }
+
+        /// Decompresses the paths of the dirty files, if they were compressed.
+        pub fn dirty_files(&self) -> Option<Result<Vec<String>, InflateError>> {
+            self.dirty_files.map(|dirty_files| {
+                let dirty_files = dirty_files.inflate_str()?;
+                Ok(dirty_files
+                    .split_terminator('\0')
+                    .map(String::from)
+                    .collect())
+            })
+        }
+    }
+
+    /// The submodules which were decompressed by [`Compressed::submodules()`].
+    #[cfg(feature = "compression")]
+    #[derive(Clone, Debug, Eq, Hash, PartialEq)]
+    pub struct InflatedSubmodules(String);
+
+    #[cfg(feature = "compression")]
+    impl InflatedSubmodules {
+        /// Returns an iterator over the submodules, in the order of their paths.
+        pub fn iter(&self) -> impl Iterator<Item = Submodule<'_>> {
+            self.entries().flatten()
+        }
+
+        // Each submodule is `<commit> <dirty> <path>`, where `<dirty>` is `0` or `1`, and
+        // is terminated by a nul byte, as paths may contain newlines.
+        fn entries(&self) -> impl Iterator<Item = Option<Submodule<'_>>> {
+            self.0.split_terminator('\0').map(|entry| {
+                let mut fields = entry.splitn(3, ' ');
+                let (commit, dirty, path) = (fields.next()?, fields.next()?, fields.next()?);
+                let dirty = match dirty {
+                    "0" => false,
+                    "1" => true,
+                    _ => return None,
+                };
+                Some(Submodule {
+                    path,
+                    commit,
+                    dirty,
+                })
+            })
+        }
+    }
+
+    /// The nearest tag which a commit is descended from.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use version_control_info::git::NearestTag;
+    ///
+    /// let nearest_tag = NearestTag { name: "v1.4.2", distance: 17 };
+    /// let version = match nearest_tag.distance {
+    ///     0 => nearest_tag.name.to_string(),
+    ///     distance => format!("{}+{}", nearest_tag.name, distance),
+    /// };
+    /// assert_eq!(version, "v1.4.2+17");
+    /// ```
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+    pub struct NearestTag<'a> {
+        /// The name of the tag, e.g. `v1.4.2`.
+        pub name: &'a str,
+        /// The number of commits since the tag. This is `0` if the commit is tagged.
+        pub distance: u32,
+    }
+
+    /// A version tag, such as `v1.4.2-rc.1`, parsed as a [semantic version].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use version_control_info::git::TagVersion;
+    ///
+    /// let version = TagVersion {
+    ///     tag: "v1.4.2-rc.1",
+    ///     major: 1,
+    ///     minor: 4,
+    ///     patch: 2,
+    ///     pre: Some("rc.1"),
+    ///     build: None,
+    /// };
+    /// assert!((version.major, version.minor, version.patch) >= (1, 4, 0));
+    /// assert!(version.is_prerelease());
+    /// ```
+    ///
+    /// [semantic version]: https://semver.org
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+    pub struct TagVersion<'a> {
+        /// The name of the tag, e.g. `v1.4.2-rc.1`.
+        pub tag: &'a str,
+        /// The major version.
+        pub major: u64,
+        /// The minor version.
+        pub minor: u64,
+        /// The patch version.
+        pub patch: u64,
+        /// The pre-release identifiers, e.g. `rc.1`.
+        pub pre: Option<&'a str>,
+        /// The build metadata, e.g. `build.5`. This doesn't affect the precedence of
+        /// the version.
+        pub build: Option<&'a str>,
+    }
+
+    impl TagVersion<'_> {
+        /// Returns `true` if the version is a pre-release, i.e. if it has any
+        /// pre-release identifiers.
+        #[inline]
+        #[must_use]
+        pub const fn is_prerelease(&self) -> bool {
+            self.pre.is_some()
+        }
+    }
+
+    /// The commit which a tag pointed to when it was recorded.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use version_control_info::git::TagTarget;
+    ///
+    /// let target = TagTarget {
+    ///     tag: "v1.0.0",
+    ///     commit: "5c3f8f1e4ea7d1c1b7b2f0e9a8d6c4b2a0e8f6d4",
+    /// };
+    /// // e.g. the output of `git rev-parse v1.0.0^{commit}` on a deployment server
+    /// let current = "0b9d6f4e2c0a8e6d4b2a0f8e6c4a2b0d8f6e4c2a";
+    /// if target.commit != current {
+    ///     println!("{} has been moved since it was built", target.tag);
+    /// }
+    /// ```
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+    pub struct TagTarget<'a> {
+        /// The name of the tag, e.g. `v1.0.0`.
+        pub tag: &'a str,
+        /// The full hash of the commit which the tag pointed to. Annotated tags are
+        /// peeled, so this is never the hash of the tag object.
+        pub commit: &'a str,
+    }
+
+    /// The tag which a commit is exactly at.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use version_control_info::git::ExactTag;
+    ///
+    /// let tag = ExactTag {
+    ///     ref_name: "refs/tags/v1.2.3",
+    ///     annotated: true,
+    /// };
+    /// assert_eq!(tag.name(), "v1.2.3");
+    /// // e.g. only publish builds of annotated release tags
+    /// assert!(tag.annotated && tag.name().starts_with('v'));
+    /// ```
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+    pub struct ExactTag<'a> {
+        /// The full name of the ref of the tag, e.g. `refs/tags/v1.2.3`.
+        pub ref_name: &'a str,
+        /// Whether the tag is an annotated tag, which is an object with its own
+        /// message, tagger and date, rather than a lightweight tag, which is only a
+        /// ref to the commit.
+        pub annotated: bool,
+    }
+
+    impl<'a> ExactTag<'a> {
+        /// Returns the short name of the tag, e.g. `v1.2.3`.
+        #[inline]
+        #[must_use]
+        pub fn name(&self) -> &'a str {
+            self.ref_name
+                .strip_prefix("refs/tags/")
+                .unwrap_or(self.ref_name)
+        }
+    }
+
+    /// Returns `true` if `name` is a valid full ref name, such as `refs/heads/main`.
+    ///
+    /// This follows the same rules as `git check-ref-format`, so the name must contain
+    /// at least one `/`. Use [`is_valid_branch_name()`] or [`is_valid_tag_name()`] to
+    /// check a short name, such as `main`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use version_control_info::git::is_valid_ref_name;
+    ///
+    /// assert!(is_valid_ref_name("refs/heads/feature/login"));
+    /// assert!(!is_valid_ref_name("main"));
+    /// assert!(!is_valid_ref_name("refs/heads/bad..name"));
+    /// assert!(!is_valid_ref_name("refs/tags/v1.0.lock"));
+    /// ```
+    #[must_use]
+    pub const fn is_valid_ref_name(name: &str) -> bool {
+        is_valid(name, false)
+    }
+
+    /// Returns `true` if `name` is a valid branch name, such as `main`.
+    ///
+    /// This follows the same rules as `git check-ref-format --branch`.
+    #[must_use]
+    pub const fn is_valid_branch_name(name: &str) -> bool {
+        let bytes = name.as_bytes();
+        let is_head = bytes.len() == 4
+            && bytes[0] == b'H'
+            && bytes[1] == b'E'
+            && bytes[2] == b'A'
+            && bytes[3] == b'D';
+        !is_head && !starts_with_dash(bytes) && is_valid(name, true)
+    }
+
+    /// Returns `true` if `name` is a valid tag name, such as `v1.0.0`.
+    ///
+    /// This follows the same rules which `git tag` uses.
+    #[must_use]
+    pub const fn is_valid_tag_name(name: &str) -> bool {
+        !starts_with_dash(name.as_bytes()) && is_valid(name, true)
+    }
+
+    #[inline]
+    const fn starts_with_dash(bytes: &[u8]) -> bool {
+        !bytes.is_empty() && bytes[0] == b'-'
+    }
+
+    // Checks the rules listed in the `git check-ref-format` documentation. A short name
+    // is checked as if it was prefixed with `refs/heads/` or `refs/tags/`, so it doesn't
+    // need a `/`, and may be `@`.
+    const fn is_valid(name: &str, is_short: bool) -> bool {
+        let bytes = name.as_bytes();
+        if bytes.is_empty() || bytes[bytes.len() - 1] == b'.' {
+            return false;
+        }
+        if !is_short && bytes.len() == 1 && bytes[0] == b'@' {
+            return false;
+        }
+
+        let mut has_slash = false;
+        let mut component_start = 0;
+        let mut i = 0;
+        while i <= bytes.len() {
+            // the end of the name also ends the last component
+            let byte = if i < bytes.len() { bytes[i] } else { b'/' };
+            let prev = if i > 0 { bytes[i - 1] } else { 0 };
+            match byte {
+                b'/' => {
+                    // components can't be empty, start with `.`, or end with `.lock`
+                    if component_start == i
+                        || bytes[component_start] == b'.'
+                        || ends_with_lock(bytes, component_start, i)
+                    {
+                        return false;
+                    }
+                    has_slash |= i < bytes.len();
+                    component_start = i + 1;
+                }
+                b'.' if prev == b'.' => return false,
+                b'{' if prev == b'@' => return false,
+                0x00..=0x20 | 0x7f | b'~' | b'^' | b':' | b'?' | b'*' | b'[' | b'\\' => {
+                    return false;
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+
+        is_short || has_slash
+    }
+
+    const fn ends_with_lock(bytes: &[u8], start: usize, end: usize) -> bool {
+        let suffix = b".lock";
+        if end - start < suffix.len() {
+            return false;
+        }
+
+        let mut i = 0;
+        while i < suffix.len() {
+            if bytes[end - suffix.len() + i] != suffix[i] {
+                return false;
+            }
+            i += 1;
+        }
+        true
+    }
+}
+
+#[doc(inline)]
+#[deprecated]
+pub use git::ExtraData as GitExtraData;
+
+/// Module containing types and functionality specific to mercurial repositories.
+pub mod mercurial {
+    use core::fmt;
+    #[cfg(feature = "serde")]
+    use serde::{Deserialize, Serialize};
+
+    /// Contains extra data about the Mercurial repository.
+    ///
+    /// # Notes
+    ///
+    /// At the moment, this will never be available when building the dependency
+    /// from `crates.io`.
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(Serialize))]
+    pub struct ExtraData<'a> {
+        /// Local revision number.
+        pub local_revision: &'a str,
+        /// The branch of the current revision.
+        pub branch: &'a str,
+        /// The list of tags for the current revision.
+        pub tags: &'a [&'a str],
+        /// The list of bookmarks for the current revision.
+        pub bookmarks: &'a [&'a str],
+        /// Whether the working directory had uncommitted changes when the version
+        /// control info was detected, as shown by the `+` suffix of `hg identify`.
+        ///
+        /// Unlike [`git::ExtraData::dirty`](crate::git::ExtraData::dirty), files which
+        /// are not tracked do not make the working directory dirty.
+        pub dirty: bool,
+        /// The phase of the current revision.
+        ///
+        /// A build of a [`Draft`](Phase::Draft) or [`Secret`](Phase::Secret)
+        /// revision can't be reproduced from the public repository, as the revision
+        /// may not have been pushed, and may still be rewritten. This is `None` if the
+        /// info was read from a snapshot which didn't record it.
+        pub phase: Option<Phase>,
+        /// The active bookmark, which moves forward when a new revision is committed,
+        /// as shown by `hg bookmarks --active`.
+        ///
+        /// This is one of the [`bookmarks`](Self::bookmarks) of the current revision.
+        /// It is `None` if no bookmark is active.
+        pub active_bookmark: Option<&'a str>,
+        /// The date of the revision, in seconds since the Unix epoch.
+        ///
+        /// This is `None` if the info was read from a snapshot which didn't record
+        /// it.
+        pub commit_timestamp: Option<i64>,
+        /// The author of the revision.
+        ///
+        /// Identities contain personal information, so this is only recorded if it
+        /// was requested in the build script.
+        pub author: Option<crate::git::Identity<'a>>,
+        /// The topic of the current revision, when the `topic` extension of the
+        /// evolve workflow is enabled.
+        ///
+        /// Topics are lightweight branches for drafts, which are used instead of
+        /// named branches, so the [`branch`](Self::branch) is usually `default`
+        /// when there is a topic. This is `None` if the revision has no topic, or if
+        /// the extension is not enabled.
+        pub topic: Option<&'a str>,
+    }
+
+    /// The phase of a Mercurial revision, which tracks whether it has been shared.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use version_control_info::mercurial::Phase;
+    ///
+    /// let phase = Phase::Draft;
+    /// if !phase.is_public() {
+    ///     println!("warning: this build is of an unpublished {} revision", phase);
+    /// }
+    /// ```
+    #[non_exhaustive]
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+    pub enum Phase {
+        /// The revision has been pushed or pulled, so it can't be rewritten.
+        Public,
+        /// The revision has only been committed locally.
+        Draft,
+        /// The revision is local, and is never shared with other repositories.
+        Secret,
+    }
+
+    impl Phase {
+        /// Returns `true` if the revision is [`Public`](Self::Public).
+        #[inline]
+        #[must_use]
+        pub const fn is_public(self) -> bool {
+            matches!(self, Self::Public)
+        }
+
+        /// Returns the name of the phase, as printed by `hg phase`, e.g. `draft`.
+        #[inline]
+        #[must_use]
+        pub const fn as_str(self) -> &'static str {
+            match self {
+                Self::Public => "public",
+                Self::Draft => "draft",
+                Self::Secret => "secret",
+            }
+        }
+    }
+
+    impl fmt::Display for Phase {
+        #[inline]
+        fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+            fmt.write_str(self.as_str())
+        }
+    }
+}
+
+#[doc(inline)]
+#[deprecated]
+pub use mercurial::ExtraData as MercurialExtraData;
+
+/// Module containing types and functionality specific to Jujutsu repositories.
+pub mod jujutsu {
+    #[cfg(feature = "serde")]
+    use serde::Serialize;
+
+    /// Contains extra data about the Jujutsu repository.
+    ///
+    /// # Notes
+    ///
+    /// The working copy commit (`@`) is recorded, so the commit id will usually
+    /// differ from the `HEAD` commit of a colocated git repository.
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(Serialize))]
+    pub struct ExtraData<'a> {
+        /// The local bookmarks which point at the working copy commit.
+        pub bookmarks: &'a [&'a str],
+        /// The tags which point at the working copy commit.
+        pub tags: &'a [&'a str],
+    }
+}
+
+/// Module containing types and functionality specific to Pijul repositories.
+pub mod pijul {
+    #[cfg(feature = "serde")]
+    use serde::{Deserialize, Serialize};
+
+    /// Contains extra data about the Pijul repository.
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+    pub struct ExtraData<'a> {
+        /// The name of the current channel.
+        pub channel: &'a str,
+        /// The hash of the most recent change applied to the channel.
+        pub change: &'a str,
+    }
+}
+
+/// Module containing types and functionality specific to Fossil checkouts.
+pub mod fossil {
+    #[cfg(feature = "serde")]
+    use serde::Serialize;
+
+    /// Contains extra data about the Fossil checkout.
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(Serialize))]
+    pub struct ExtraData<'a> {
+        /// The branch of the checked out check-in.
+        pub branch: &'a str,
+        /// The tags of the checked out check-in.
+        ///
+        /// Fossil records branches as tags, so this will usually contain the branch name.
+        pub tags: &'a [&'a str],
+    }
+}
+
+/// Module containing types and functionality specific to Subversion working copies.
+pub mod subversion {
+    #[cfg(feature = "serde")]
+    use serde::{Deserialize, Serialize};
+
+    /// Contains extra data about the Subversion working copy.
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+    pub struct ExtraData<'a> {
+        /// The URL of the working copy in the repository.
+        pub url: &'a str,
+    }
+}
+
+/// Module containing types and functionality specific to Bazaar branches.
+pub mod bazaar {
+    #[cfg(feature = "serde")]
+    use serde::{Deserialize, Serialize};
+
+    /// Contains extra data about the Bazaar branch.
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+    pub struct ExtraData<'a> {
+        /// The revision number of the tree in the branch.
+        ///
+        /// Unlike the revision id, this is only meaningful in the context of the branch.
+        pub revno: &'a str,
+        /// The nickname of the branch.
+        pub nick: &'a str,
+    }
+}
+
+/// Module containing types and functionality specific to Darcs repositories.
+pub mod darcs {
+    #[cfg(feature = "serde")]
+    use serde::{Deserialize, Serialize};
+
+    /// Contains extra data about the Darcs repository.
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+    pub struct ExtraData<'a> {
+        /// The name of the repository, which is the name of its root directory.
+        pub repository_name: &'a str,
+    }
+}
+
+/// Module containing types and functionality specific to Sapling repositories.
+pub mod sapling {
+    #[cfg(feature = "serde")]
+    use serde::Serialize;
+
+    /// Contains extra data about the Sapling repository.
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(Serialize))]
+    pub struct ExtraData<'a> {
+        /// The bookmarks which point at the working copy parent commit.
+        pub bookmarks: &'a [&'a str],
+    }
+}
+
+/// Module containing types and functionality specific to Perforce workspaces.
+pub mod perforce {
+    #[cfg(feature = "serde")]
+    use serde::{Deserialize, Serialize};
+
+    /// Contains extra data about the Perforce workspace.
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+    pub struct ExtraData<'a> {
+        /// The name of the client workspace.
+        pub client: &'a str,
+        /// The stream of the client workspace.
+        ///
+        /// This will be `None` if the workspace is not a stream workspace.
+        pub stream: Option<&'a str>,
+    }
+}
+
+/// Module containing types and functionality specific to Plastic SCM workspaces.
+pub mod plastic {
+    #[cfg(feature = "serde")]
+    use serde::{Deserialize, Serialize};
+
+    /// Contains extra data about the Plastic SCM workspace.
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+    pub struct ExtraData<'a> {
+        /// The full name of the branch of the changeset, e.g. `/main/task001`.
+        pub branch: &'a str,
+        /// The name of the repository which the workspace is connected to.
+        pub repository: &'a str,
+    }
+}
+
+/// Module containing types and functionality specific to clients of the Android `repo` tool.
+pub mod android_repo {
+    #[cfg(feature = "serde")]
+    use serde::{Deserialize, Serialize};
+
+    /// Contains extra data about the `repo` client.
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(Serialize))]
+    pub struct ExtraData<'a> {
+        /// Every project in the manifest, in the order in which they are listed.
+        pub projects: &'a [Project<'a>],
+    }
+
+    /// A git repository which is checked out as part of a `repo` client.
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+    pub struct Project<'a> {
+        /// The name of the project on its remote, e.g. `platform/build`.
+        pub name: &'a str,
+        /// The path of the project, relative to the root of the client, e.g. `build/make`.
+        pub path: &'a str,
+        /// The commit which is checked out in the project.
+        pub commit: &'a str,
+    }
+}
+
+/// The source from which the version control information was read.
+#[non_exhaustive]
+#[derive(Clone, Copy, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum Source {
+    /// The version control info was read directly from the repository data.
+    Repository,
+    /// The version control information was read from the `.cargo_vcs_info.json` file.
+    ///
+    /// This is only present in packages published to `crates.io`. It is also only
+    /// present in repositories which use `git`, and is unreliable, as there is no
+    /// guarantee that this value corresponds to an actual commit in the repository.
+    ///
+    /// If the `source` is set to this value, then the version control info
+    /// should be seen as potentially unreliable.
+    ///
+    /// For more info, see [The Cargo Book][book].
+    ///
+    /// [book]: https://doc.rust-lang.org/cargo/commands/cargo-package.html#cargo_vcs_infojson-format.
+    CargoVcsInfoFile,
+    /// The version control information was provided by a lookup function for
+    /// vendored crates in the build script, e.g. from a manifest of vendored sources.
+    ///
+    /// This is only as reliable as the manifest which it was looked up from.
+    VendorManifest,
+    /// The version control information was read from a `.git-commit-id` file, in an
+    /// archive created with `git archive`.
+    ///
+    /// The file is committed with the `export-subst` attribute and the contents
+    /// `$Format:%H$`, which `git archive` replaces with the hash of the archived commit.
+    /// Only the commit hash is available from an archive.
+    GitArchive,
+    /// The version control information was read from a `.vcs_info_snapshot.json` file,
+    /// which was generated from the repository and committed by the maintainers.
+    ///
+    /// The snapshot is used when there is no version control metadata, e.g. when
+    /// building from a tarball. It records the commit which it was generated from, so
+    /// it may be older than the sources which are being built.
+    SnapshotFile,
+    /// The version control information was provided by the `VERSION_CONTROL_INFO_COMMIT`
+    /// environment variable when the crate was built.
+    ///
+    /// This is used by hermetic builds which don't have the repository metadata, such as
+    /// CI builds which strip the `.git` directory. Only git commits can be provided, and
+    /// the info is only as reliable as the build environment which set it.
+    Environment,
+    /// The version control information was read from the environment of a GitHub Actions
+    /// workflow, as there was no repository metadata.
+    ///
+    /// The commit is the one which the workflow ran for, which for a pull request is the
+    /// merge of the pull request into its base branch. The branch is the head branch of
+    /// the pull request in that case.
+    GitHubActions,
+    /// The version control information was read from the predefined variables of a
+    /// GitLab CI job, as there was no repository metadata.
+    ///
+    /// The branch is the source branch for merge request pipelines. Tag pipelines have
+    /// the tag, rather than a branch.
+    GitLabCi,
+    /// The version control information was read from the environment of a job in
+    /// another CI service, such as Jenkins or CircleCI, as there was no repository
+    /// metadata.
+    ///
+    /// This is also used by the CI providers which are registered in the build script.
+    Ci,
+    /// No version control information was found, and the build script embedded
+    /// placeholder information instead of failing.
+    ///
+    /// The placeholder is a git commit whose hash is git's null hash, of all zeroes,
+    /// with no other information.
+    Unknown,
+}
+
+impl fmt::Debug for Source {
+    #[inline]
+    fn fmt(&self, fmtr: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let source = match *self {
+            Self::CargoVcsInfoFile => ".cargo_vcs_info.json",
+            Self::Repository => "Repository",
+            Self::VendorManifest => "VendorManifest",
+            Self::GitArchive => ".git-commit-id",
+            Self::SnapshotFile => ".vcs_info_snapshot.json",
+            Self::Environment => "Environment",
+            Self::GitHubActions => "GitHub Actions",
+            Self::GitLabCi => "GitLab CI",
+            Self::Ci => "CI",
+            Self::Unknown => "Unknown",
+        };
+        fmtr.write_str(source)
+    }
+}
+
+impl Source {
+    // The name of the variant, which is used in the checksum and in the JSON output,
+    // and must match the name which the build crate uses.
+    #[inline]
+    pub(crate) const fn name(&self) -> &'static str {
+        match *self {
+            Self::Repository => "Repository",
+            Self::CargoVcsInfoFile => "CargoVcsInfoFile",
+            Self::VendorManifest => "VendorManifest",
+            Self::GitArchive => "GitArchive",
+            Self::SnapshotFile => "SnapshotFile",
+            Self::Environment => "Environment",
+            Self::GitHubActions => "GitHubActions",
+            Self::GitLabCi => "GitLabCi",
+            Self::Ci => "Ci",
+            Self::Unknown => "Unknown",
+        }
+    }
+}
+
+/// Where the sources of a crate were obtained from when it was built.
+///
+/// This is useful for telling whether a dependency was built from a `[patch]`
+/// or path override, rather than from the sources which were published.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum Origin {
+    /// The crate was downloaded from a registry, such as `crates.io`.
+    Registry,
+    /// The crate was checked out from a git repository by `cargo`, as a `git`
+    /// dependency.
+    Git,
+    /// The crate was built from a local path.
+    ///
+    /// This is the case for workspace members and `path` dependencies.
+    Path,
+    /// The crate was vendored into a local directory with `cargo vendor`, and is
+    /// used through source replacement.
+    ///
+    /// The sources are expected to match those published to the registry, but the
+    /// `.cargo_vcs_info.json` file of a vendored crate cannot be checked against the
+    /// registry, so a [`Source::CargoVcsInfoFile`] should be treated as unreliable.
+    Vendored,
+    /// The crate replaced a registry or `git` dependency through a `[patch]` section,
+    /// with either a local path or another `git` repository.
+    Patched,
+}
+
+impl Origin {
+    /// Returns `true` if the crate was not built from a registry, or from sources
+    /// vendored from a registry.
+    #[inline]
+    #[must_use]
+    pub const fn is_overridden(&self) -> bool {
+        !matches!(*self, Self::Registry | Self::Vendored)
+    }
+
+    #[inline]
+    pub(crate) const fn name(&self) -> &'static str {
+        match *self {
+            Self::Registry => "Registry",
+            Self::Git => "Git",
+            Self::Path => "Path",
+            Self::Vendored => "Vendored",
+            Self::Patched => "Patched",
+        }
+    }
+}
+
+/// The service which hosts the upstream repository of a crate.
+///
+/// This is classified from the url of the `origin` remote of a git repository, or
+/// from the `repository` key of the package manifest.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum RepoHost {
+    /// The repository is hosted on GitHub, or a GitHub Enterprise server.
+    GitHub,
+    /// The repository is hosted on GitLab, or a self-managed GitLab server.
+    GitLab,
+    /// The repository is hosted on Bitbucket.
+    Bitbucket,
+    /// The repository is hosted on a Gitea or Forgejo server, such as Codeberg.
+    Gitea,
+    /// The repository is hosted on Azure DevOps.
+    AzureDevOps,
+    /// The repository is hosted on a service which could not be identified.
+    Other,
+}
+
+impl RepoHost {
+    #[inline]
+    pub(crate) const fn name(&self) -> &'static str {
+        match *self {
+            Self::GitHub => "GitHub",
+            Self::GitLab => "GitLab",
+            Self::Bitbucket => "Bitbucket",
+            Self::Gitea => "Gitea",
+            Self::AzureDevOps => "AzureDevOps",
+            Self::Other => "Other",
+        }
+    }
+}
+
+/// The release channel of a build, such as stable or nightly.
+///
+/// The channel is classified in the build script, by rules which match the branch
+/// and the tags of the commit, e.g. a tag matching `v*` for [`Stable`](Self::Stable)
+/// and a branch matching `beta/*` for [`Beta`](Self::Beta). The first rule which
+/// matches decides the channel, and a build which no rule matches is
+/// [`Dev`](Self::Dev).
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use version_control_info::Channel;
+///
+/// let info = version_control_info::get!();
+/// let experimental = matches!(info.channel, Some(Channel::Nightly | Channel::Dev));
+/// ```
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum Channel {
+    /// A release build.
+    Stable,
+    /// A release candidate, or another build which is being tested before a release.
+    Beta,
+    /// A regular build of the development branch.
+    Nightly,
+    /// Any other build.
+    Dev,
+}
+
+impl Channel {
+    /// Returns the name of the channel in lowercase, e.g. `"stable"`.
+    #[inline]
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match *self {
+            Self::Stable => "stable",
+            Self::Beta => "beta",
+            Self::Nightly => "nightly",
+            Self::Dev => "dev",
+        }
+    }
+}
+
+/// Options which control how version control info is displayed to people.
+///
+/// This is used by the human-readable renderers in this crate, such as the
+/// [`console`] renderer.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct DisplayOptions<'a> {
+    /// The labels which are displayed alongside the values.
+    pub labels: Labels<'a>,
+}
+
+impl DisplayOptions<'_> {
+    /// Creates a new `DisplayOptions`, which uses the [`Labels::ENGLISH`] labels.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            labels: Labels::ENGLISH,
+        }
+    }
+}
+
+/// The text displayed by the human-readable renderers.
+///
+/// These can be translated to display version control info in other languages.
+/// Names of version control systems and files are never translated.
+///
+/// # Example
+///
+/// ```rust
+/// use version_control_info::{DisplayOptions, Labels};
+///
+/// let options = DisplayOptions {
+///     labels: Labels {
+///         branch: "Zweig",
+///         tags: "Markierungen",
+///         ..Labels::ENGLISH
+///     },
+/// };
+/// # let _ = options;
+/// ```
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Labels<'a> {
+    /// The label for the commit.
+    pub commit: &'a str,
+    /// The label for the branch, or the closest equivalent.
+    pub branch: &'a str,
+    /// The label for the list of tags.
+    pub tags: &'a str,
+    /// Displayed when the working tree had uncommitted changes.
+    pub dirty: &'a str,
+    /// The label for the [`Source`] of the info.
+    pub source: &'a str,
+    /// The label for the [`Origin`] of the crate.
+    pub origin: &'a str,
+    /// Displayed for [`Source::Repository`].
+    pub repository: &'a str,
+    /// Displayed for [`Origin::Registry`].
+    pub registry: &'a str,
+    /// Displayed for [`Origin::Path`].
+    pub path: &'a str,
+    /// Displayed for [`Source::VendorManifest`].
+    pub vendor_manifest: &'a str,
+    /// Displayed for [`Origin::Vendored`].
+    pub vendored: &'a str,
+    /// Displayed for [`Origin::Patched`].
+    pub patched: &'a str,
+    /// Displayed for [`Source::Environment`].
+    pub environment: &'a str,
+    /// Displayed for [`Source::Unknown`].
+    pub unknown: &'a str,
+}
+
+impl Labels<'_> {
+    /// The default, English, labels.
+    pub const ENGLISH: Self = Self {
+        commit: "commit",
+        branch: "branch",
+        tags: "tags",
+        dirty: "dirty",
+        source: "source",
+        origin: "origin",
+        repository: "repository",
+        registry: "registry",
+        path: "path",
+        vendor_manifest: "vendor manifest",
+        vendored: "vendored",
+        patched: "patched",
+        environment: "environment",
+        unknown: "unknown",
+    };
+}
+
+impl Default for Labels<'_> {
+    #[inline]
+    fn default() -> Self {
+        Self::ENGLISH
+    }
+}
+
+/// An error representing that no version control information was found.
+#[non_exhaustive]
+pub enum Error {
+    /// There was no version control information found in the project directory.
+    NoVersionControl,
+    /// A repository was found in the project directory, but it has no commits yet.
+    EmptyRepository,
+    /// The version control information was explicitly redacted in the build script.
+    Redacted,
+    /// An uncategorised error occurred.
+    Other {
+        /// The message associated with the error.
+        reason: &'static str,
+    },
+}
+
+impl Error {
+    /// Returns the category of the error.
+    ///
+    /// Unlike `Error`, [`ErrorKind`] is exhaustive, so it can be matched on without a
+    /// wildcard arm. Variants which are added to `Error` are categorised into one of
+    /// the existing kinds.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use version_control_info::{Error, ErrorKind};
+    ///
+    /// fn describe(error: &Error) -> &'static str {
+    ///     match error.kind() {
+    ///         ErrorKind::Unavailable => "built outside of a repository",
+    ///         ErrorKind::Redacted => "hidden",
+    ///         ErrorKind::Other => "unknown",
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(describe(&Error::EmptyRepository), "built outside of a repository");
+    /// ```
+    #[inline]
+    #[must_use]
+    pub const fn kind(&self) -> ErrorKind {
+        match *self {
+            Self::NoVersionControl | Self::EmptyRepository => ErrorKind::Unavailable,
+            Self::Redacted => ErrorKind::Redacted,
+            Self::Other { .. } => ErrorKind::Other,
+        }
+    }
+}
+
+impl fmt::Debug for Error {
+    #[inline]
+    fn fmt(&self, fmtr: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::NoVersionControl => fmtr.debug_struct("NoVersionControl").finish(),
+            Self::EmptyRepository => fmtr.debug_struct("EmptyRepository").finish(),
+            Self::Redacted => fmtr.debug_struct("Redacted").finish(),
+            Self::Other { ref reason } => {
+                fmtr.debug_struct("Other").field("reason", reason).finish()
+            }
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    #[inline]
+    fn fmt(&self, fmtr: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match *self {
+            Self::NoVersionControl => "version control not found",
+            Self::EmptyRepository => "the repository has no commits",
+            Self::Redacted => "version control information is redacted",
+            Self::Other { reason } => reason,
+        };
+        fmtr.write_str(message)
+    }
+}
+
+impl ErrorTrait for Error {}
+
+/// The category of an [`Error`], as returned by [`Error::kind()`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum ErrorKind {
+    /// There is no commit to describe, as the crate was not built from a repository,
+    /// or the repository had no commits.
+    Unavailable,
+    /// The version control information was explicitly redacted in the build script.
+    Redacted,
+    /// Any other error, such as one from a custom detector.
+    Other,
+}
+
+#[doc(hidden)]
+pub mod __private {
+    use crate::{Error, Info};
+    use core::fmt;
+
+    /// Creates an `Error::Other` from the arguments of the `vcs_error!()` macro.
+    ///
+    /// Without the `alloc` feature, the message can't be formatted, so the format
+    /// string is used as the reason instead.
+    #[cold]
+    #[must_use]
+    pub fn other_error(
+        args: fmt::Arguments<'_>,
+        #[cfg_attr(feature = "alloc", allow(unused_variables))] format_string: &'static str,
+    ) -> Error {
+        if let Some(reason) = args.as_str() {
+            return Error::Other { reason };
+        }
+
+        #[cfg(feature = "alloc")]
+        let reason = alloc::fmt::format(args).leak();
+        #[cfg(not(feature = "alloc"))]
+        let reason = format_string;
+
+        Error::Other { reason }
+    }
+
+    /// Panics with the reason that no version control info was detected.
+    ///
+    /// This is used by `get!()` when the build script was configured to fail at runtime,
+    /// and returns `Info` so that the code which uses it isn't unreachable.
+    #[cold]
+    #[track_caller]
+    pub const fn missing_info(reason: &'static str) -> Info<'static> {
+        panic!("{}", reason)
+    }
+
+    /// Returns the sub-string of `s` between the byte offsets `start` and `end`.
+    ///
+    /// This is used by generated code to slice up interned strings at compile time.
+    #[inline]
+    #[must_use]
+    pub const fn substr(s: &str, start: usize, end: usize) -> &str {
+        let (_, rest) = s.split_at(start);
+        let (sub, _) = rest.split_at(end - start);
+        sub
+    }
+
+    /// A `const` version of `str::starts_with`.
+    #[inline]
+    #[must_use]
+    pub const fn starts_with(s: &str, prefix: &str) -> bool {
+        let (s, prefix) = (s.as_bytes(), prefix.as_bytes());
+        if prefix.len() > s.len() {
+            return false;
+        }
+
+        let mut i = 0;
+        while i < prefix.len() {
+            if s[i] != prefix[i] {
+                return false;
+            }
+            i += 1;
+        }
+        true
+    }
+}
+
+/// Retrieves the version control info.
+///
+/// If the `version_control_info_build::detect()` function has not been run in a build
+/// script, this macro will fail.
+///
+/// If the build-stage vcs detection has failed, then this will result in a compile error,
+/// unless the build script was configured to panic when this is evaluated, or to embed
+/// placeholder info instead. If you need to handle failures gracefully, use the
+/// [`try_get!()`] macro.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// # fn main() {
+/// use version_control_info::Info;
+/// const INFO: version_control_info::Info<'_> = version_control_info::get!();
+/// println!("commit = {}", INFO.commit());
+/// # }
+/// ```
+#[macro_export]
+macro_rules! get {
+    () => {
+        include!(concat!(
+            env!("OUT_DIR"),
+            "/version_control_info_get_generated.rs"
+        ))
+    };
+}
+
+/// Attempt to retrieve the version control info.
 ///
 /// If the `version_control_info_build::detect()` function has not been run in a build
 /// script, this macro will fail.
 ///
 /// # Example
-/// 
+///
 /// ```rust,ignore
 /// # fn main() {
 /// use version_control_info::{Info, Error};
@@ -349,3 +2442,242 @@ macro_rules! try_get {
         ))
     };
 }
+
+/// Retrieves the version control info of every version control system which was
+/// detected, as a `&'static [Info<'static>]`.
+///
+/// Some repositories are managed by several version control systems at once, e.g. a
+/// jj repository which is colocated with git. The first element is the same info as
+/// the [`get!()`] macro returns, and is followed by the info of any colocated systems,
+/// in order of precedence. Colocated systems are only detected if the
+/// `record_colocated` option was set in the build script.
+///
+/// Unlike [`get!()`], this macro does not fail if no version control info could be
+/// detected. The slice is empty instead.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// # fn main() {
+/// for info in version_control_info::get_all!() {
+///     println!("{} commit = {}", info.specific.kind(), info.commit());
+/// }
+/// # }
+/// ```
+#[macro_export]
+macro_rules! get_all {
+    () => {
+        include!(concat!(
+            env!("OUT_DIR"),
+            "/version_control_info_get_all_generated.rs"
+        ))
+    };
+}
+
+/// Retrieves the version control info as a JSON string.
+///
+/// This is only available if the `json` option was enabled when the version control
+/// info was generated in the build script, which writes the JSON sidecar file that
+/// this macro includes. The string is the same as the output of [`Info::to_json()`],
+/// byte-for-byte.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// # fn main() {
+/// const JSON: &str = version_control_info::get_json!();
+/// assert_eq!(JSON, version_control_info::get!().to_json());
+/// # }
+/// ```
+#[macro_export]
+macro_rules! get_json {
+    () => {
+        include_str!(concat!(env!("OUT_DIR"), "/version_control_info.json"))
+    };
+}
+
+/// Creates a [`BuildReport`] for the version control info, with the package and
+/// target which the calling crate is being built as.
+///
+/// The target and build time are set by the build script, and are `None` unless it
+/// generated the version control info with `GenerateOptions::report()` turned on.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// # fn main() {
+/// let report = version_control_info::build_report!();
+/// assert_eq!(report.package_name, env!("CARGO_PKG_NAME"));
+/// # }
+/// ```
+///
+/// [`BuildReport`]: crate::report::BuildReport
+#[cfg(feature = "report")]
+#[macro_export]
+macro_rules! build_report {
+    () => {{
+        let mut report = $crate::report::BuildReport::new(
+            $crate::get!(),
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION"),
+        );
+        report.target = option_env!("VERSION_CONTROL_INFO_TARGET");
+        report.built_at =
+            option_env!("VERSION_CONTROL_INFO_BUILD_TIMESTAMP").and_then(|time| time.parse().ok());
+        report
+    }};
+}
+
+/// Retrieves the checksum of the version control info, as an `Option<u64>`.
+///
+/// This is `None` unless the `integrity_checksum` option was enabled when the version
+/// control info was generated in the build script. See the [`integrity`] module for
+/// more details.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// # fn main() {
+/// const CHECKSUM: Option<u64> = version_control_info::integrity_checksum!();
+/// if let Some(checksum) = CHECKSUM {
+///     println!("checksum = {:016x}", checksum);
+/// }
+/// # }
+/// ```
+#[macro_export]
+macro_rules! integrity_checksum {
+    () => {
+        include!(concat!(
+            env!("OUT_DIR"),
+            "/version_control_info_checksum_generated.rs"
+        ))
+    };
+}
+
+/// Retrieves the [`SourceMap`] of the crate, as an `Option<SourceMap<'static>>`.
+///
+/// This is `None` unless the `source_map` option was enabled when the version control
+/// info was generated in the build script, and the crate was built with a dev profile.
+/// See the [`source_map`](mod@source_map) module for more details.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// # fn main() {
+/// use version_control_info::source_map::SourceMap;
+///
+/// const SOURCE_MAP: Option<SourceMap<'_>> = version_control_info::source_map!();
+/// if let Some(source_map) = SOURCE_MAP {
+///     println!("this crate is at {:?} in its repository", source_map.crate_path);
+/// }
+/// # }
+/// ```
+///
+/// [`SourceMap`]: crate::source_map::SourceMap
+#[macro_export]
+macro_rules! source_map {
+    () => {
+        include!(concat!(
+            env!("OUT_DIR"),
+            "/version_control_info_source_map_generated.rs"
+        ))
+    };
+}
+
+/// Asserts in debug builds that the embedded version control info matches its checksum.
+///
+/// If the version control info could not be detected, or no checksum was generated,
+/// then there is nothing to check, and the assertion always passes. See the
+/// [`integrity`] module for more details.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// fn main() {
+///     version_control_info::debug_assert_integrity!();
+/// }
+/// ```
+#[macro_export]
+macro_rules! debug_assert_integrity {
+    () => {
+        if let ::core::result::Result::Ok(info) = $crate::try_get!() {
+            ::core::debug_assert!(
+                $crate::integrity::verify(&info, $crate::integrity_checksum!()),
+                "the embedded version control info does not match its checksum"
+            );
+        }
+    };
+}
+
+/// Creates an [`Error::Other`] with a formatted reason.
+///
+/// This takes the same arguments as [`format!()`], so that custom detectors and
+/// providers can return informative errors.
+///
+/// The reason of an `Error::Other` is a `&'static str`, so with the `alloc` feature, the
+/// formatted message is allocated and leaked. This is fine for errors which are only
+/// created a few times, such as during startup, but the macro should not be used in a
+/// loop. Messages without any arguments are never allocated.
+///
+/// Without the `alloc` feature, the message can't be formatted, so the format string is
+/// used as the reason instead, e.g. `"could not read {}"`.
+///
+/// # Example
+///
+/// ```rust
+/// use version_control_info::{Error, vcs_error};
+///
+/// fn read_revision(path: &str) -> Result<&'static str, Error> {
+///     Err(vcs_error!("could not read the revision from {}", path))
+/// }
+///
+/// let error = read_revision("REVISION").unwrap_err();
+/// assert_eq!(error.to_string(), "could not read the revision from REVISION");
+/// ```
+///
+/// [`format!()`]: https://doc.rust-lang.org/std/macro.format.html
+#[macro_export]
+macro_rules! vcs_error {
+    ($format_string:literal $(, $arg:expr)* $(,)?) => {
+        $crate::__private::other_error(
+            ::core::format_args!($format_string $(, $arg)*),
+            $format_string,
+        )
+    };
+}
+
+/// Asserts at compile time that the embedded commit starts with the given prefix.
+///
+/// This is useful to make sure that a build really is of the commit which you expect,
+/// e.g. when reproducing a bug from a historical release. If the commit does not
+/// start with the prefix, then a compile error is emitted.
+///
+/// By default, the version control info from the [`get!()`] macro is checked, but
+/// any constant [`Info`] may be passed as the first argument.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// version_control_info::expect_commit_prefix!("e1ef127");
+///
+/// const INFO: version_control_info::Info<'_> = version_control_info::get!();
+/// version_control_info::expect_commit_prefix!(INFO, "e1ef127");
+/// ```
+#[macro_export]
+macro_rules! expect_commit_prefix {
+    ($prefix:literal $(,)?) => {
+        $crate::expect_commit_prefix!($crate::get!(), $prefix);
+    };
+    ($info:expr, $prefix:literal $(,)?) => {
+        const _: () = {
+            let info: $crate::Info<'_> = $info;
+            if !$crate::__private::starts_with(info.commit(), $prefix) {
+                ::core::panic!(::core::concat!(
+                    "the embedded commit does not start with `",
+                    $prefix,
+                    "`"
+                ));
+            }
+        };
+    };
+}
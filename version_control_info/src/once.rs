@@ -0,0 +1,99 @@
+use crate::Info;
+use core::{fmt, ops::Deref};
+
+#[cfg(feature = "std")]
+use core::sync::OnceLock as OnceCell;
+#[cfg(not(feature = "std"))]
+use once_cell::sync::OnceCell;
+
+/// A value which is derived from version control info, and is only computed once.
+///
+/// The version control info embedded by the [`get!()`] and [`try_get!()`] macros is a
+/// constant, so anything derived from it, such as a formatted version string or a
+/// serialized JSON body, will be the same every time it is computed. A `VcsInfoOnce`
+/// can be stored in a `static` to compute the derived value the first time it is
+/// accessed, and to reuse it afterwards.
+///
+/// The source of the value is an [`Info`] by default, but any other constant can be
+/// used, e.g. the `Result` returned by [`try_get!()`].
+///
+/// This type is available when the `std` feature is enabled. In `no_std` environments,
+/// it is available through the `once_cell` feature, which requires a
+/// [`critical-section`] implementation to be provided.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use version_control_info::{Error, Info, VcsInfoOnce};
+///
+/// static VERSION: VcsInfoOnce<String> = VcsInfoOnce::new(version_control_info::get!(), |info| {
+///     format!("{} ({:.8})", env!("CARGO_PKG_VERSION"), info.commit())
+/// });
+///
+/// static TRY_VERSION: VcsInfoOnce<String, Result<Info<'static>, Error>> =
+///     VcsInfoOnce::new(version_control_info::try_get!(), |info| match info {
+///         Ok(info) => format!("{} ({:.8})", env!("CARGO_PKG_VERSION"), info.commit()),
+///         Err(_) => String::from(env!("CARGO_PKG_VERSION")),
+///     });
+///
+/// fn handle_version_request() -> &'static str {
+///     &VERSION
+/// }
+/// ```
+///
+/// [`get!()`]: crate::get
+/// [`try_get!()`]: crate::try_get
+/// [`critical-section`]: https://docs.rs/critical-section
+pub struct VcsInfoOnce<T, S = Info<'static>> {
+    source: S,
+    init: fn(&S) -> T,
+    value: OnceCell<T>,
+}
+
+impl<T, S> VcsInfoOnce<T, S> {
+    /// Creates a new `VcsInfoOnce`, which will compute its value by calling `init` on
+    /// the `source` the first time that it is accessed.
+    #[inline]
+    #[must_use]
+    pub const fn new(source: S, init: fn(&S) -> T) -> Self {
+        Self {
+            source,
+            init,
+            value: OnceCell::new(),
+        }
+    }
+
+    /// Returns the source which the value is computed from.
+    #[inline]
+    #[must_use]
+    pub const fn source(&self) -> &S {
+        &self.source
+    }
+
+    /// Returns the derived value, computing it if this is the first time that it has
+    /// been accessed.
+    #[inline]
+    #[must_use]
+    pub fn get(&self) -> &T {
+        self.value.get_or_init(|| (self.init)(&self.source))
+    }
+}
+
+impl<T, S> Deref for VcsInfoOnce<T, S> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.get()
+    }
+}
+
+impl<T: fmt::Debug, S: fmt::Debug> fmt::Debug for VcsInfoOnce<T, S> {
+    #[inline]
+    fn fmt(&self, fmtr: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmtr.debug_struct("VcsInfoOnce")
+            .field("source", &self.source)
+            .field("value", &self.value.get())
+            .finish()
+    }
+}
@@ -0,0 +1,160 @@
+//! Parsers for the formats which version control info is stored in, for services which
+//! read it from untrusted sources, such as uploaded binaries or crate archives.
+//!
+//! Every parser is a pure function over bytes, which never panics, and only allocates
+//! in proportion to its input. Invalid input, including invalid UTF-8, is reported as
+//! an error, or as `None` where the format has no errors to report. This makes them
+//! suitable as fuzzing targets, e.g. with `cargo fuzz`:
+//!
+//! ```rust,ignore
+//! #![no_main]
+//!
+//! libfuzzer_sys::fuzz_target!(|data: &[u8]| {
+//!     let _ = version_control_info::parse::json(data);
+//!     let _ = version_control_info::parse::cargo_vcs_info(data);
+//!     let _ = version_control_info::parse::archive_commit(data);
+//! });
+//! ```
+//!
+//! This module requires the `alloc` feature.
+
+use crate::json::{Parser, Value};
+use crate::{JsonError, JsonInfo, parse_versioned_json};
+use alloc::{string::String, vec::Vec};
+use core::str;
+
+/// Parses the JSON which is written by [`Info::write_json()`], or by the build script's
+/// JSON sidecar.
+///
+/// This is the same as [`parse_versioned_json()`], except that invalid UTF-8 is
+/// reported as a [`JsonError::Syntax`] error at the offset of the first invalid byte.
+///
+/// [`Info::write_json()`]: crate::Info::write_json
+#[inline]
+pub fn json(bytes: &[u8]) -> Result<JsonInfo, JsonError> {
+    parse_versioned_json(utf8(bytes)?)
+}
+
+/// The contents of the `.cargo_vcs_info.json` file which `cargo package` adds to a
+/// crate, as read by [`cargo_vcs_info()`].
+#[non_exhaustive]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct CargoVcsInfo {
+    /// The git commit which the crate was packaged from, if the file records one.
+    pub git: Option<CargoGitInfo>,
+    /// The path of the crate in its repository, e.g. `crates/app`. The root of the
+    /// repository is an empty path.
+    pub path_in_vcs: String,
+}
+
+/// The git commit which a crate was packaged from.
+#[non_exhaustive]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct CargoGitInfo {
+    /// The full commit hash.
+    pub sha1: String,
+    /// Whether the working tree had uncommitted changes when the crate was packaged.
+    pub dirty: bool,
+}
+
+/// Parses the `.cargo_vcs_info.json` file which `cargo package` adds to a crate.
+///
+/// Unknown keys are ignored, so that files from newer versions of cargo can be read.
+///
+/// # Example
+///
+/// ```rust
+/// use version_control_info::parse::cargo_vcs_info;
+///
+/// let json = br#"{
+///   "git": {
+///     "sha1": "aac20b6e7e543e6dd4118b246c77225e3a3a1302"
+///   },
+///   "path_in_vcs": "crates/app"
+/// }"#;
+/// let info = cargo_vcs_info(json).unwrap();
+/// let git = info.git.unwrap();
+/// assert_eq!(git.sha1, "aac20b6e7e543e6dd4118b246c77225e3a3a1302");
+/// assert!(!git.dirty);
+/// assert_eq!(info.path_in_vcs, "crates/app");
+///
+/// assert!(cargo_vcs_info(b"{\"git\":").is_err());
+/// ```
+pub fn cargo_vcs_info(bytes: &[u8]) -> Result<CargoVcsInfo, JsonError> {
+    let mut parser = Parser::new(utf8(bytes)?);
+    let entries = parser.object()?;
+    parser.finish()?;
+
+    let (mut git, mut path_in_vcs) = (None, None);
+    for (key, value) in entries {
+        match (&*key, value) {
+            ("git", Value::Object(git_entries)) => git = Some(cargo_git_info(git_entries)?),
+            ("git", Value::Null) => git = None,
+            ("git", _) => return Err(JsonError::InvalidValue { field: "git" }),
+            ("path_in_vcs", Value::String(path)) => path_in_vcs = Some(path),
+            ("path_in_vcs", _) => {
+                return Err(JsonError::InvalidValue {
+                    field: "path_in_vcs",
+                });
+            }
+            _ => {}
+        }
+    }
+
+    Ok(CargoVcsInfo {
+        git,
+        // cargo only started to write the path in 1.58
+        path_in_vcs: path_in_vcs.unwrap_or_default(),
+    })
+}
+
+// cargo only writes `dirty` when it is `true`.
+fn cargo_git_info(entries: Vec<(String, Value)>) -> Result<CargoGitInfo, JsonError> {
+    let (mut sha1, mut dirty) = (None, false);
+    for (key, value) in entries {
+        match (&*key, value) {
+            ("sha1", Value::String(hash)) => sha1 = Some(hash),
+            ("sha1", _) => return Err(JsonError::InvalidValue { field: "sha1" }),
+            ("dirty", Value::Bool(value)) => dirty = value,
+            ("dirty", _) => return Err(JsonError::InvalidValue { field: "dirty" }),
+            _ => {}
+        }
+    }
+
+    Ok(CargoGitInfo {
+        sha1: sha1.ok_or(JsonError::MissingField { field: "sha1" })?,
+        dirty,
+    })
+}
+
+/// Parses the commit hash from the `.git-commit-id` file of an archive which was
+/// created with `git archive`.
+///
+/// The file contains the `$Format:%H$` placeholder in the repository, which `git
+/// archive` replaces with the commit hash, if the file has the `export-subst`
+/// attribute. This returns `None` if the contents are not a full SHA-1 or SHA-256
+/// hash, e.g. if the placeholder was never replaced.
+///
+/// # Example
+///
+/// ```rust
+/// use version_control_info::parse::archive_commit;
+///
+/// let commit = archive_commit(b"aac20b6e7e543e6dd4118b246c77225e3a3a1302\n");
+/// assert_eq!(commit, Some("aac20b6e7e543e6dd4118b246c77225e3a3a1302"));
+/// assert_eq!(archive_commit(b"$Format:%H$\n"), None);
+/// ```
+#[must_use]
+pub fn archive_commit(bytes: &[u8]) -> Option<&str> {
+    // this must accept the same contents as the build crate
+    let hash = utf8(bytes).ok()?.split_whitespace().next()?;
+    let is_hash = matches!(hash.len(), 40 | 64) && hash.bytes().all(|b| b.is_ascii_hexdigit());
+    is_hash.then_some(hash)
+}
+
+#[inline]
+fn utf8(bytes: &[u8]) -> Result<&str, JsonError> {
+    str::from_utf8(bytes).map_err(|e| JsonError::Syntax {
+        offset: e.valid_up_to(),
+    })
+}
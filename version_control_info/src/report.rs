@@ -0,0 +1,88 @@
+//! A common payload for reporting which builds are deployed where.
+//!
+//! A [`BuildReport`] combines the version control info of a binary with the package
+//! and target which it was built as, so that every service which reports itself to a
+//! collector sends the same shape of data. With the `serde` feature, it can be
+//! serialized to any format which the collector ingests.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! # fn main() {
+//! let report = version_control_info::build_report!();
+//! println!(
+//!     "{} {} ({}) at commit {}",
+//!     report.package_name,
+//!     report.package_version,
+//!     report.target.unwrap_or("unknown target"),
+//!     report.info.commit(),
+//! );
+//! # }
+//! ```
+
+use crate::Info;
+#[cfg(feature = "serde")]
+use serde::Serialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The version control info of a binary, along with the package and target which it
+/// was built as.
+///
+/// Timestamps are in seconds since the Unix epoch. The [`build_report!()`] macro fills
+/// in every field from the build environment.
+///
+/// [`build_report!()`]: crate::build_report
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct BuildReport<'a> {
+    /// The version control info which the binary was built from.
+    pub info: Info<'a>,
+    /// The name of the package, from `CARGO_PKG_NAME`.
+    pub package_name: &'a str,
+    /// The version of the package, from `CARGO_PKG_VERSION`.
+    pub package_version: &'a str,
+    /// The target triple which the binary was built for, e.g. `x86_64-unknown-linux-gnu`.
+    ///
+    /// This is set by the build script when it asks for a report, and is `None` if it
+    /// is not known.
+    pub target: Option<&'a str>,
+    /// The time at which the build script detected the version control info.
+    ///
+    /// The build script only runs again when the repository changes, so this may be
+    /// earlier than the time at which the binary was compiled. It is taken from
+    /// `SOURCE_DATE_EPOCH` if it is set, so that reproducible builds are identical.
+    pub built_at: Option<i64>,
+    /// The time at which the current commit was made, if it is known.
+    ///
+    /// See [`Info::timestamp()`] for more details.
+    pub committed_at: Option<i64>,
+    /// The time at which the report was created.
+    pub reported_at: i64,
+}
+
+impl<'a> BuildReport<'a> {
+    /// Creates a report for `info`, reported at the current time.
+    ///
+    /// The target and build time are not known outside of the build environment, so
+    /// they are `None`. Use the [`build_report!()`] macro to fill them in.
+    ///
+    /// [`build_report!()`]: crate::build_report
+    #[must_use]
+    pub fn new(info: Info<'a>, package_name: &'a str, package_version: &'a str) -> Self {
+        // a clock before the epoch is treated as the epoch
+        let reported_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs() as i64)
+            .unwrap_or_default();
+
+        Self {
+            info,
+            package_name,
+            package_version,
+            target: None,
+            built_at: None,
+            committed_at: info.timestamp(),
+            reported_at,
+        }
+    }
+}
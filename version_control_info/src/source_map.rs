@@ -0,0 +1,157 @@
+//! Resolves the source paths of a debug build to paths in its repository.
+//!
+//! Panic locations and [`file!()`] are relative to the directory which rustc was run
+//! from, which for a crate in a cargo workspace is the root of the workspace, and may
+//! have been rewritten by `--remap-path-prefix`. When the `source_map` generate option
+//! is enabled in the build script of a dev profile build, the path of the crate in its
+//! repository is recorded along with those mappings, so that tools which open the
+//! source of a panic at the embedded commit can find the right file.
+//!
+//! The mappings contain paths on the machine which built the crate, so they are never
+//! recorded for release builds.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! # fn main() {
+//! if let Some(source_map) = version_control_info::source_map!() {
+//!     let path = source_map.repo_path(file!());
+//!     println!("this file is {:?} at {}", path, version_control_info::get!().commit());
+//! }
+//! # }
+//! ```
+//!
+//! [`file!()`]: https://doc.rust-lang.org/std/macro.file.html
+
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The paths which are needed to resolve the source files of a build to paths in its
+/// repository.
+///
+/// Paths in the repository are relative to its root, and always use `/` as the
+/// separator, e.g. `crates/app`. The root of the repository itself is an empty path.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct SourceMap<'a> {
+    /// The path of the crate in the repository, i.e. of the directory containing its
+    /// `Cargo.toml`.
+    pub crate_path: &'a str,
+    /// Maps the root of the cargo workspace on the machine which built the crate to
+    /// its path in the repository.
+    ///
+    /// Relative source paths are relative to the root of the workspace.
+    pub workspace: PathRemap<'a>,
+    /// The `--remap-path-prefix` options which were passed to rustc in `RUSTFLAGS`,
+    /// in the order in which they were passed.
+    pub remaps: &'a [PathRemap<'a>],
+}
+
+/// A mapping from a path prefix to its replacement, in the same style as the
+/// `--remap-path-prefix FROM=TO` option of rustc.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct PathRemap<'a> {
+    /// The prefix which is replaced.
+    pub from: &'a str,
+    /// The replacement for the prefix.
+    pub to: &'a str,
+}
+
+impl<'a> SourceMap<'a> {
+    /// Returns the path in the repository of a source file of the build, such as the
+    /// result of [`file!()`] or the file of a panic location.
+    ///
+    /// The remaps are undone first, before the path is resolved against the root of
+    /// the workspace. This returns `None` if the file is outside of the workspace, e.g.
+    /// if it is in a dependency.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use version_control_info::source_map::{PathRemap, SourceMap};
+    ///
+    /// let source_map = SourceMap {
+    ///     crate_path: "crates/app",
+    ///     workspace: PathRemap { from: "/home/ci/build", to: "" },
+    ///     remaps: &[PathRemap { from: "/home/ci/build", to: "/src" }],
+    /// };
+    /// assert_eq!(
+    ///     source_map.repo_path("crates/app/src/main.rs").as_deref(),
+    ///     Some("crates/app/src/main.rs"),
+    /// );
+    /// assert_eq!(
+    ///     source_map.repo_path("/src/crates/app/src/lib.rs").as_deref(),
+    ///     Some("crates/app/src/lib.rs"),
+    /// );
+    /// assert_eq!(source_map.repo_path("/home/ci/.cargo/registry/src/lib.rs"), None);
+    /// ```
+    ///
+    /// [`file!()`]: https://doc.rust-lang.org/std/macro.file.html
+    #[cfg(feature = "alloc")]
+    #[must_use]
+    pub fn repo_path(&self, file: &str) -> Option<String> {
+        // rustc uses the last remap which matches, so they are undone in reverse
+        let remap = self
+            .remaps
+            .iter()
+            .rev()
+            .find_map(|remap| strip_path_prefix(file, remap.to).map(|rest| (remap, rest)));
+        let unmapped = match remap {
+            Some((remap, rest)) => join(remap.from, rest),
+            None => String::from(file),
+        };
+
+        let relative = match is_absolute(&unmapped) {
+            true => strip_path_prefix(&unmapped, self.workspace.from)?,
+            false => &unmapped,
+        };
+        Some(join(self.workspace.to, relative).replace('\\', "/"))
+    }
+}
+
+// Returns the rest of `path` after `prefix`, if `prefix` is made of whole components of
+// `path`. An empty prefix is a prefix of every relative path.
+#[cfg(feature = "alloc")]
+fn strip_path_prefix<'p>(path: &'p str, prefix: &str) -> Option<&'p str> {
+    if prefix.is_empty() {
+        return (!is_absolute(path)).then_some(path);
+    }
+
+    let rest = path.strip_prefix(prefix)?;
+    if rest.is_empty() || prefix.ends_with(is_separator) {
+        Some(rest)
+    } else {
+        rest.strip_prefix(is_separator)
+    }
+}
+
+#[cfg(feature = "alloc")]
+fn join(base: &str, path: &str) -> String {
+    let mut joined = String::from(base);
+    if !joined.is_empty() && !path.is_empty() && !joined.ends_with(is_separator) {
+        joined.push('/');
+    }
+    joined.push_str(path);
+    joined
+}
+
+// Paths may come from a windows machine, so drive letters and either separator are
+// handled on every platform.
+#[cfg(feature = "alloc")]
+fn is_absolute(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    match *bytes {
+        [first, ..] if is_separator(first as char) => true,
+        [drive, b':', ..] => drive.is_ascii_alphabetic(),
+        _ => false,
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[inline]
+fn is_separator(ch: char) -> bool {
+    ch == '/' || ch == '\\'
+}
@@ -0,0 +1,75 @@
+use crate::{Info, VcsKind};
+
+/// A read-only view of version control info.
+///
+/// This trait is object-safe, so that plugin systems can pass a `&dyn VcsInfoView`
+/// across crate boundaries, without depending on a concrete type, or on the lifetime
+/// of the borrowed data.
+///
+/// # Example
+///
+/// ```rust
+/// use version_control_info::git::HashAlgorithm;
+/// use version_control_info::{Info, Origin, Source, SpecificInfo, VcsInfoView, VcsKind};
+///
+/// fn describe(view: &dyn VcsInfoView) -> String {
+///     format!("{} {}", view.kind(), view.commit())
+/// }
+///
+/// let info = Info {
+///     specific: SpecificInfo::Git {
+///         commit_hash: "aac20b6e7e543e6dd4118b246c77225e3a3a1302",
+///         hash_algorithm: HashAlgorithm::Sha1,
+///         extra: None,
+///     },
+///     source: Source::Repository,
+///     origin: Origin::Path,
+///     host: None,
+///     verified: true,
+///     channel: None,
+/// };
+/// assert_eq!(describe(&info), "git aac20b6e7e543e6dd4118b246c77225e3a3a1302");
+/// assert_eq!(info.kind(), VcsKind::Git);
+/// ```
+pub trait VcsInfoView {
+    /// Returns the full commit hash, or the closest equivalent for the version
+    /// control system.
+    fn commit(&self) -> &str;
+
+    /// Returns the branch, or the closest equivalent for the version control system.
+    ///
+    /// Returns `None` if the version control system has no equivalent, or if the
+    /// branch is not known.
+    fn branch(&self) -> Option<&str>;
+
+    /// Returns the list of tags associated with the current commit.
+    ///
+    /// * Returns `None` if the tag information could not be found.
+    /// * Returns `Some(&[])` if there are no tags associated with the commit.
+    fn tags(&self) -> Option<&[&str]>;
+
+    /// Returns the kind of version control system which the info was read from.
+    fn kind(&self) -> VcsKind;
+}
+
+impl VcsInfoView for Info<'_> {
+    #[inline]
+    fn commit(&self) -> &str {
+        self.specific.commit()
+    }
+
+    #[inline]
+    fn branch(&self) -> Option<&str> {
+        self.specific.branch()
+    }
+
+    #[inline]
+    fn tags(&self) -> Option<&[&str]> {
+        self.specific.tags()
+    }
+
+    #[inline]
+    fn kind(&self) -> VcsKind {
+        self.specific.kind()
+    }
+}
@@ -0,0 +1,226 @@
+use crate::{
+    AndroidRepoExtraInfo, AndroidRepoProject, AutoIndent, Detail, DetectOptions, GenerateOptions,
+    Info, Source, SpecificInfo, git, write_extra_data,
+};
+use std::{
+    error::Error,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{Command, Output, Stdio},
+};
+
+// Only the root of a `repo` client contains a `.repo` directory, and the project is
+// usually inside one of the git repositories which the client checks out.
+#[inline(always)]
+pub(crate) fn client_root<P: ?Sized + AsRef<Path>>(project_path: &P) -> Option<PathBuf> {
+    #[inline(never)]
+    fn inner(project_path: &Path) -> Option<PathBuf> {
+        project_path
+            .ancestors()
+            .find(|dir| manifests_dir(dir).is_dir())
+            .map(Path::to_path_buf)
+    }
+
+    inner(project_path.as_ref())
+}
+
+// The checkout of the manifest repository, which `repo sync` reads the manifest from.
+#[inline]
+fn manifests_dir(client_root: &Path) -> PathBuf {
+    client_root.join(".repo").join("manifests")
+}
+
+// Returns the files which should trigger regeneration when they change.
+pub(crate) fn rerun_paths(
+    client_root: &Path,
+    extra: Option<&AndroidRepoExtraInfo>,
+) -> io::Result<Vec<PathBuf>> {
+    let mut paths = git::rerun_paths(&manifests_dir(client_root), None)?;
+    // `repo sync` moves the `HEAD` of every project which has changed
+    let project_heads = extra
+        .map(|extra| extra.projects.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .map(|project| client_root.join(&project.path).join(".git").join("HEAD"))
+        .filter(|head| head.is_file());
+    paths.extend(project_heads);
+    Ok(paths)
+}
+
+pub(crate) fn detect_repo_client(
+    project_dir: &Path,
+    options: &DetectOptions,
+) -> Result<Info, Box<dyn Error + Send + Sync + 'static>> {
+    fn handle_output(
+        program: &str,
+        output: Output,
+    ) -> Result<String, Box<dyn Error + Send + Sync + 'static>> {
+        if !output.status.success() {
+            let msg = format!(
+                "{} failed: {}",
+                program,
+                String::from_utf8_lossy(&output.stderr)
+            );
+            return Err(From::from(io::Error::other(msg)));
+        }
+
+        let mut stdout = String::from_utf8(output.stdout)?;
+        stdout.truncate(stdout.trim_end().len());
+        Ok(stdout)
+    }
+
+    let client_root = client_root(project_dir)
+        .ok_or_else(|| io::Error::other("could not find the .repo directory"))?;
+
+    // the manifest repository is never the configured git directory
+    let git_options = DetectOptions {
+        git_dir: None,
+        ..options.clone()
+    };
+    let manifest_revision = git::git(&manifests_dir(&client_root), &git_options)
+        .args(["rev-parse", "HEAD"])
+        .spawn()?;
+    // `repo manifest -r` prints the manifest with the revision of every project
+    // replaced by the commit which is checked out, which can take a while in a
+    // large client.
+    let manifest = match options.detail {
+        Detail::Quick => None,
+        Detail::Full => Some(
+            repo(&client_root)
+                .args(["manifest", "--revision-as-HEAD", "--output-file", "-"])
+                .spawn()?,
+        ),
+    };
+
+    let manifest_revision = handle_output("git", manifest_revision.wait_with_output()?)?;
+    let extra = match manifest {
+        Some(manifest) => {
+            let manifest = handle_output("repo", manifest.wait_with_output()?)?;
+            Some(AndroidRepoExtraInfo {
+                projects: parse_projects(&manifest)?,
+            })
+        }
+        None => None,
+    };
+
+    Ok(Info {
+        specific: SpecificInfo::AndroidRepo {
+            manifest_revision,
+            extra,
+        },
+        source: Source::Repository,
+    })
+}
+
+pub(crate) fn write_specific_info(
+    file: &mut dyn Write,
+    manifest_revision: &str,
+    extra: Option<&AndroidRepoExtraInfo>,
+    _options: &GenerateOptions,
+    indent: &AutoIndent<'_>,
+) -> io::Result<()> {
+    writeln_indented!(
+        indent,
+        file,
+        "specific: version_control_info::SpecificInfo::AndroidRepo {{"
+    )?;
+    {
+        let _indent = indent.increment();
+        writeln_indented!(indent, file, "manifest_revision: {:?},", manifest_revision)?;
+        write_extra_data(
+            file,
+            extra,
+            "version_control_info::android_repo::ExtraData",
+            indent,
+            |file, extra, indent| {
+                writeln_indented!(indent, file, "projects: &[")?;
+                {
+                    let _indent = indent.increment();
+                    for project in &extra.projects {
+                        writeln_indented!(
+                            indent,
+                            file,
+                            "version_control_info::android_repo::Project {{"
+                        )?;
+                        {
+                            let _indent = indent.increment();
+                            writeln_indented!(indent, file, "name: {:?},", project.name)?;
+                            writeln_indented!(indent, file, "path: {:?},", project.path)?;
+                            writeln_indented!(indent, file, "commit: {:?},", project.commit)?;
+                        }
+                        writeln_indented!(indent, file, "}},")?;
+                    }
+                }
+                writeln_indented!(indent, file, "],")
+            },
+        )?;
+    }
+    writeln_indented!(indent, file, "}},")
+}
+
+// Reads the `<project>` elements of a manifest, e.g.
+//
+// <project name="platform/build" path="build/make" revision="4e4fc1b..." upstream="main"/>
+//
+// The path of a project defaults to its name.
+fn parse_projects(manifest: &str) -> io::Result<Vec<AndroidRepoProject>> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "unexpected output from repo");
+
+    let mut projects = vec![];
+    let mut rest = manifest;
+    while let Some(start) = rest.find("<project") {
+        let element = &rest[start + "<project".len()..];
+        let end = element.find('>').ok_or_else(invalid)?;
+        rest = &element[end..];
+
+        // skip elements which only start with `project`, such as `<project-notice>`
+        let attributes = &element[..end];
+        if !attributes.starts_with(char::is_whitespace) {
+            continue;
+        }
+
+        let name = attribute(attributes, "name").ok_or_else(invalid)?;
+        let path = attribute(attributes, "path").unwrap_or_else(|| name.clone());
+        let commit = attribute(attributes, "revision").ok_or_else(invalid)?;
+        projects.push(AndroidRepoProject { name, path, commit });
+    }
+
+    Ok(projects)
+}
+
+// Returns the unescaped value of the `key` attribute in the attributes of an element.
+fn attribute(attributes: &str, key: &str) -> Option<String> {
+    let mut rest = attributes;
+    loop {
+        let (name, value) = rest.split_once('=')?;
+        let value = value.trim_start();
+        let quote = value.chars().next().filter(|&ch| ch == '"' || ch == '\'')?;
+        let (value, after) = value[1..].split_once(quote)?;
+        if name.trim() == key {
+            return Some(
+                value
+                    .replace("&lt;", "<")
+                    .replace("&gt;", ">")
+                    .replace("&quot;", "\"")
+                    .replace("&apos;", "'")
+                    .replace("&amp;", "&"),
+            );
+        }
+        rest = after;
+    }
+}
+
+#[inline]
+fn repo<P: ?Sized + AsRef<Path>>(cwd: &P) -> Command {
+    #[inline(never)]
+    fn inner(cwd: &Path) -> Command {
+        let mut cmnd = Command::new("repo");
+        cmnd.stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .current_dir(cwd);
+        cmnd
+    }
+
+    inner(cwd.as_ref())
+}
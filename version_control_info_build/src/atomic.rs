@@ -0,0 +1,85 @@
+use std::{
+    ffi::OsString,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+// A file which is written to a temporary file next to it, and renamed into place once
+// it is finished.
+//
+// If the build script is interrupted, e.g. by Ctrl-C or by running out of memory, the
+// previous version of the file is left intact, rather than a truncated file which
+// `include!()`s as garbage. Cargo runs the build script again on the next build, as
+// it never finished, which regenerates the file.
+pub(crate) struct AtomicFile {
+    writer: Option<BufWriter<File>>,
+    temp_path: PathBuf,
+    path: PathBuf,
+}
+
+impl AtomicFile {
+    pub(crate) fn create(path: PathBuf) -> io::Result<Self> {
+        let temp_path = temp_path(&path);
+        // a temporary file left behind by an interrupted build is simply truncated
+        let writer = File::create(&temp_path).map(BufWriter::new)?;
+        Ok(Self {
+            writer: Some(writer),
+            temp_path,
+            path,
+        })
+    }
+
+    // Renames the file into place. If this is not called, the file is discarded.
+    pub(crate) fn finish(mut self) -> io::Result<()> {
+        if let Some(writer) = self.writer.take() {
+            // the file has to be closed before it is renamed on windows
+            let file = writer
+                .into_inner()
+                .map_err(io::IntoInnerError::into_error)?;
+            drop(file);
+        }
+        fs::rename(&self.temp_path, &self.path)
+    }
+
+    #[inline]
+    fn writer(&mut self) -> &mut BufWriter<File> {
+        // the writer is only taken by `finish()`, which consumes the file
+        self.writer
+            .as_mut()
+            .expect("the file has already been finished")
+    }
+}
+
+impl Write for AtomicFile {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writer().write(buf)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer().flush()
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        // the file has either been renamed already, or is incomplete
+        drop(self.writer.take());
+        let _ = fs::remove_file(&self.temp_path);
+    }
+}
+
+// Writes `contents` to `path` atomically, in the same way as `fs::write()`.
+pub(crate) fn write(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut file = AtomicFile::create(path.to_path_buf())?;
+    file.write_all(contents)?;
+    file.finish()
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    let mut temp_path = OsString::from(path.as_os_str());
+    temp_path.push(".tmp");
+    PathBuf::from(temp_path)
+}
@@ -0,0 +1,120 @@
+use crate::{
+    AutoIndent, BazaarExtraInfo, DetectOptions, GenerateOptions, Info, Source, SpecificInfo,
+    write_extra_data,
+};
+use std::{
+    error::Error,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{Command, Output, Stdio},
+};
+
+#[inline(always)]
+pub(crate) fn has_bzr_folder<P: ?Sized + AsRef<Path>>(project_path: &P) -> bool {
+    #[inline(never)]
+    fn inner(project_path: &Path) -> bool {
+        project_path.join(".bzr").is_dir()
+    }
+
+    inner(project_path.as_ref())
+}
+
+pub(crate) fn rerun_paths(project_dir: &Path) -> Vec<PathBuf> {
+    let bzr_dir = project_dir.join(".bzr");
+    let mut paths = vec![
+        bzr_dir.join("branch").join("last-revision"),
+        bzr_dir.join("branch").join("branch.conf"),
+        bzr_dir.join("checkout").join("dirstate"),
+    ];
+    paths.retain(|path| path.is_file());
+    paths
+}
+
+pub(crate) fn detect_bzr_directory(
+    project_dir: &Path,
+    _options: &DetectOptions,
+) -> Result<Info, Box<dyn Error + Send + Sync + 'static>> {
+    fn run(project_dir: &Path, args: &[&str]) -> io::Result<Output> {
+        // Breezy is the maintained fork of Bazaar, but may only be installed
+        // under the original `bzr` name.
+        match brz(project_dir, "brz").args(args).output() {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                brz(project_dir, "bzr").args(args).output()
+            }
+            result => result,
+        }
+    }
+
+    fn handle_output(output: Output) -> Result<String, Box<dyn Error + Send + Sync + 'static>> {
+        if !output.status.success() {
+            let msg = format!("brz failed: {}", String::from_utf8_lossy(&output.stderr));
+            return Err(From::from(io::Error::other(msg)));
+        }
+
+        let stdout = String::from_utf8(output.stdout)?;
+        Ok(stdout.trim().to_owned())
+    }
+
+    // prints the revision number and revision id, e.g.
+    //
+    // 42 jane@example.com-20240101120000-abcdef0123456789
+    let revision_info = handle_output(run(project_dir, &["revision-info", "--tree"])?)?;
+    let nick = handle_output(run(project_dir, &["nick"])?)?;
+
+    let (revno, revision_id) = revision_info
+        .split_once(' ')
+        .map(|(revno, revision_id)| (revno.to_owned(), revision_id.trim().to_owned()))
+        .ok_or_else(|| io::Error::other("unexpected output from brz revision-info"))?;
+
+    Ok(Info {
+        specific: SpecificInfo::Bazaar {
+            revision_id,
+            extra: Some(BazaarExtraInfo { revno, nick }),
+        },
+        source: Source::Repository,
+    })
+}
+
+pub(crate) fn write_specific_info(
+    file: &mut dyn Write,
+    revision_id: &str,
+    extra: Option<&BazaarExtraInfo>,
+    _options: &GenerateOptions,
+    indent: &AutoIndent<'_>,
+) -> io::Result<()> {
+    writeln_indented!(
+        indent,
+        file,
+        "specific: version_control_info::SpecificInfo::Bazaar {{"
+    )?;
+    {
+        let _indent = indent.increment();
+        writeln_indented!(indent, file, "revision_id: {:?},", revision_id)?;
+        write_extra_data(
+            file,
+            extra,
+            "version_control_info::bazaar::ExtraData",
+            indent,
+            |file, extra, indent| {
+                writeln_indented!(indent, file, "revno: {:?},", extra.revno)?;
+                writeln_indented!(indent, file, "nick: {:?},", extra.nick)
+            },
+        )?;
+    }
+    writeln_indented!(indent, file, "}},")
+}
+
+#[inline]
+fn brz<P: ?Sized + AsRef<Path>>(cwd: &P, program: &str) -> Command {
+    #[inline(never)]
+    fn inner(cwd: &Path, program: &str) -> Command {
+        let mut cmnd = Command::new(program);
+        cmnd.stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .current_dir(cwd);
+        cmnd
+    }
+
+    inner(cwd.as_ref(), program)
+}
@@ -0,0 +1,228 @@
+use crate::{BuildEnv, DetectOptions, Info, Source, SpecificInfo, atomic, git};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    env,
+    error::Error,
+    fmt::Write as _,
+    fs::{self, OpenOptions},
+    io,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+const DIR_NAME: &str = "version_control_info";
+const OUT_DIR_FILE_NAME: &str = "version_control_info_cache.json";
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Entry {
+    key: String,
+    specific: SpecificInfo,
+    remote_url: Option<String>,
+}
+
+// Returns the git info and remote url of the repository, reusing the result of another
+// build script in the same target directory, or of an earlier run of this build script,
+// if the repository hasn't changed since.
+//
+// The state of the working tree isn't cached, as cargo doesn't watch it, so it is
+// detected again on every run.
+//
+// The shared cache is locked while the repository is detected, so that concurrent build
+// scripts wait for the first one to finish rather than running git themselves. The
+// cache in `OUT_DIR` has a single entry, as it is only used by one build script.
+pub(crate) fn detect_git(
+    project_dir: &Path,
+    options: &DetectOptions,
+    detect: impl FnOnce(
+        &DetectOptions,
+    ) -> Result<(Info, Option<String>), Box<dyn Error + Send + Sync + 'static>>,
+) -> Result<(Info, Option<String>), Box<dyn Error + Send + Sync + 'static>> {
+    let key = key(project_dir, options)?;
+    let (entry_path, _lock) = match (options.shared_cache, options.persistent_cache) {
+        (true, _) => match cache_dir(options) {
+            Some(cache_dir) => {
+                fs::create_dir_all(&cache_dir)?;
+                let lock = OpenOptions::new()
+                    .create(true)
+                    .truncate(false)
+                    .write(true)
+                    .open(cache_dir.join(".lock"))?;
+                lock.lock()?;
+                (cache_dir.join(&key).with_extension("json"), Some(lock))
+            }
+            None => return detect(options),
+        },
+        (false, true) => match options.env.resolve_out_dir() {
+            Ok(out_dir) => (out_dir.join(OUT_DIR_FILE_NAME), None),
+            Err(_) => return detect(options),
+        },
+        (false, false) => return detect(options),
+    };
+
+    // an entry which can't be read is simply detected again, and overwritten
+    let entry = fs::read(&entry_path)
+        .ok()
+        .and_then(|contents| serde_json::from_slice::<Entry>(&contents).ok())
+        .filter(|entry| entry.key == key);
+    let (mut info, remote_url) = match entry {
+        Some(entry) => {
+            let info = Info {
+                specific: entry.specific,
+                source: Source::Repository,
+            };
+            (info, entry.remote_url)
+        }
+        None => {
+            let committed_options = DetectOptions {
+                record_dirty: false,
+                record_dirty_files: false,
+                ..options.clone()
+            };
+            let (info, remote_url) = detect(&committed_options)?;
+            let entry = Entry {
+                key,
+                specific: info.specific.clone(),
+                remote_url,
+            };
+            atomic::write(&entry_path, &serde_json::to_vec(&entry)?)?;
+            (info, entry.remote_url)
+        }
+    };
+    git::detect_working_tree(project_dir, options, &mut info)?;
+    Ok((info, remote_url))
+}
+
+// Build scripts of every package in the workspace share the profile directory, e.g.
+// `target/debug`, which contains the `build` directory holding their `OUT_DIR`s.
+fn cache_dir(options: &DetectOptions) -> Option<PathBuf> {
+    let out_dir = options.env.resolve_out_dir().ok()?;
+    let build_dir = out_dir
+        .ancestors()
+        .find(|dir| dir.file_name().is_some_and(|name| name == "build"))?;
+    build_dir
+        .parent()
+        .map(|profile_dir| profile_dir.join(DIR_NAME))
+}
+
+// The key covers the files which cargo is told to watch for the repository, so an entry
+// is only reused while none of them have changed, i.e. while `HEAD`, the refs and the
+// index are the same. The contents of `HEAD` are covered too, as checking out another
+// branch may not change its length, or its modification time on coarse file systems.
+fn key(project_dir: &Path, options: &DetectOptions) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    hasher.update(env!("CARGO_PKG_VERSION"));
+    hasher.update(project_dir.as_os_str().as_encoded_bytes());
+    // the vendor lookup function has a different address in every build script, and
+    // isn't used for repositories anyway. The build environment differs between the
+    // build scripts too, but doesn't change what is detected.
+    let options = DetectOptions {
+        vendor_lookup: None,
+        env: BuildEnv::new(),
+        ..options.clone()
+    };
+    hasher.update(format!("{:?}", options));
+
+    for path in git::rerun_paths(project_dir, options.git_dir.as_deref())? {
+        hasher.update(path.as_os_str().as_encoded_bytes());
+        let metadata = fs::metadata(&path)?;
+        let modified = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        hasher.update(metadata.len().to_le_bytes());
+        hasher.update(modified.as_nanos().to_le_bytes());
+        if path.file_name().is_some_and(|name| name == "HEAD") {
+            hasher.update(fs::read(&path)?);
+        }
+    }
+
+    let key = hasher
+        .finalize()
+        .iter()
+        .fold(String::new(), |mut hex, byte| {
+            let _ = write!(hex, "{:02x}", byte);
+            hex
+        });
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs::File, process, time::Duration};
+
+    struct ProjectDir(PathBuf);
+
+    impl ProjectDir {
+        fn new(name: &str) -> Self {
+            let dir = env::temp_dir().join(format!(
+                "version_control_info_cache_{}_{}",
+                name,
+                process::id()
+            ));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(dir.join(".git")).unwrap();
+            Self(dir)
+        }
+
+        // writes `HEAD` with the same modification time every time, as a checkout on a
+        // coarse file system would
+        fn write_head(&self, contents: &str) {
+            let head = self.0.join(".git").join("HEAD");
+            fs::write(&head, contents).unwrap();
+            File::options()
+                .write(true)
+                .open(&head)
+                .unwrap()
+                .set_modified(UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+                .unwrap();
+        }
+    }
+
+    impl Drop for ProjectDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    const COMMIT: &str = "1111111111111111111111111111111111111111\n";
+    const OTHER_COMMIT: &str = "2222222222222222222222222222222222222222\n";
+
+    #[test]
+    fn keys_are_stable() {
+        let project_dir = ProjectDir::new("stable");
+        project_dir.write_head(COMMIT);
+
+        let options = DetectOptions::full();
+        assert_eq!(
+            key(&project_dir.0, &options).unwrap(),
+            key(&project_dir.0, &options).unwrap()
+        );
+    }
+
+    #[test]
+    fn keys_cover_the_contents_of_head() {
+        let project_dir = ProjectDir::new("head");
+        let options = DetectOptions::full();
+
+        project_dir.write_head(COMMIT);
+        let before = key(&project_dir.0, &options).unwrap();
+        project_dir.write_head(OTHER_COMMIT);
+        assert_ne!(before, key(&project_dir.0, &options).unwrap());
+    }
+
+    #[test]
+    fn keys_cover_the_options_but_not_the_build_env() {
+        let project_dir = ProjectDir::new("options");
+        project_dir.write_head(COMMIT);
+
+        let options = DetectOptions::full();
+        let key = |options: &DetectOptions| key(&project_dir.0, options).unwrap();
+        assert_ne!(key(&options), key(&options.clone().record_dirty(false)));
+        assert_eq!(
+            key(&options),
+            key(&options.clone().env(BuildEnv::new().out_dir("/tmp/out")))
+        );
+    }
+}
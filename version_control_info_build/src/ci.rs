@@ -0,0 +1,357 @@
+use crate::{CiProvider, DetectOptions, GitHashAlgorithm, Info, Source, SpecificInfo, overrides};
+use std::{error::Error, io};
+
+// The providers which are consulted after the registered ones.
+const BUILT_IN: [&dyn CiProvider; 6] = [
+    &GitHubActions,
+    &GitLabCi,
+    &Jenkins,
+    &CircleCi,
+    &Buildkite,
+    &AzurePipelines,
+];
+
+// Returns the commit which a CI job is running for, if the build is running in one of the
+// CI services. Jobs which export the sources, or which check them out without the
+// repository metadata, still have the commit in the environment.
+pub(crate) fn detect(
+    options: &DetectOptions,
+) -> Result<Option<Info>, Box<dyn Error + Send + Sync + 'static>> {
+    for provider in providers(options) {
+        if let Some(info) = provider.detect(options)? {
+            return Ok(Some(info));
+        }
+    }
+    Ok(None)
+}
+
+// The registered providers, followed by the built-in ones.
+pub(crate) fn providers(options: &DetectOptions) -> impl Iterator<Item = &dyn CiProvider> {
+    options
+        .ci_providers
+        .iter()
+        .map(|provider| provider.0)
+        .chain(BUILT_IN)
+}
+
+struct GitHubActions;
+
+impl CiProvider for GitHubActions {
+    fn name(&self) -> &'static str {
+        "GitHub Actions"
+    }
+
+    fn env_vars(&self) -> &'static [&'static str] {
+        &[
+            "GITHUB_ACTIONS",
+            "GITHUB_SHA",
+            "GITHUB_REF",
+            "GITHUB_HEAD_REF",
+            "GITHUB_SERVER_URL",
+            "GITHUB_REPOSITORY",
+        ]
+    }
+
+    fn detect(
+        &self,
+        options: &DetectOptions,
+    ) -> Result<Option<Info>, Box<dyn Error + Send + Sync + 'static>> {
+        if overrides::var("GITHUB_ACTIONS")?.as_deref() != Some("true") {
+            return Ok(None);
+        }
+
+        // the commit of a pull request merges it into the base branch, so the closest
+        // branch is the head branch of the pull request
+        let (branch, tag) = parse_ref(&overrides::var("GITHUB_REF")?.unwrap_or_default());
+        let branch = match branch {
+            Some(branch) => Some(branch),
+            None => overrides::var("GITHUB_HEAD_REF")?.filter(|head_ref| !head_ref.is_empty()),
+        };
+        // the server differs for GitHub Enterprise
+        let server_url = overrides::var("GITHUB_SERVER_URL")?;
+        let remote_url = match (server_url, overrides::var("GITHUB_REPOSITORY")?) {
+            (Some(server_url), Some(repository)) => Some(format!(
+                "{}/{}",
+                server_url.trim_end_matches('/'),
+                repository
+            )),
+            _ => None,
+        };
+
+        let job = Job {
+            commit_var: "GITHUB_SHA",
+            branch,
+            tag,
+            remote_url,
+        };
+        Ok(job.info(Source::GitHubActions, options)?)
+    }
+}
+
+struct GitLabCi;
+
+impl CiProvider for GitLabCi {
+    fn name(&self) -> &'static str {
+        "GitLab CI"
+    }
+
+    fn env_vars(&self) -> &'static [&'static str] {
+        &[
+            "GITLAB_CI",
+            "CI_COMMIT_SHA",
+            "CI_COMMIT_REF_NAME",
+            "CI_COMMIT_TAG",
+            "CI_PROJECT_URL",
+        ]
+    }
+
+    fn detect(
+        &self,
+        options: &DetectOptions,
+    ) -> Result<Option<Info>, Box<dyn Error + Send + Sync + 'static>> {
+        if overrides::var("GITLAB_CI")?.as_deref() != Some("true") {
+            return Ok(None);
+        }
+
+        // the ref name is the source branch for merge request pipelines, and the tag
+        // for tag pipelines
+        let tag = overrides::var("CI_COMMIT_TAG")?;
+        let branch = match tag {
+            Some(_) => None,
+            None => overrides::var("CI_COMMIT_REF_NAME")?,
+        };
+
+        let job = Job {
+            commit_var: "CI_COMMIT_SHA",
+            branch,
+            tag,
+            remote_url: overrides::var("CI_PROJECT_URL")?,
+        };
+        Ok(job.info(Source::GitLabCi, options)?)
+    }
+}
+
+struct Jenkins;
+
+impl CiProvider for Jenkins {
+    fn name(&self) -> &'static str {
+        "Jenkins"
+    }
+
+    fn env_vars(&self) -> &'static [&'static str] {
+        &[
+            "JENKINS_URL",
+            "GIT_COMMIT",
+            "BRANCH_NAME",
+            "GIT_BRANCH",
+            "TAG_NAME",
+            "GIT_URL",
+        ]
+    }
+
+    fn detect(
+        &self,
+        options: &DetectOptions,
+    ) -> Result<Option<Info>, Box<dyn Error + Send + Sync + 'static>> {
+        if overrides::var("JENKINS_URL")?.is_none() {
+            return Ok(None);
+        }
+
+        // multibranch pipelines set the name of the branch, or of the tag for tag
+        // builds, while the git plugin sets the remote branch, e.g. `origin/main`
+        let tag = overrides::var("TAG_NAME")?;
+        let branch = match (&tag, overrides::var("BRANCH_NAME")?) {
+            (Some(_), _) => None,
+            (None, Some(branch)) => Some(branch),
+            (None, None) => {
+                overrides::var("GIT_BRANCH")?.map(|branch| match branch.split_once('/') {
+                    Some((_, branch)) => String::from(branch),
+                    None => branch,
+                })
+            }
+        };
+
+        let job = Job {
+            commit_var: "GIT_COMMIT",
+            branch,
+            tag,
+            remote_url: overrides::var("GIT_URL")?,
+        };
+        Ok(job.info(Source::Ci, options)?)
+    }
+}
+
+struct CircleCi;
+
+impl CiProvider for CircleCi {
+    fn name(&self) -> &'static str {
+        "CircleCI"
+    }
+
+    fn env_vars(&self) -> &'static [&'static str] {
+        &[
+            "CIRCLECI",
+            "CIRCLE_SHA1",
+            "CIRCLE_BRANCH",
+            "CIRCLE_TAG",
+            "CIRCLE_REPOSITORY_URL",
+        ]
+    }
+
+    fn detect(
+        &self,
+        options: &DetectOptions,
+    ) -> Result<Option<Info>, Box<dyn Error + Send + Sync + 'static>> {
+        if overrides::var("CIRCLECI")?.as_deref() != Some("true") {
+            return Ok(None);
+        }
+
+        let job = Job {
+            commit_var: "CIRCLE_SHA1",
+            branch: overrides::var("CIRCLE_BRANCH")?,
+            tag: overrides::var("CIRCLE_TAG")?,
+            remote_url: overrides::var("CIRCLE_REPOSITORY_URL")?,
+        };
+        Ok(job.info(Source::Ci, options)?)
+    }
+}
+
+struct Buildkite;
+
+impl CiProvider for Buildkite {
+    fn name(&self) -> &'static str {
+        "Buildkite"
+    }
+
+    fn env_vars(&self) -> &'static [&'static str] {
+        &[
+            "BUILDKITE",
+            "BUILDKITE_COMMIT",
+            "BUILDKITE_BRANCH",
+            "BUILDKITE_TAG",
+            "BUILDKITE_REPO",
+        ]
+    }
+
+    fn detect(
+        &self,
+        options: &DetectOptions,
+    ) -> Result<Option<Info>, Box<dyn Error + Send + Sync + 'static>> {
+        if overrides::var("BUILDKITE")?.as_deref() != Some("true") {
+            return Ok(None);
+        }
+        // builds which are triggered without a commit have `HEAD`, which is only
+        // resolved by the checkout, so there is no commit to use
+        let commit = overrides::var("BUILDKITE_COMMIT")?;
+        if commit.is_some_and(|commit| GitHashAlgorithm::of(&commit).is_none()) {
+            return Ok(None);
+        }
+
+        let tag = overrides::var("BUILDKITE_TAG")?;
+        let branch = match tag {
+            Some(_) => None,
+            None => overrides::var("BUILDKITE_BRANCH")?,
+        };
+
+        let job = Job {
+            commit_var: "BUILDKITE_COMMIT",
+            branch,
+            tag,
+            remote_url: overrides::var("BUILDKITE_REPO")?,
+        };
+        Ok(job.info(Source::Ci, options)?)
+    }
+}
+
+struct AzurePipelines;
+
+impl CiProvider for AzurePipelines {
+    fn name(&self) -> &'static str {
+        "Azure Pipelines"
+    }
+
+    fn env_vars(&self) -> &'static [&'static str] {
+        &[
+            "TF_BUILD",
+            "BUILD_REPOSITORY_PROVIDER",
+            "BUILD_SOURCEVERSION",
+            "BUILD_SOURCEBRANCH",
+            "SYSTEM_PULLREQUEST_SOURCEBRANCH",
+            "BUILD_REPOSITORY_URI",
+        ]
+    }
+
+    fn detect(
+        &self,
+        options: &DetectOptions,
+    ) -> Result<Option<Info>, Box<dyn Error + Send + Sync + 'static>> {
+        // the source version is a changeset number for repositories which don't use git
+        let provider = overrides::var("BUILD_REPOSITORY_PROVIDER")?;
+        if overrides::var("TF_BUILD")?.is_none() || provider.as_deref() == Some("TfsVersionControl")
+        {
+            return Ok(None);
+        }
+
+        // the source branch of a pull request is a full ref, e.g. `refs/heads/feature`
+        let (branch, tag) = parse_ref(&overrides::var("BUILD_SOURCEBRANCH")?.unwrap_or_default());
+        let branch = match branch {
+            Some(branch) => Some(branch),
+            None => overrides::var("SYSTEM_PULLREQUEST_SOURCEBRANCH")?
+                .and_then(|source_branch| parse_ref(&source_branch).0),
+        };
+
+        let job = Job {
+            commit_var: "BUILD_SOURCEVERSION",
+            branch,
+            tag,
+            remote_url: overrides::var("BUILD_REPOSITORY_URI")?,
+        };
+        Ok(job.info(Source::Ci, options)?)
+    }
+}
+
+// The commit which a CI job is running for, which is read from `commit_var`.
+struct Job {
+    commit_var: &'static str,
+    branch: Option<String>,
+    tag: Option<String>,
+    remote_url: Option<String>,
+}
+
+impl Job {
+    // Returns `None` if the job has no commit. A job which isn't on a branch is detached,
+    // as in git.
+    fn info(self, source: Source, options: &DetectOptions) -> io::Result<Option<Info>> {
+        let Some(commit_hash) = overrides::var(self.commit_var)? else {
+            return Ok(None);
+        };
+        let hash_algorithm = GitHashAlgorithm::of(&commit_hash)
+            .ok_or_else(|| overrides::invalid_var(self.commit_var, "not a full commit hash"))?;
+
+        let branch = self.branch.unwrap_or_else(|| String::from("HEAD"));
+        let mut extra = overrides::git_extra_info(&commit_hash, branch, options.short_hash_length)?;
+        extra.tags = self.tag.map(|tag| vec![tag]);
+        extra.remote_url = self.remote_url;
+
+        Ok(Some(Info {
+            specific: SpecificInfo::Git {
+                commit_hash,
+                hash_algorithm,
+                extra: Some(Box::new(extra)),
+            },
+            source,
+        }))
+    }
+}
+
+// Returns the branch or the tag of a full ref. Other refs, such as those of pull
+// requests, have neither.
+fn parse_ref(git_ref: &str) -> (Option<String>, Option<String>) {
+    if let Some(branch) = git_ref.strip_prefix("refs/heads/") {
+        (Some(String::from(branch)), None)
+    } else if let Some(tag) = git_ref.strip_prefix("refs/tags/") {
+        (None, Some(String::from(tag)))
+    } else {
+        (None, None)
+    }
+}
@@ -0,0 +1,76 @@
+use crate::{Deflated, GitCompressed, Info, SpecificInfo};
+use miniz_oxide::deflate::{CompressionLevel, compress_to_vec};
+use std::{borrow::Cow, fmt::Write as _};
+
+// Compresses the payloads of the info which are at least `threshold` bytes long, and
+// smaller once compressed, moving them to the `compressed` field of the extra data.
+//
+// Each payload is encoded as text, which is decoded by the accessors of
+// `version_control_info::git::Compressed`. List entries are terminated by nul bytes, as
+// paths may contain newlines.
+pub(crate) fn compress(mut info: Cow<'_, Info>, threshold: usize) -> Cow<'_, Info> {
+    let SpecificInfo::Git {
+        extra: Some(ref extra),
+        ..
+    } = info.specific
+    else {
+        return info;
+    };
+
+    let submodules = extra.submodules.as_ref().map(|submodules| {
+        submodules
+            .iter()
+            .fold(String::new(), |mut text, submodule| {
+                let dirty = u8::from(submodule.dirty);
+                let _ = write!(text, "{} {} {}\0", submodule.commit, dirty, submodule.path);
+                text
+            })
+    });
+    let dirty_files = extra.dirty_files.as_ref().map(|dirty_files| {
+        dirty_files.iter().fold(String::new(), |mut text, path| {
+            text.extend([&**path, "\0"]);
+            text
+        })
+    });
+    let compressed = GitCompressed {
+        submodules: submodules.and_then(|text| deflate(&text, threshold)),
+        notes: extra
+            .notes
+            .as_ref()
+            .and_then(|notes| deflate(notes, threshold)),
+        dirty_files: dirty_files.and_then(|text| deflate(&text, threshold)),
+    };
+    if compressed == GitCompressed::default() {
+        return info;
+    }
+
+    if let SpecificInfo::Git {
+        extra: Some(ref mut extra),
+        ..
+    } = info.to_mut().specific
+    {
+        if compressed.submodules.is_some() {
+            extra.submodules = None;
+        }
+        if compressed.notes.is_some() {
+            extra.notes = None;
+        }
+        if compressed.dirty_files.is_some() {
+            extra.dirty_files = None;
+        }
+        extra.compressed = Some(compressed);
+    }
+    info
+}
+
+fn deflate(text: &str, threshold: usize) -> Option<Deflated> {
+    if text.len() < threshold {
+        return None;
+    }
+
+    let bytes = compress_to_vec(text.as_bytes(), CompressionLevel::BestCompression as u8);
+    (bytes.len() < text.len()).then_some(Deflated {
+        bytes,
+        len: text.len(),
+    })
+}
@@ -0,0 +1,126 @@
+use crate::{
+    AutoIndent, DarcsExtraInfo, DetectOptions, EmptyRepositoryError, GenerateOptions, Info, Source,
+    SpecificInfo, write_extra_data,
+};
+use std::{
+    error::Error,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{Command, Output, Stdio},
+};
+
+#[inline(always)]
+pub(crate) fn has_darcs_folder<P: ?Sized + AsRef<Path>>(project_path: &P) -> bool {
+    #[inline(never)]
+    fn inner(project_path: &Path) -> bool {
+        project_path.join("_darcs").is_dir()
+    }
+
+    inner(project_path.as_ref())
+}
+
+#[inline]
+pub(crate) fn inventory_file(project_dir: &Path) -> PathBuf {
+    project_dir.join("_darcs").join("hashed_inventory")
+}
+
+pub(crate) fn detect_darcs_directory(
+    project_dir: &Path,
+    _options: &DetectOptions,
+) -> Result<Info, Box<dyn Error + Send + Sync + 'static>> {
+    fn handle_output(output: Output) -> Result<String, Box<dyn Error + Send + Sync + 'static>> {
+        if !output.status.success() {
+            let msg = format!("darcs failed: {}", String::from_utf8_lossy(&output.stderr));
+            return Err(From::from(io::Error::other(msg)));
+        }
+
+        String::from_utf8(output.stdout).map_err(From::from)
+    }
+
+    let log = darcs(project_dir)
+        .args(["log", "--last=1", "--xml-output"])
+        .spawn()?;
+    let repo = darcs(project_dir).args(["show", "repo"]).spawn()?;
+
+    let (log, repo) = (
+        handle_output(log.wait_with_output()?)?,
+        handle_output(repo.wait_with_output()?)?,
+    );
+
+    // the patch is described by an element like:
+    //
+    // <patch author='jane@example.com' date='20240101120000' ... hash='0123abcd...'>
+    let patch_hash = log
+        .split_once("<patch ")
+        .map(|(_, patch)| patch)
+        .ok_or_else(|| EmptyRepositoryError::new(project_dir.join("_darcs")))?
+        .split_once("hash='")
+        .and_then(|(_, hash)| hash.split_once('\''))
+        .map(|(hash, _)| hash.to_owned())
+        .ok_or_else(|| io::Error::other("could not find the hash of the latest darcs patch"))?;
+
+    // `darcs show repo` prints a `Key: value` pair on each line, and the repository
+    // is named after its root directory.
+    let repository_name = repo
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Root:"))
+        .and_then(|root| Path::new(root.trim()).file_name())
+        .map(|name| name.to_string_lossy().into_owned())
+        .ok_or_else(|| io::Error::other("could not find the darcs repository root"))?;
+
+    Ok(Info {
+        specific: SpecificInfo::Darcs {
+            patch_hash,
+            extra: Some(DarcsExtraInfo { repository_name }),
+        },
+        source: Source::Repository,
+    })
+}
+
+pub(crate) fn write_specific_info(
+    file: &mut dyn Write,
+    patch_hash: &str,
+    extra: Option<&DarcsExtraInfo>,
+    _options: &GenerateOptions,
+    indent: &AutoIndent<'_>,
+) -> io::Result<()> {
+    writeln_indented!(
+        indent,
+        file,
+        "specific: version_control_info::SpecificInfo::Darcs {{"
+    )?;
+    {
+        let _indent = indent.increment();
+        writeln_indented!(indent, file, "patch_hash: {:?},", patch_hash)?;
+        write_extra_data(
+            file,
+            extra,
+            "version_control_info::darcs::ExtraData",
+            indent,
+            |file, extra, indent| {
+                writeln_indented!(
+                    indent,
+                    file,
+                    "repository_name: {:?},",
+                    extra.repository_name
+                )
+            },
+        )?;
+    }
+    writeln_indented!(indent, file, "}},")
+}
+
+#[inline]
+fn darcs<P: ?Sized + AsRef<Path>>(cwd: &P) -> Command {
+    #[inline(never)]
+    fn inner(cwd: &Path) -> Command {
+        let mut cmnd = Command::new("darcs");
+        cmnd.stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .current_dir(cwd);
+        cmnd
+    }
+
+    inner(cwd.as_ref())
+}
@@ -0,0 +1,129 @@
+use crate::{
+    AutoIndent, DetectOptions, FossilExtraInfo, GenerateOptions, Info, Source, SpecificInfo,
+    write_extra_data, write_tags,
+};
+use std::{
+    error::Error,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{Command, Output, Stdio},
+};
+
+// The checkout database is named `_FOSSIL_` on Windows, and `.fslckout` elsewhere,
+// but either name may be found on any platform.
+const CHECKOUT_FILE_NAMES: [&str; 2] = [".fslckout", "_FOSSIL_"];
+
+#[inline(always)]
+pub(crate) fn checkout_file<P: ?Sized + AsRef<Path>>(project_path: &P) -> Option<PathBuf> {
+    #[inline(never)]
+    fn inner(project_path: &Path) -> Option<PathBuf> {
+        CHECKOUT_FILE_NAMES
+            .iter()
+            .map(|file_name| project_path.join(file_name))
+            .find(|path| path.is_file())
+    }
+
+    inner(project_path.as_ref())
+}
+
+pub(crate) fn detect_fossil_checkout(
+    project_dir: &Path,
+    _options: &DetectOptions,
+) -> Result<Info, Box<dyn Error + Send + Sync + 'static>> {
+    fn handle_output(output: Output) -> Result<String, Box<dyn Error + Send + Sync + 'static>> {
+        if !output.status.success() {
+            let msg = format!("fossil failed: {}", String::from_utf8_lossy(&output.stderr));
+            return Err(From::from(io::Error::other(msg)));
+        }
+
+        String::from_utf8(output.stdout).map_err(From::from)
+    }
+
+    let info = fossil(project_dir).arg("info").spawn()?;
+    let branch = fossil(project_dir).args(["branch", "current"]).spawn()?;
+
+    let (info, branch) = (
+        handle_output(info.wait_with_output()?)?,
+        handle_output(branch.wait_with_output()?)?,
+    );
+
+    // `fossil info` prints a `key: value` pair on each line, e.g.
+    //
+    // checkout:     3e2d8b5c8e5d2a2b... 2024-01-01 12:00:00 UTC
+    // tags:         trunk, release
+    let field = |key: &str| {
+        info.lines().find_map(|line| {
+            let (line_key, value) = line.split_once(':')?;
+            (line_key.trim() == key).then(|| value.trim())
+        })
+    };
+
+    let checkout_hash = field("checkout")
+        .and_then(|checkout| checkout.split_whitespace().next())
+        .map(String::from)
+        .ok_or_else(|| io::Error::other("could not find the fossil checkout hash"))?;
+    let tags = field("tags")
+        .map(|tags| {
+            tags.split(',')
+                .map(str::trim)
+                .filter(|tag| !tag.is_empty())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(Info {
+        specific: SpecificInfo::Fossil {
+            checkout_hash,
+            extra: Some(FossilExtraInfo {
+                branch: branch.trim().to_owned(),
+                tags,
+            }),
+        },
+        source: Source::Repository,
+    })
+}
+
+pub(crate) fn write_specific_info(
+    file: &mut dyn Write,
+    checkout_hash: &str,
+    extra: Option<&FossilExtraInfo>,
+    options: &GenerateOptions,
+    indent: &AutoIndent<'_>,
+) -> io::Result<()> {
+    writeln_indented!(
+        indent,
+        file,
+        "specific: version_control_info::SpecificInfo::Fossil {{"
+    )?;
+    {
+        let _indent = indent.increment();
+        writeln_indented!(indent, file, "checkout_hash: {:?},", checkout_hash)?;
+        write_extra_data(
+            file,
+            extra,
+            "version_control_info::fossil::ExtraData",
+            indent,
+            |file, extra, indent| {
+                writeln_indented!(indent, file, "branch: {:?},", extra.branch)?;
+                write_tags(file, &extra.tags, options, indent)
+            },
+        )?;
+    }
+    writeln_indented!(indent, file, "}},")
+}
+
+#[inline]
+fn fossil<P: ?Sized + AsRef<Path>>(cwd: &P) -> Command {
+    #[inline(never)]
+    fn inner(cwd: &Path) -> Command {
+        let mut cmnd = Command::new("fossil");
+        cmnd.stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .current_dir(cwd);
+        cmnd
+    }
+
+    inner(cwd.as_ref())
+}
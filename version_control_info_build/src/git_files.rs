@@ -0,0 +1,611 @@
+use crate::{
+    Detail, DetectOptions, EmptyRepositoryError, GitHashAlgorithm, Info, Source, SpecificInfo, git,
+    overrides,
+};
+use miniz_oxide::inflate::decompress_to_vec_zlib_with_limit;
+use std::{
+    cmp::Ordering,
+    error::Error,
+    fs::{self, File},
+    io::{self, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+// git stops following symbolic refs after this many, as they may form a cycle.
+const MAX_SYMREF_DEPTH: usize = 5;
+// A tag may point to another tag, which is only followed this many times.
+const MAX_TAG_DEPTH: usize = 5;
+// Only the header of an object is needed, which is at its start.
+const MAX_OBJECT_PREFIX_LEN: usize = 4096;
+
+// Detects the git info of the repository by reading its files, rather than by running
+// git, so that it works where git isn't installed, and doesn't spawn any processes.
+//
+// Only `HEAD`, the refs and the headers of tagged objects are read, which give the
+// commit, the branch and the tags of the commit. Deltas in packs aren't resolved, so
+// the tags are unknown if a tag can't be peeled without resolving one. The working
+// tree isn't checked and the commit isn't read, so whether the repository is dirty,
+// and the timestamp of the commit, are unknown too. The rest of the info is filled in
+// as for a commit from the environment.
+pub(crate) fn detect(
+    project_dir: &Path,
+    options: &DetectOptions,
+) -> Result<Info, Box<dyn Error + Send + Sync + 'static>> {
+    let git_dir = match options.git_dir {
+        Some(ref git_dir) => git_dir.clone(),
+        None => git::resolve_git_dir(project_dir)?,
+    };
+    let refs = Refs::read(&git_dir)?;
+
+    let (branch, commit_hash) = match refs.head()? {
+        (branch, Some(commit_hash)) => (branch, commit_hash),
+        (_, None) => return Err(From::from(EmptyRepositoryError::new(git_dir))),
+    };
+    let hash_algorithm = GitHashAlgorithm::of(&commit_hash).ok_or_else(|| {
+        io::Error::other(format!(
+            "HEAD resolves to an invalid commit hash: {}",
+            commit_hash
+        ))
+    })?;
+
+    // the commit of the superproject, which may not be the commit of the submodule
+    // that it records. a configured git directory is never a submodule.
+    let superproject_commit = match git::superproject_dir(project_dir) {
+        Some(superproject_dir) if options.git_dir.is_none() => {
+            let superproject_refs = Refs::read(&git::resolve_git_dir(&superproject_dir)?)?;
+            superproject_refs.head()?.1
+        }
+        _ => None,
+    };
+
+    let mut extra = overrides::git_extra_info(&commit_hash, branch, options.short_hash_length)?;
+    extra.tags = match options.detail {
+        Detail::Quick => None,
+        Detail::Full => refs.tags_of(&commit_hash)?,
+    };
+    extra.dirty = None;
+    extra.commit_timestamp = None;
+    extra.superproject_commit = superproject_commit;
+    // only exists in a shallow clone, and is removed when its history is fetched
+    extra.shallow = refs.common_dir.join("shallow").exists();
+
+    Ok(Info {
+        specific: SpecificInfo::Git {
+            commit_hash,
+            hash_algorithm,
+            extra: Some(Box::new(extra)),
+        },
+        source: Source::Repository,
+    })
+}
+
+// The refs of a repository, which are either loose files in the `refs` directory, or
+// lines of `packed-refs`. A loose ref takes precedence over a packed ref of the same
+// name, as a packed ref which is updated is written as a loose ref.
+struct Refs {
+    git_dir: PathBuf,
+    common_dir: PathBuf,
+    packed: Vec<PackedRef>,
+    // every annotated tag in `packed-refs` is followed by the commit it points to
+    fully_peeled: bool,
+}
+
+// A line of `packed-refs`, e.g. `<hash> refs/tags/v1.0`, which is followed by a line
+// with the commit which an annotated tag points to, e.g. `^<hash>`.
+struct PackedRef {
+    name: String,
+    hash: String,
+    peeled: Option<String>,
+}
+
+impl Refs {
+    fn read(git_dir: &Path) -> io::Result<Self> {
+        let common_dir = git::resolve_common_dir(git_dir)?;
+        let packed_refs = match fs::read_to_string(common_dir.join("packed-refs")) {
+            Ok(packed_refs) => packed_refs,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+
+        let mut packed = Vec::<PackedRef>::new();
+        let mut fully_peeled = false;
+        for line in packed_refs.lines() {
+            // the header lists the traits of the file, e.g. `# pack-refs with: peeled`
+            if let Some(header) = line.strip_prefix('#') {
+                fully_peeled |= header.split_whitespace().any(|name| name == "fully-peeled");
+                continue;
+            }
+            if let Some(peeled) = line.strip_prefix('^') {
+                if let Some(tag) = packed.last_mut() {
+                    tag.peeled = Some(peeled.to_owned());
+                }
+            } else if let Some((hash, name)) = line.split_once(' ') {
+                packed.push(PackedRef {
+                    name: name.to_owned(),
+                    hash: hash.to_owned(),
+                    peeled: None,
+                });
+            }
+        }
+
+        Ok(Self {
+            git_dir: git_dir.to_path_buf(),
+            common_dir,
+            packed,
+            fully_peeled,
+        })
+    }
+
+    // Returns the branch of `HEAD`, and its commit, which a branch without any commits
+    // doesn't have yet. A detached `HEAD` has the branch `HEAD`, as with
+    // `git rev-parse --abbrev-ref HEAD`.
+    fn head(&self) -> io::Result<(String, Option<String>)> {
+        // `HEAD` is per worktree, so it is in the git directory rather than the common one
+        let head = fs::read_to_string(self.git_dir.join("HEAD"))?;
+        let head = head.trim_end();
+        match head.strip_prefix("ref:") {
+            Some(head_ref) => {
+                let head_ref = head_ref.trim_start();
+                let branch = head_ref.strip_prefix("refs/heads/").unwrap_or(head_ref);
+                Ok((branch.to_owned(), self.resolve(head_ref)?))
+            }
+            None => Ok((String::from("HEAD"), Some(head.to_owned()))),
+        }
+    }
+
+    // Returns the hash which the ref points to, following symbolic refs, or `None` if
+    // the ref doesn't exist.
+    fn resolve(&self, name: &str) -> io::Result<Option<String>> {
+        let mut name = name.to_owned();
+        for _ in 0..MAX_SYMREF_DEPTH {
+            let Some(value) = self.loose(&name)? else {
+                return Ok(self.packed(&name).map(|packed| packed.hash.clone()));
+            };
+            match value.strip_prefix("ref:") {
+                Some(target) => name = target.trim_start().to_owned(),
+                None => return Ok(Some(value)),
+            }
+        }
+        Err(io::Error::other(format!(
+            "too many levels of symbolic refs at {}",
+            name
+        )))
+    }
+
+    fn loose(&self, name: &str) -> io::Result<Option<String>> {
+        match fs::read_to_string(self.common_dir.join(name)) {
+            Ok(value) => Ok(Some(value.trim_end().to_owned())),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn packed(&self, name: &str) -> Option<&PackedRef> {
+        self.packed.iter().find(|packed| packed.name == name)
+    }
+
+    // Returns the names of the tags which point to the commit, sorted as with
+    // `git tag --points-at`, or `None` if an annotated tag can't be peeled.
+    fn tags_of(&self, commit_hash: &str) -> io::Result<Option<Vec<String>>> {
+        let mut loose = vec![];
+        loose_refs(&self.common_dir.join("refs").join("tags"), "", &mut loose)?;
+
+        let mut tags = vec![];
+        for (name, hash) in &loose {
+            // a loose tag which is the same as its packed ref can still be peeled
+            let packed = self.packed(&format!("refs/tags/{}", name));
+            match self.points_at(hash, packed, commit_hash)? {
+                Some(true) => tags.push(name.clone()),
+                Some(false) => {}
+                None => return Ok(None),
+            }
+        }
+        for packed in &self.packed {
+            let Some(name) = packed.name.strip_prefix("refs/tags/") else {
+                continue;
+            };
+            if loose.iter().any(|(loose_name, _)| loose_name == name) {
+                continue;
+            }
+            match self.points_at(&packed.hash, Some(packed), commit_hash)? {
+                Some(true) => tags.push(name.to_owned()),
+                Some(false) => {}
+                None => return Ok(None),
+            }
+        }
+
+        tags.sort();
+        Ok(Some(tags))
+    }
+
+    // Returns whether the tag with the hash points to the commit, or `None` if it isn't
+    // known. `packed-refs` records the commit of an annotated tag, so its object is only
+    // read if the tag isn't packed with the same hash. Otherwise annotated tags are
+    // followed until the commit, or an object which isn't a tag, is reached.
+    fn points_at(
+        &self,
+        hash: &str,
+        packed: Option<&PackedRef>,
+        commit_hash: &str,
+    ) -> io::Result<Option<bool>> {
+        if let Some(packed) = packed.filter(|packed| packed.hash == hash) {
+            match packed.peeled {
+                Some(ref peeled) => return Ok(Some(hash == commit_hash || peeled == commit_hash)),
+                None if self.fully_peeled => return Ok(Some(hash == commit_hash)),
+                None => {}
+            }
+        }
+
+        let mut hash = hash.to_owned();
+        for _ in 0..MAX_TAG_DEPTH {
+            if hash == commit_hash {
+                return Ok(Some(true));
+            }
+            match self.object(&hash)? {
+                Some(Object::Tag { target }) => hash = target,
+                Some(Object::Other) => return Ok(Some(false)),
+                None => return Ok(None),
+            }
+        }
+        Err(io::Error::other(format!(
+            "too many levels of annotated tags at {}",
+            hash
+        )))
+    }
+
+    // Returns the object, or `None` if it is neither a loose object nor a packed object
+    // which isn't a delta, e.g. if it is in an alternate object directory.
+    fn object(&self, hash: &str) -> io::Result<Option<Object>> {
+        // the hash is read from a ref, so it is checked before it is used as a path
+        if GitHashAlgorithm::of(hash).is_none() {
+            return Ok(None);
+        }
+        let objects_dir = self.common_dir.join("objects");
+        let (dir, file) = hash.split_at(2);
+        match fs::read(objects_dir.join(dir).join(file)) {
+            Ok(compressed) => return Ok(parse_loose_object(&inflate_prefix(&compressed))),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+
+        let entries = match fs::read_dir(objects_dir.join("pack")) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let name = (0..hash.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hash[i..i + 2], 16))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(io::Error::other)?;
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_none_or(|extension| extension != "idx") {
+                continue;
+            }
+            if let Some(offset) = find_in_pack_index(&fs::read(&path)?, &name) {
+                return read_pack_entry(&path.with_extension("pack"), offset);
+            }
+        }
+        Ok(None)
+    }
+}
+
+// An object, as far as it is needed to peel tags.
+enum Object {
+    Tag { target: String },
+    Other,
+}
+
+// Inflates the start of an object, which is all that is needed to read its header.
+// Packed objects are read in chunks, so the end of the data may be missing too.
+fn inflate_prefix(compressed: &[u8]) -> Vec<u8> {
+    match decompress_to_vec_zlib_with_limit(compressed, MAX_OBJECT_PREFIX_LEN) {
+        Ok(object) => object,
+        Err(e) => e.output,
+    }
+}
+
+// A loose object starts with its type and length, e.g. `tag 140\0`, which is followed
+// by its contents. Returns `None` if the object is malformed.
+fn parse_loose_object(object: &[u8]) -> Option<Object> {
+    let nul = object.iter().position(|&byte| byte == 0)?;
+    match object[..nul].starts_with(b"tag ") {
+        true => parse_tag_target(&object[nul + 1..]).map(|target| Object::Tag { target }),
+        false => Some(Object::Other),
+    }
+}
+
+// The contents of a tag object start with the object which it points to, e.g.
+// `object <hash>\ntype commit\n...`.
+fn parse_tag_target(tag: &[u8]) -> Option<String> {
+    let tag = String::from_utf8_lossy(tag);
+    let target = tag.lines().next()?.strip_prefix("object ")?;
+    Some(target.to_owned())
+}
+
+// A version 2 pack index starts with a magic number and its version, followed by the
+// number of names which start with each byte or a lower one, then the sorted names,
+// their checksums, and their offsets in the pack. Offsets with the high bit set are
+// indices into a table of 64 bit offsets, which comes last.
+fn find_in_pack_index(index: &[u8], name: &[u8]) -> Option<u64> {
+    const FANOUT_START: usize = 8;
+    const NAMES_START: usize = FANOUT_START + 256 * 4;
+
+    let u32_at = |pos: usize| {
+        Some(u32::from_be_bytes(
+            index.get(pos..pos + 4)?.try_into().ok()?,
+        ))
+    };
+    if index.get(..FANOUT_START)? != b"\xfftOc\0\0\0\x02" {
+        return None;
+    }
+    let first_byte = usize::from(*name.first()?);
+    let (mut low, mut high) = match first_byte {
+        0 => (0, u32_at(FANOUT_START)? as usize),
+        _ => (
+            u32_at(FANOUT_START + (first_byte - 1) * 4)? as usize,
+            u32_at(FANOUT_START + first_byte * 4)? as usize,
+        ),
+    };
+    let count = u32_at(FANOUT_START + 255 * 4)? as usize;
+
+    let name_at = |i: usize| index.get(NAMES_START + i * name.len()..)?.get(..name.len());
+    let position = loop {
+        if low >= high {
+            return None;
+        }
+        let mid = low + (high - low) / 2;
+        match name_at(mid)?.cmp(name) {
+            Ordering::Less => low = mid + 1,
+            Ordering::Greater => high = mid,
+            Ordering::Equal => break mid,
+        }
+    };
+
+    let offsets_start = NAMES_START + count * (name.len() + 4);
+    let offset = u32_at(offsets_start + position * 4)?;
+    if offset & 0x8000_0000 == 0 {
+        return Some(u64::from(offset));
+    }
+    let large_offset = offsets_start + count * 4 + (offset & 0x7fff_ffff) as usize * 8;
+    Some(u64::from_be_bytes(
+        index.get(large_offset..large_offset + 8)?.try_into().ok()?,
+    ))
+}
+
+// An entry in a pack starts with its type and length, in a variable length encoding
+// where the high bit of each byte is set if another byte follows, and the type is in
+// the three bits after it in the first byte. An object which isn't a delta of another
+// object is then deflated.
+fn read_pack_entry(path: &Path, offset: u64) -> io::Result<Option<Object>> {
+    const OBJ_COMMIT: u8 = 1;
+    const OBJ_TREE: u8 = 2;
+    const OBJ_BLOB: u8 = 3;
+    const OBJ_TAG: u8 = 4;
+
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    let mut entry = vec![];
+    file.take(MAX_OBJECT_PREFIX_LEN as u64)
+        .read_to_end(&mut entry)?;
+    let Some(header_len) = entry.iter().position(|&byte| byte & 0x80 == 0) else {
+        return Ok(None);
+    };
+
+    match (entry[0] >> 4) & 0x7 {
+        OBJ_TAG => {
+            let tag = inflate_prefix(&entry[header_len + 1..]);
+            Ok(parse_tag_target(&tag).map(|target| Object::Tag { target }))
+        }
+        OBJ_COMMIT | OBJ_TREE | OBJ_BLOB => Ok(Some(Object::Other)),
+        // deltas would have to be resolved against their bases
+        _ => Ok(None),
+    }
+}
+
+// Collects the names and hashes of the loose refs in `dir`, whose names start with
+// `prefix`. Refs may be nested, e.g. `refs/tags/release/v1.0`.
+fn loose_refs(dir: &Path, prefix: &str, refs: &mut Vec<(String, String)>) -> io::Result<()> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+
+    for entry in entries {
+        let entry = entry?;
+        // git doesn't create refs whose names aren't valid UTF-8
+        let Some(file_name) = entry.file_name().to_str().map(String::from) else {
+            continue;
+        };
+        let name = format!("{}{}", prefix, file_name);
+        if entry.file_type()?.is_dir() {
+            loose_refs(&entry.path(), &format!("{}/", name), refs)?;
+        } else {
+            let hash = fs::read_to_string(entry.path())?;
+            refs.push((name, hash.trim_end().to_owned()));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use miniz_oxide::deflate::compress_to_vec_zlib;
+    use std::{env, process};
+
+    const COMMIT: &str = "1111111111111111111111111111111111111111";
+    const OTHER_COMMIT: &str = "2222222222222222222222222222222222222222";
+    const PACKED_TAG: &str = "3333333333333333333333333333333333333333";
+    const LOOSE_TAG: &str = "4444444444444444444444444444444444444444";
+
+    // A git directory with only the files which are read, which is removed when dropped.
+    struct GitDir(PathBuf);
+
+    impl GitDir {
+        fn new(name: &str) -> Self {
+            let dir = env::temp_dir().join(format!(
+                "version_control_info_git_files_{}_{}",
+                name,
+                process::id()
+            ));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+
+        fn write(&self, path: &str, contents: &[u8]) {
+            let path = self.0.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+
+        fn write_object(&self, hash: &str, kind: &str, body: &str) {
+            let object = format!("{} {}\0{}", kind, body.len(), body);
+            let (dir, file) = hash.split_at(2);
+            self.write(
+                &format!("objects/{}/{}", dir, file),
+                &compress_to_vec_zlib(object.as_bytes(), 6),
+            );
+        }
+    }
+
+    impl Drop for GitDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn head_resolves_through_loose_and_packed_refs() {
+        let git_dir = GitDir::new("head");
+        git_dir.write("HEAD", b"ref: refs/heads/main\n");
+        git_dir.write(
+            "packed-refs",
+            format!(
+                "# pack-refs with: peeled fully-peeled sorted \n{} refs/heads/main\n",
+                OTHER_COMMIT
+            )
+            .as_bytes(),
+        );
+        let refs = Refs::read(&git_dir.0).unwrap();
+        assert_eq!(
+            refs.head().unwrap(),
+            (String::from("main"), Some(String::from(OTHER_COMMIT)))
+        );
+
+        // a loose ref takes precedence over a packed ref of the same name
+        git_dir.write("refs/heads/main", format!("{}\n", COMMIT).as_bytes());
+        let refs = Refs::read(&git_dir.0).unwrap();
+        assert_eq!(
+            refs.head().unwrap(),
+            (String::from("main"), Some(String::from(COMMIT)))
+        );
+
+        git_dir.write("HEAD", format!("{}\n", COMMIT).as_bytes());
+        let refs = Refs::read(&git_dir.0).unwrap();
+        assert_eq!(
+            refs.head().unwrap(),
+            (String::from("HEAD"), Some(String::from(COMMIT)))
+        );
+    }
+
+    #[test]
+    fn tags_are_peeled_from_packed_refs_and_loose_objects() {
+        let git_dir = GitDir::new("tags");
+        git_dir.write(
+            "packed-refs",
+            format!(
+                "# pack-refs with: peeled fully-peeled sorted \n\
+                 {PACKED_TAG} refs/tags/packed-annotated\n\
+                 ^{COMMIT}\n\
+                 {COMMIT} refs/tags/packed-lightweight\n\
+                 {OTHER_COMMIT} refs/tags/packed-other\n",
+            )
+            .as_bytes(),
+        );
+        git_dir.write("refs/tags/release/loose-lightweight", COMMIT.as_bytes());
+        git_dir.write("refs/tags/loose-annotated", LOOSE_TAG.as_bytes());
+        let tag = format!("object {}\ntype commit\ntag v1.0\n\nmessage\n", COMMIT);
+        git_dir.write_object(LOOSE_TAG, "tag", &tag);
+        git_dir.write_object(
+            COMMIT,
+            "commit",
+            "tree 5555555555555555555555555555555555555555\n",
+        );
+
+        let refs = Refs::read(&git_dir.0).unwrap();
+        assert_eq!(
+            refs.tags_of(COMMIT).unwrap(),
+            Some(vec![
+                String::from("loose-annotated"),
+                String::from("packed-annotated"),
+                String::from("packed-lightweight"),
+                String::from("release/loose-lightweight"),
+            ])
+        );
+        assert_eq!(
+            refs.tags_of(OTHER_COMMIT).unwrap(),
+            Some(vec![String::from("packed-other")])
+        );
+    }
+
+    #[test]
+    fn tags_are_unknown_if_an_object_is_missing() {
+        let git_dir = GitDir::new("missing");
+        git_dir.write("refs/tags/lightweight", COMMIT.as_bytes());
+        git_dir.write("refs/tags/unreadable", LOOSE_TAG.as_bytes());
+
+        let refs = Refs::read(&git_dir.0).unwrap();
+        assert_eq!(refs.tags_of(COMMIT).unwrap(), None);
+    }
+
+    #[test]
+    fn objects_are_found_in_pack_indexes() {
+        let names = [[0x00; 20], [0x12; 20], [0x12 + 1; 20], [0xff; 20]];
+        let offsets = [12_u32, 34, 0x8000_0000, 56];
+        let mut index = b"\xfftOc\0\0\0\x02".to_vec();
+        for byte in 0..=255_u8 {
+            let count = names.iter().filter(|name| name[0] <= byte).count() as u32;
+            index.extend(count.to_be_bytes());
+        }
+        names.iter().for_each(|name| index.extend(name));
+        names.iter().for_each(|_| index.extend([0; 4]));
+        offsets
+            .iter()
+            .for_each(|offset| index.extend(offset.to_be_bytes()));
+        index.extend(0x1_0000_0000_u64.to_be_bytes());
+
+        assert_eq!(find_in_pack_index(&index, &[0x00; 20]), Some(12));
+        assert_eq!(find_in_pack_index(&index, &[0x12; 20]), Some(34));
+        assert_eq!(find_in_pack_index(&index, &[0x13; 20]), Some(0x1_0000_0000));
+        assert_eq!(find_in_pack_index(&index, &[0xff; 20]), Some(56));
+        assert_eq!(find_in_pack_index(&index, &[0x11; 20]), None);
+        assert_eq!(find_in_pack_index(&index[..100], &[0xff; 20]), None);
+    }
+
+    #[test]
+    fn packed_tags_are_peeled_and_deltas_are_unknown() {
+        let git_dir = GitDir::new("pack");
+        let tag = format!("object {}\ntype commit\n", COMMIT);
+        let mut pack = b"PACK".to_vec();
+        // a tag, whose length fits in the first byte of its header
+        pack.push(0x40 | tag.len() as u8 & 0x0f);
+        pack.push((tag.len() >> 4) as u8);
+        pack[4] |= 0x80;
+        pack.extend(compress_to_vec_zlib(tag.as_bytes(), 6));
+        let delta_offset = pack.len() as u64;
+        // an offset delta
+        pack.extend([0x60, 0x00]);
+        git_dir.write("objects/pack/pack-test.pack", &pack);
+        let path = git_dir.0.join("objects/pack/pack-test.pack");
+
+        let Some(Object::Tag { target }) = read_pack_entry(&path, 4).unwrap() else {
+            panic!("the tag wasn't read");
+        };
+        assert_eq!(target, COMMIT);
+        assert!(read_pack_entry(&path, delta_offset).unwrap().is_none());
+    }
+}
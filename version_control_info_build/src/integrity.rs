@@ -0,0 +1,68 @@
+use crate::{Channel, Info, Origin, RepoHost};
+use std::hash::{Hash, Hasher};
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+// Computes the checksum which is checked by `version_control_info::integrity::verify()`.
+//
+// Every field of the runtime crate's `Info` is hashed, in the same order as its derived
+// `Hash` impl hashes them. The types of this crate are hashed with their own derived
+// `Hash` impls, which write the same bytes as those of the runtime crate only as long
+// as they declare the same fields and variants in the same order.
+pub(crate) fn checksum(
+    info: &Info,
+    origin: Origin,
+    host: Option<RepoHost>,
+    verified: bool,
+    channel: Option<Channel>,
+) -> u64 {
+    let mut hasher = Fnv1a(FNV_OFFSET_BASIS);
+    info.specific.hash(&mut hasher);
+    info.source.hash(&mut hasher);
+    origin.hash(&mut hasher);
+    host.hash(&mut hasher);
+    verified.hash(&mut hasher);
+    channel.hash(&mut hasher);
+    hasher.finish()
+}
+
+// The same as `Fnv1a` in the runtime crate, which may be built for a target with a
+// different pointer width or byte order than the build script.
+struct Fnv1a(u64);
+
+impl Hasher for Fnv1a {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write_u64(u64::from(i));
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write_u64(u64::from(i));
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_u64(i as i64 as u64);
+    }
+}
@@ -0,0 +1,119 @@
+use crate::{Channel, Info, Origin, RepoHost, SpecificInfo};
+use std::fmt::Write;
+
+pub(crate) const FILE_NAME: &str = "version_control_info.json";
+
+// The same as `Info::JSON_SCHEMA_VERSION` in the runtime crate.
+const SCHEMA_VERSION: u32 = 3;
+
+// Returns the info as a compact JSON object.
+//
+// This must produce exactly the same bytes as `Info::write_json()` in the runtime crate,
+// so that the sidecar file can be compared with the output of `Info::to_json()`.
+pub(crate) fn to_json(
+    info: &Info,
+    origin: Origin,
+    host: Option<RepoHost>,
+    verified: bool,
+    channel: Option<Channel>,
+) -> String {
+    let mut json = format!("{{\"schema\":{},\"kind\":", SCHEMA_VERSION);
+    write_string(&mut json, kind(&info.specific));
+    json.push_str(",\"commit\":");
+    write_string(&mut json, info.commit());
+    json.push_str(",\"branch\":");
+    match info.specific.branch() {
+        Some(branch) => write_string(&mut json, branch),
+        None => json.push_str("null"),
+    }
+    json.push_str(",\"tags\":");
+    match info.specific.tags() {
+        Some(tags) => {
+            json.push('[');
+            for (i, tag) in tags.iter().enumerate() {
+                if i > 0 {
+                    json.push(',');
+                }
+                write_string(&mut json, tag);
+            }
+            json.push(']');
+        }
+        None => json.push_str("null"),
+    }
+    json.push_str(",\"source\":");
+    write_string(&mut json, info.source.name());
+    json.push_str(",\"origin\":");
+    write_string(&mut json, origin.name());
+    json.push_str(",\"host\":");
+    match host {
+        Some(host) => write_string(&mut json, host.name()),
+        None => json.push_str("null"),
+    }
+    let _ = write!(json, ",\"verified\":{},\"channel\":", verified);
+    match channel {
+        Some(channel) => write_string(&mut json, channel_name(channel)),
+        None => json.push_str("null"),
+    }
+    json.push_str(",\"dirty\":");
+    match info.specific.dirty() {
+        Some(dirty) => {
+            let _ = write!(json, "{}", dirty);
+        }
+        None => json.push_str("null"),
+    }
+    json.push_str(",\"timestamp\":");
+    match info.specific.timestamp() {
+        Some(timestamp) => {
+            let _ = write!(json, "{}", timestamp);
+        }
+        None => json.push_str("null"),
+    }
+    json.push('}');
+    json
+}
+
+// The same names as `VcsKind::as_str()` in the runtime crate.
+fn kind(specific: &SpecificInfo) -> &'static str {
+    match *specific {
+        SpecificInfo::Git { .. } => "git",
+        SpecificInfo::Mercurial { .. } => "mercurial",
+        SpecificInfo::Jujutsu { .. } => "jj",
+        SpecificInfo::Pijul { .. } => "pijul",
+        SpecificInfo::Fossil { .. } => "fossil",
+        SpecificInfo::Subversion { .. } => "svn",
+        SpecificInfo::Bazaar { .. } => "bzr",
+        SpecificInfo::Darcs { .. } => "darcs",
+        SpecificInfo::Sapling { .. } => "sl",
+        SpecificInfo::Perforce { .. } => "p4",
+        SpecificInfo::PlasticScm { .. } => "plastic",
+        SpecificInfo::AndroidRepo { .. } => "repo",
+    }
+}
+
+// The same names as `Channel::as_str()` in the runtime crate.
+fn channel_name(channel: Channel) -> &'static str {
+    match channel {
+        Channel::Stable => "stable",
+        Channel::Beta => "beta",
+        Channel::Nightly => "nightly",
+        Channel::Dev => "dev",
+    }
+}
+
+fn write_string(json: &mut String, value: &str) {
+    json.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            '\u{0}'..='\u{1f}' => {
+                let _ = write!(json, "\\u{:04x}", ch as u32);
+            }
+            _ => json.push(ch),
+        }
+    }
+    json.push('"');
+}
@@ -0,0 +1,124 @@
+use crate::{
+    AutoIndent, DetectOptions, GenerateOptions, Info, JujutsuExtraInfo, Source, SpecificInfo,
+    write_extra_data, write_str_slice, write_tags,
+};
+use std::{
+    error::Error,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+// Each field is written on its own line, with list entries separated by spaces,
+// as neither bookmark nor tag names may contain whitespace.
+const LOG_TEMPLATE: &str = concat!(
+    r#"commit_id ++ "\n" ++ change_id ++ "\n""#,
+    r#" ++ local_bookmarks.map(|b| b.name()).join(" ") ++ "\n""#,
+    r#" ++ tags.map(|t| t.name()).join(" ") ++ "\n""#,
+);
+
+#[inline(always)]
+pub(crate) fn has_jj_folder<P: ?Sized + AsRef<Path>>(project_path: &P) -> io::Result<bool> {
+    #[inline(never)]
+    fn inner(project_path: &Path) -> io::Result<bool> {
+        if !project_path.join(".jj").is_dir() {
+            return Ok(false);
+        }
+
+        let output = jj(project_path).arg("root").spawn()?.wait_with_output()?;
+        Ok(output.status.success())
+    }
+
+    inner(project_path.as_ref())
+}
+
+#[inline]
+pub(crate) fn op_heads_dir(project_dir: &Path) -> PathBuf {
+    project_dir.join(".jj").join("repo").join("op_heads")
+}
+
+pub(crate) fn detect_jj_directory(
+    project_dir: &Path,
+    _options: &DetectOptions,
+) -> Result<Info, Box<dyn Error + Send + Sync + 'static>> {
+    let output = jj(project_dir)
+        .args(["log", "--no-graph", "-r", "@", "-T", LOG_TEMPLATE])
+        .spawn()?
+        .wait_with_output()?;
+
+    if !output.status.success() {
+        let msg = format!("jj failed: {}", String::from_utf8_lossy(&output.stderr));
+        return Err(From::from(io::Error::other(msg)));
+    }
+
+    let stdout = String::from_utf8(output.stdout)?;
+    let mut lines = stdout.lines();
+    let mut next_line = || {
+        lines
+            .next()
+            .ok_or_else(|| io::Error::other("unexpected output from jj log"))
+    };
+
+    let commit_id = next_line()?.to_owned();
+    let change_id = next_line()?.to_owned();
+    let bookmarks = next_line()?.split_whitespace().map(String::from).collect();
+    let tags = next_line()?.split_whitespace().map(String::from).collect();
+
+    Ok(Info {
+        specific: SpecificInfo::Jujutsu {
+            commit_id,
+            change_id,
+            extra: Some(JujutsuExtraInfo { bookmarks, tags }),
+        },
+        source: Source::Repository,
+    })
+}
+
+pub(crate) fn write_specific_info(
+    file: &mut dyn Write,
+    commit_id: &str,
+    change_id: &str,
+    extra: Option<&JujutsuExtraInfo>,
+    options: &GenerateOptions,
+    indent: &AutoIndent<'_>,
+) -> io::Result<()> {
+    writeln_indented!(
+        indent,
+        file,
+        "specific: version_control_info::SpecificInfo::Jujutsu {{"
+    )?;
+    {
+        let _indent = indent.increment();
+        writeln_indented!(indent, file, "commit_id: {:?},", commit_id)?;
+        writeln_indented!(indent, file, "change_id: {:?},", change_id)?;
+        write_extra_data(
+            file,
+            extra,
+            "version_control_info::jujutsu::ExtraData",
+            indent,
+            |file, extra, indent| {
+                write_str_slice(file, "bookmarks", &extra.bookmarks, indent)?;
+                write_tags(file, &extra.tags, options, indent)
+            },
+        )?;
+    }
+    writeln_indented!(indent, file, "}},")
+}
+
+#[inline]
+fn jj<P: ?Sized + AsRef<Path>>(cwd: &P) -> Command {
+    #[inline(never)]
+    fn inner(cwd: &Path) -> Command {
+        let mut cmnd = Command::new("jj");
+        // don't snapshot the working copy from a build script, as that would
+        // modify the repository.
+        cmnd.args(["--ignore-working-copy", "--no-pager", "--color=never"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .current_dir(cwd);
+        cmnd
+    }
+
+    inner(cwd.as_ref())
+}
@@ -1,342 +1,2837 @@
 #![deny(unsafe_code)]
 #![warn(unused)]
+#![warn(missing_docs)]
+
+//! # version_control_info_build
+//!
+//! This crate detects the version control info of a crate from its build script,
+//! and generates the files which the `version_control_info` crate embeds with its
+//! `get!()` and `try_get!()` macros.
+//!
+//! Call `detect()` to find the repository, then pass the detection to
+//! `generate_version_control_info()`. `emit_default()` does both, without failing the
+//! build when there is no repository.
 
 use crate::cargo_vcs_info::CargoVcsInfo;
+use atomic::AtomicFile;
 use git::has_git_folder;
+use jujutsu::has_jj_folder;
+use mercurial::has_hg_folder;
+use pijul::has_pijul_folder;
+use serde::{Deserialize, Serialize};
 use std::{
+    borrow::Cow,
     cell::Cell,
     env,
     error::Error,
-    ffi::OsStr,
-    fs::{self, File},
+    ffi::{OsStr, OsString},
+    fmt, fs,
+    hash::{Hash, Hasher},
     io::{self, Write, stdout},
     path::{Path, PathBuf},
+    process, ptr,
 };
 use util::rerun_cargo_if_changed;
 
-mod cargo_vcs_info;
-mod git;
-mod util;
+// Implements `name()` for an enum which mirrors one in the runtime crate, returning the
+// name of the variant there. The variants have the same names in both crates.
+macro_rules! runtime_names {
+    ($enum:ident { $($variant:ident),+ $(,)? }) => {
+        impl $enum {
+            #[inline]
+            pub(crate) fn name(&self) -> &'static str {
+                match *self {
+                    $(Self::$variant => stringify!($variant),)+
+                }
+            }
+        }
+    };
+}
+
+macro_rules! writeln_indented {
+    ($indent:expr, $out:expr, $msg:literal $(,)?) => {
+        writeln!($out, concat!("{__space:__indent$}", $msg), __space = ' ', __indent = $indent.num_chars())
+    };
+    ($indent:expr, $out:expr, $msg:literal, $($arg:expr),+ $(,)?) => {
+        writeln!($out, concat!("{__space:__indent$}", $msg), $($arg),+, __space = ' ', __indent = $indent.num_chars())
+    };
+}
+
+mod android_repo;
+mod atomic;
+mod bazaar;
+mod cache;
+mod cargo_vcs_info;
+mod ci;
+mod compression;
+mod darcs;
+mod fossil;
+mod git;
+mod git_files;
+mod integrity;
+mod json;
+mod jujutsu;
+mod manifest;
+mod mercurial;
+mod overrides;
+mod perforce;
+mod pijul;
+mod plastic;
+mod reproducible;
+mod sapling;
+mod snapshot;
+mod source_map;
+mod subversion;
+mod util;
+
+/// The version control info which was detected for a crate, returned by `detect()`.
+#[derive(Debug)]
+pub struct VersionControlDetection {
+    detected: DetectedInfo,
+    project_dir: PathBuf,
+    origin: Origin,
+    host: Option<RepoHost>,
+    git_dir: Option<PathBuf>,
+    colocated: Vec<Backend>,
+    colocated_info: Vec<Info>,
+    commit_verified: bool,
+    source_map: Option<source_map::SourceMap>,
+    env: BuildEnv,
+}
+
+impl VersionControlDetection {
+    /// The info of the repository, or `None` if the crate isn't in one.
+    #[inline]
+    #[must_use]
+    pub fn version_control_info(&self) -> Option<&Info> {
+        match self.detected {
+            DetectedInfo::VersionControl(ref info) => Some(info),
+            _ => None,
+        }
+    }
+
+    /// The directory which detection started from, usually `CARGO_MANIFEST_DIR`.
+    #[inline]
+    #[must_use]
+    pub fn project_dir(&self) -> &Path {
+        self.project_dir.as_path()
+    }
+
+    /// Where the crate was fetched from.
+    #[inline]
+    #[must_use]
+    pub fn origin(&self) -> Origin {
+        self.origin
+    }
+
+    /// The service which hosts the repository, worked out from its remote.
+    #[inline]
+    #[must_use]
+    pub fn host(&self) -> Option<RepoHost> {
+        self.host
+    }
+
+    /// The other backends which were found in the project directory, when
+    /// `DetectOptions::record_colocated()` is set.
+    #[inline]
+    #[must_use]
+    pub fn colocated_backends(&self) -> &[Backend] {
+        &self.colocated
+    }
+
+    // Whether the commit of `info` is known to exist in a repository. Info which was read
+    // from the repository always is, while the commit of info from other sources is
+    // only checked when `DetectOptions::verify_commit()` is set.
+    #[inline]
+    fn is_verified(&self, info: &Info) -> bool {
+        info.source == Source::Repository || self.commit_verified
+    }
+
+    /// The version control info of every backend which was detected, starting with
+    /// the primary info. Colocated backends are only detected when
+    /// `DetectOptions::record_colocated()` is set.
+    pub fn all_version_control_info(&self) -> impl Iterator<Item = &Info> {
+        self.version_control_info()
+            .into_iter()
+            .chain(&self.colocated_info)
+    }
+
+    /// Lets the build script change the detected info before it is generated, e.g. to
+    /// drop some tags, or to rewrite the branch. `f` is not called if no info was
+    /// detected, and the info of colocated backends is not passed to it.
+    ///
+    /// Any strings may be set, as every string is escaped when it is generated. The
+    /// commit can't be made empty though, and an error is returned if it is.
+    pub fn modify_info<F>(&mut self, f: F) -> Result<(), Box<dyn Error + Send + Sync + 'static>>
+    where
+        F: FnOnce(&mut Info) -> Result<(), Box<dyn Error + Send + Sync + 'static>>,
+    {
+        let info = match self.detected {
+            DetectedInfo::VersionControl(ref mut info) => info,
+            _ => return Ok(()),
+        };
+
+        f(info)?;
+        if info.commit().is_empty() {
+            return Err(From::from("the commit of the modified info is empty"));
+        }
+        if let SpecificInfo::Git {
+            ref commit_hash,
+            hash_algorithm,
+            ..
+        } = info.specific
+            && GitHashAlgorithm::of(commit_hash) != Some(hash_algorithm)
+        {
+            let msg = format!(
+                "the commit of the modified info is not a full {:?} hash",
+                hash_algorithm
+            );
+            return Err(From::from(msg));
+        }
+        Ok(())
+    }
+}
+
+/// Options for `detect_with()`, which choose what is detected and how.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct DetectOptions {
+    detail: Detail,
+    isolate_git_config: bool,
+    vendor_lookup: Option<VendorLookup>,
+    ci_providers: Vec<RegisteredCiProvider>,
+    detection_order: &'static [DetectionStep],
+    git_dir: Option<PathBuf>,
+    preferred_backend: Option<Backend>,
+    record_colocated: bool,
+    search_depth: usize,
+    record_tree_hash: bool,
+    record_parents: bool,
+    record_branch: bool,
+    record_timestamp: bool,
+    record_dirty: bool,
+    record_subtree_split: bool,
+    record_identities: bool,
+    record_trailers: bool,
+    record_subject: bool,
+    record_index_tree: bool,
+    record_upstream: bool,
+    record_tag_targets: bool,
+    record_tag_version: bool,
+    record_exact_tag: bool,
+    record_submodules: bool,
+    record_notes: bool,
+    record_commit_count: bool,
+    record_root_commits: bool,
+    record_dirty_files: bool,
+    verify_signatures: bool,
+    verify_commit: bool,
+    short_hash_length: Option<usize>,
+    shared_cache: bool,
+    persistent_cache: bool,
+    read_git_files: bool,
+    fallback_on_missing_tool: bool,
+    remote_name: Option<String>,
+    git_command: ToolCommand,
+    hg_command: ToolCommand,
+    env: BuildEnv,
+}
+
+impl Default for DetectOptions {
+    #[inline]
+    fn default() -> Self {
+        Self::full()
+    }
+}
+
+impl DetectOptions {
+    /// Options which only record the commit and the branch, with as few subprocesses as
+    /// possible.
+    ///
+    /// A git repository runs `git rev-parse` for the commit hash and for the branch.
+    /// The short hash is a prefix of the commit hash rather than the unambiguous one
+    /// which git picks, and whether the repository is shallow is read from the git
+    /// directory. The tags, `git describe`, the remote, the commit timestamp and
+    /// whether the working tree is dirty aren't recorded. Each of them can be turned
+    /// back on with the other options.
+    #[inline]
+    #[must_use]
+    pub const fn quick() -> Self {
+        Self {
+            detail: Detail::Quick,
+            isolate_git_config: false,
+            vendor_lookup: None,
+            ci_providers: Vec::new(),
+            detection_order: DetectionStep::DEFAULT_ORDER,
+            git_dir: None,
+            preferred_backend: None,
+            record_colocated: false,
+            search_depth: 0,
+            record_tree_hash: false,
+            record_parents: false,
+            record_branch: true,
+            record_timestamp: false,
+            record_dirty: false,
+            record_subtree_split: false,
+            record_identities: false,
+            record_trailers: false,
+            record_subject: false,
+            record_index_tree: false,
+            record_upstream: false,
+            record_tag_targets: false,
+            record_tag_version: false,
+            record_exact_tag: false,
+            record_submodules: false,
+            record_notes: false,
+            record_commit_count: false,
+            record_root_commits: false,
+            record_dirty_files: false,
+            verify_signatures: false,
+            verify_commit: false,
+            short_hash_length: None,
+            shared_cache: false,
+            persistent_cache: false,
+            read_git_files: false,
+            fallback_on_missing_tool: false,
+            remote_name: None,
+            git_command: ToolCommand::new(),
+            hg_command: ToolCommand::new(),
+            env: BuildEnv::new(),
+        }
+    }
+
+    /// The default options, which record the details of the commit and of the working
+    /// tree on top of what `quick()` records.
+    ///
+    /// On top of `quick()`, a git repository runs `git rev-parse` for the short hash,
+    /// the tree hash and whether the repository is shallow, `git log` for the parents
+    /// and the commit timestamp, `git status` to check whether the working tree is
+    /// dirty, `git tag` for the tags of the commit, `git describe` twice for its
+    /// description and the nearest tag, `git config` for the sparse checkout and
+    /// `git remote` for the url of the remote.
+    #[inline]
+    #[must_use]
+    pub const fn full() -> Self {
+        // the struct update syntax would drop the rest of `quick()`, which a const fn
+        // can't do
+        let mut options = Self::quick();
+        options.detail = Detail::Full;
+        options.record_tree_hash = true;
+        options.record_parents = true;
+        options.record_timestamp = true;
+        options.record_dirty = true;
+        options
+    }
+
+    /// Ignores the system and user git configuration files, so that only the
+    /// repository configuration is used. This makes detection independent of
+    /// the machine it is run on.
+    #[inline]
+    #[must_use]
+    pub const fn isolate_git_config(mut self, isolate_git_config: bool) -> Self {
+        self.isolate_git_config = isolate_git_config;
+        self
+    }
+
+    /// Sets a function which looks up the version control info of a vendored crate,
+    /// e.g. from a manifest recording where each vendored crate was taken from.
+    #[inline]
+    #[must_use]
+    pub const fn vendor_lookup(mut self, vendor_lookup: VendorLookupFn) -> Self {
+        self.vendor_lookup = Some(VendorLookup(vendor_lookup));
+        self
+    }
+
+    /// The order in which the sources of version control info are tried, until one of
+    /// them has it. Sources which are left out are never used, so e.g. leaving out
+    /// `DetectionStep::CiEnvironment` ignores the environment of CI jobs.
+    #[inline]
+    #[must_use]
+    pub const fn detection_order(mut self, detection_order: &'static [DetectionStep]) -> Self {
+        self.detection_order = detection_order;
+        self
+    }
+
+    /// Registers a provider which detects the commit from the environment of a CI service,
+    /// for checkouts without repository metadata. Registered providers are consulted in
+    /// order, before the built-in ones.
+    #[inline]
+    #[must_use]
+    pub fn ci_provider(mut self, provider: &'static dyn CiProvider) -> Self {
+        self.ci_providers.push(RegisteredCiProvider(provider));
+        self
+    }
+
+    /// Detects version control info from the git directory at `git_dir`, such as a
+    /// bare repository, rather than from a `.git` folder in the project directory.
+    ///
+    /// If this is not set, the `GIT_DIR` environment variable is used instead.
+    #[inline]
+    #[must_use]
+    pub fn git_dir<P: Into<PathBuf>>(mut self, git_dir: P) -> Self {
+        self.git_dir = Some(git_dir.into());
+        self
+    }
+
+    /// Uses `backend` if it is found, even when a backend which is usually preferred
+    /// is also found, e.g. to use mercurial in a colocated git and mercurial repository.
+    #[inline]
+    #[must_use]
+    pub const fn prefer_backend(mut self, backend: Backend) -> Self {
+        self.preferred_backend = Some(backend);
+        self
+    }
+
+    /// Checks for every backend, rather than stopping at the first one which is found,
+    /// so that colocated backends are recorded.
+    #[inline]
+    #[must_use]
+    pub const fn record_colocated(mut self, record_colocated: bool) -> Self {
+        self.record_colocated = record_colocated;
+        self
+    }
+
+    /// Searches up to `max_depth` parent directories of the cargo workspace for a
+    /// repository, if there is none in the workspace directory itself, e.g. when the
+    /// workspace is in a sub-directory of a monorepo.
+    #[inline]
+    #[must_use]
+    pub const fn search_parents(mut self, max_depth: usize) -> Self {
+        self.search_depth = max_depth;
+        self
+    }
+
+    /// Records the hash of the tree of the commit. This is on by default, apart from with
+    /// `DetectOptions::quick()`, and can be turned off to save a git command and the
+    /// bytes of the hash in the binary.
+    #[inline]
+    #[must_use]
+    pub const fn record_tree_hash(mut self, record_tree_hash: bool) -> Self {
+        self.record_tree_hash = record_tree_hash;
+        self
+    }
+
+    /// Records the hashes of the parents of the commit. Like the tree hash, this is on by
+    /// default, apart from with `DetectOptions::quick()`.
+    #[inline]
+    #[must_use]
+    pub const fn record_parents(mut self, record_parents: bool) -> Self {
+        self.record_parents = record_parents;
+        self
+    }
+
+    /// Records the branch of a git repository. This is on by default, and can be turned
+    /// off to save a git command, in which case the branch is left empty.
+    #[inline]
+    #[must_use]
+    pub const fn record_branch(mut self, record_branch: bool) -> Self {
+        self.record_branch = record_branch;
+        self
+    }
+
+    /// Records the committer date of the commit of a git repository. This is on by
+    /// default, apart from with `DetectOptions::quick()`, and can be turned off to save
+    /// a git command, in which case the timestamp is `None`.
+    #[inline]
+    #[must_use]
+    pub const fn record_timestamp(mut self, record_timestamp: bool) -> Self {
+        self.record_timestamp = record_timestamp;
+        self
+    }
+
+    /// Records whether the working tree of a git repository is dirty. This is on by
+    /// default, apart from with `DetectOptions::quick()`, and can be turned off to save
+    /// `git status`, which reads the whole
+    /// working tree, in which case the dirty flag is `None`, and the output of
+    /// `git describe` has no `-dirty` suffix.
+    #[inline]
+    #[must_use]
+    pub const fn record_dirty(mut self, record_dirty: bool) -> Self {
+        self.record_dirty = record_dirty;
+        self
+    }
+
+    /// Records the commit which a git repository was split from with `git subtree split`,
+    /// read from the `git-subtree-split:` trailer of `HEAD`, so that the build can be
+    /// traced back to the commit of the original repository.
+    #[inline]
+    #[must_use]
+    pub const fn record_subtree_split(mut self, record_subtree_split: bool) -> Self {
+        self.record_subtree_split = record_subtree_split;
+        self
+    }
+
+    /// Records the names and email addresses of the author and committer of the commit.
+    /// This is off by default, as it embeds personal information in the binary. Mercurial
+    /// revisions have no committer, so only their author is recorded.
+    #[inline]
+    #[must_use]
+    pub const fn record_identities(mut self, record_identities: bool) -> Self {
+        self.record_identities = record_identities;
+        self
+    }
+
+    /// Records the trailers at the end of the commit message, such as `Change-Id`.
+    /// This is off by default, as trailers such as `Signed-off-by` contain personal
+    /// information.
+    #[inline]
+    #[must_use]
+    pub const fn record_trailers(mut self, record_trailers: bool) -> Self {
+        self.record_trailers = record_trailers;
+        self
+    }
+
+    /// Records the subject line of the commit message, e.g. to show what a release
+    /// build was built from.
+    #[inline]
+    #[must_use]
+    pub const fn record_subject(mut self, record_subject: bool) -> Self {
+        self.record_subject = record_subject;
+        self
+    }
+
+    /// Records the tree of the git index when it differs from the tree of `HEAD`, so
+    /// that a build from partially staged changes can be told apart from a build of
+    /// the commit.
+    #[inline]
+    #[must_use]
+    pub const fn record_index_tree(mut self, record_index_tree: bool) -> Self {
+        self.record_index_tree = record_index_tree;
+        self
+    }
+
+    /// Records how many commits `HEAD` is ahead of and behind its upstream branch, e.g.
+    /// `origin/main`. The counts are as of the last fetch, as the remote isn't contacted.
+    #[inline]
+    #[must_use]
+    pub const fn record_upstream(mut self, record_upstream: bool) -> Self {
+        self.record_upstream = record_upstream;
+        self
+    }
+
+    /// Records the commit which each tag points to, after peeling annotated tags, so that
+    /// a tag which is moved after the build no longer matches its recorded commit.
+    #[inline]
+    #[must_use]
+    pub const fn record_tag_targets(mut self, record_tag_targets: bool) -> Self {
+        self.record_tag_targets = record_tag_targets;
+        self
+    }
+
+    /// Records the most recent `v`-prefixed tag which `HEAD` is descended from, parsed as a
+    /// semantic version, e.g. `v1.4.2-rc.1`. Nothing is recorded if that tag isn't a valid
+    /// version, such as `v1.4`.
+    #[inline]
+    #[must_use]
+    pub const fn record_tag_version(mut self, record_tag_version: bool) -> Self {
+        self.record_tag_version = record_tag_version;
+        self
+    }
+
+    /// Records the full ref of the tag which `HEAD` is exactly at, e.g. `refs/tags/v1.2.3`,
+    /// and whether it is an annotated tag. Like `git describe`, an annotated tag is chosen
+    /// over a lightweight one when the commit has several tags. As with the list of tags,
+    /// creating a new tag doesn't make cargo run the build script again by itself.
+    #[inline]
+    #[must_use]
+    pub const fn record_exact_tag(mut self, record_exact_tag: bool) -> Self {
+        self.record_exact_tag = record_exact_tag;
+        self
+    }
+
+    /// Records the path and the checked out commit of every submodule, including nested
+    /// ones, so that a build of a superproject can report the commits of its submodules.
+    #[inline]
+    #[must_use]
+    pub const fn record_submodules(mut self, record_submodules: bool) -> Self {
+        self.record_submodules = record_submodules;
+        self
+    }
+
+    /// Records the notes which are attached to the commit with `git notes`, such as
+    /// annotations which release tooling writes for a build. cargo only runs the build
+    /// script again when notes change if the repository already had notes when it last
+    /// ran, as there is nothing to watch before the first note is added.
+    #[inline]
+    #[must_use]
+    pub const fn record_notes(mut self, record_notes: bool) -> Self {
+        self.record_notes = record_notes;
+        self
+    }
+
+    /// Records the number of commits in the history of `HEAD`, which can be used as an
+    /// increasing build number, as long as the history is never rewritten. Counting
+    /// walks the whole history, which takes a while in very large repositories.
+    #[inline]
+    #[must_use]
+    pub const fn record_commit_count(mut self, record_commit_count: bool) -> Self {
+        self.record_commit_count = record_commit_count;
+        self
+    }
+
+    /// Records the commits in the history which have no parents, which identify the
+    /// repository even when it is cloned from mirrors with different urls. Like the
+    /// commit count, this walks the whole history.
+    #[inline]
+    #[must_use]
+    pub const fn record_root_commits(mut self, record_root_commits: bool) -> Self {
+        self.record_root_commits = record_root_commits;
+        self
+    }
+
+    /// Records the paths of the files which make the working tree dirty, so that it is
+    /// known which files a dirty build was built with changes to. Like the dirty flag,
+    /// this includes untracked files, so a working tree with many files which aren't
+    /// ignored can make the generated info large.
+    #[inline]
+    #[must_use]
+    pub const fn record_dirty_files(mut self, record_dirty_files: bool) -> Self {
+        self.record_dirty_files = record_dirty_files;
+        self
+    }
+
+    /// Verifies the signatures of the commit and of its tags, with the keys which git
+    /// is configured to trust on the machine which runs the build.
+    #[inline]
+    #[must_use]
+    pub const fn verify_signatures(mut self, verify_signatures: bool) -> Self {
+        self.verify_signatures = verify_signatures;
+        self
+    }
+
+    /// Checks that the commit exists in a git repository which contains the project, when
+    /// it was read from a file such as `.cargo_vcs_info.json` rather than from the
+    /// repository, so that the info records whether the commit was verified.
+    #[inline]
+    #[must_use]
+    pub const fn verify_commit(mut self, verify_commit: bool) -> Self {
+        self.verify_commit = verify_commit;
+        self
+    }
+
+    /// Sets the minimum length of the abbreviated commit hash. git lengthens it if it
+    /// would be ambiguous in the repository. If this is not set, the minimum length is
+    /// 7, whatever the `core.abbrev` configuration of the repository or the machine is,
+    /// so that the same commit is abbreviated the same way everywhere.
+    #[inline]
+    #[must_use]
+    pub const fn short_hash_length(mut self, length: usize) -> Self {
+        self.short_hash_length = Some(length);
+        self
+    }
+
+    /// Records the url of the remote with the given name, rather than `origin`. The
+    /// remote is also used to work out where the repository is hosted.
+    #[inline]
+    #[must_use]
+    pub fn remote_name<S: Into<String>>(mut self, remote_name: S) -> Self {
+        self.remote_name = Some(remote_name.into());
+        self
+    }
+
+    /// Shares the detected git info between the build scripts of a workspace, through a
+    /// cache in the target directory, so that git is only run by the first build script
+    /// and the rest reuse its result. The cache is invalidated when `HEAD`, the refs or
+    /// the index change. Unstaged changes don't invalidate it, so the dirty flag may be
+    /// out of date until something is staged or committed. Neither does fetching the
+    /// upstream branch, so the recorded upstream counts may be out of date as well.
+    #[inline]
+    #[must_use]
+    pub const fn shared_cache(mut self, shared_cache: bool) -> Self {
+        self.shared_cache = shared_cache;
+        self
+    }
+
+    /// Keeps the detected git info in `OUT_DIR`, so that when the build script is run
+    /// again, e.g. because another watched file or variable changed, git isn't run while
+    /// the repository is the same. It is invalidated in the same way as the shared cache,
+    /// which is used instead when both are enabled.
+    #[inline]
+    #[must_use]
+    pub const fn persistent_cache(mut self, persistent_cache: bool) -> Self {
+        self.persistent_cache = persistent_cache;
+        self
+    }
+
+    /// Reads the commit, the branch and the tags of a git repository from its `HEAD` and
+    /// refs, rather than running git, which is faster, and works without git installed.
+    /// The other git info isn't recorded: the working tree is assumed to be clean, the
+    /// timestamp is `SOURCE_DATE_EPOCH`, or zero, and annotated tags are only found once
+    /// their refs are packed.
+    #[inline]
+    #[must_use]
+    pub const fn read_git_files(mut self, read_git_files: bool) -> Self {
+        self.read_git_files = read_git_files;
+        self
+    }
+
+    /// When the program for the detected backend isn't installed, e.g. when building in
+    /// a sandbox without git, warns and falls back to the `.cargo_vcs_info.json` file, a
+    /// git archive, or a snapshot, rather than failing with a `MissingToolError`.
+    #[inline]
+    #[must_use]
+    pub const fn fallback_on_missing_tool(mut self, fallback_on_missing_tool: bool) -> Self {
+        self.fallback_on_missing_tool = fallback_on_missing_tool;
+        self
+    }
+
+    /// Runs `git` as described by `git_command`, rather than the `git` program in `PATH`.
+    #[inline]
+    #[must_use]
+    pub fn git_command(mut self, git_command: ToolCommand) -> Self {
+        self.git_command = git_command;
+        self
+    }
+
+    /// Runs `hg` as described by `hg_command`, rather than the `hg` program in `PATH`.
+    #[inline]
+    #[must_use]
+    pub fn hg_command(mut self, hg_command: ToolCommand) -> Self {
+        self.hg_command = hg_command;
+        self
+    }
+
+    /// Uses the given build environment, rather than reading it from the environment of
+    /// the process. The files are later generated into its out directory.
+    #[inline]
+    #[must_use]
+    pub fn env(mut self, env: BuildEnv) -> Self {
+        self.env = env;
+        self
+    }
+}
+
+/// Called with the manifest directory of a crate vendored with `cargo vendor`.
+pub type VendorLookupFn =
+    fn(&Path) -> Result<Option<SpecificInfo>, Box<dyn Error + Send + Sync + 'static>>;
+
+// Compares lookup functions by address, so that `DetectOptions` can stay comparable.
+#[derive(Clone, Copy, Debug)]
+struct VendorLookup(VendorLookupFn);
+
+impl PartialEq for VendorLookup {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        ptr::fn_addr_eq(self.0, other.0)
+    }
+}
+
+impl Eq for VendorLookup {}
+
+impl Hash for VendorLookup {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self.0 as usize).hash(state)
+    }
+}
+
+/// A source of version control info, which is tried in the order set by
+/// `DetectOptions::detection_order`. The lookup for vendored crates is always tried first,
+/// and the environment variable overrides are always applied last.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum DetectionStep {
+    /// The repository which contains the project.
+    Repository,
+    /// The `.cargo_vcs_info.json` file of a packaged crate.
+    CargoVcsInfoFile,
+    /// The `.git-commit-id` file of an archive made with `git archive`.
+    GitArchive,
+    /// The environment of a CI job, from the registered and built-in `CiProvider`s.
+    CiEnvironment,
+    /// The `.vcs_info_snapshot.json` file committed by the maintainers.
+    SnapshotFile,
+}
+
+impl DetectionStep {
+    /// The order which the steps are tried in by default.
+    pub const DEFAULT_ORDER: &'static [Self] = &[
+        Self::Repository,
+        Self::CargoVcsInfoFile,
+        Self::GitArchive,
+        Self::CiEnvironment,
+        Self::SnapshotFile,
+    ];
+}
+
+/// Detects the commit which a CI job is running for from the environment of the job.
+///
+/// Providers are only consulted for crates in the workspace, when no other version control
+/// info is found. There are built-in providers for GitHub Actions, GitLab CI, Jenkins,
+/// CircleCI, Buildkite and Azure Pipelines.
+pub trait CiProvider: Sync {
+    /// The name of the CI service, e.g. `Jenkins`.
+    fn name(&self) -> &'static str;
+
+    /// The environment variables which the provider reads, so that detection is rerun
+    /// when any of them change.
+    fn env_vars(&self) -> &'static [&'static str];
+
+    /// Returns the info of the commit, usually with `Source::Ci`, or `None` if the build
+    /// isn't running in this CI service.
+    fn detect(
+        &self,
+        options: &DetectOptions,
+    ) -> Result<Option<Info>, Box<dyn Error + Send + Sync + 'static>>;
+}
+
+// Compares providers by address, so that `DetectOptions` can stay comparable.
+#[derive(Clone, Copy)]
+struct RegisteredCiProvider(&'static dyn CiProvider);
+
+impl PartialEq for RegisteredCiProvider {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        ptr::addr_eq(self.0, other.0)
+    }
+}
+
+impl Eq for RegisteredCiProvider {}
+
+impl Hash for RegisteredCiProvider {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        ptr::from_ref(self.0).cast::<()>().hash(state)
+    }
+}
+
+impl fmt::Debug for RegisteredCiProvider {
+    #[inline]
+    fn fmt(&self, fmtr: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmtr.write_str(self.0.name())
+    }
+}
+
+/// A version control system which can be detected.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Backend {
+    /// Git, detected with `git`.
+    Git,
+    /// Mercurial, detected with `hg`.
+    Mercurial,
+    /// Jujutsu, detected with `jj`.
+    Jujutsu,
+    /// Pijul, detected with `pijul`.
+    Pijul,
+    /// Fossil, detected with `fossil`.
+    Fossil,
+    /// Subversion, detected with `svn`.
+    Subversion,
+    /// Bazaar or Breezy, detected with `brz` or `bzr`.
+    Bazaar,
+    /// Darcs, detected with `darcs`.
+    Darcs,
+    /// Sapling, detected with `sl`.
+    Sapling,
+    /// Perforce, detected with `p4`.
+    Perforce,
+    /// Plastic SCM, detected with `cm`.
+    PlasticScm,
+    /// An Android multi-repository client, detected with `repo`.
+    AndroidRepo,
+}
+
+impl Backend {
+    // The programs which are run to detect the backend, any of which may be installed.
+    const fn programs(self) -> &'static [&'static str] {
+        match self {
+            Self::Git => &["git"],
+            Self::Mercurial => &["hg"],
+            Self::Jujutsu => &["jj"],
+            Self::Pijul => &["pijul"],
+            Self::Fossil => &["fossil"],
+            Self::Subversion => &["svn"],
+            Self::Bazaar => &["brz", "bzr"],
+            Self::Darcs => &["darcs"],
+            Self::Sapling => &["sl"],
+            Self::Perforce => &["p4"],
+            Self::PlasticScm => &["cm"],
+            Self::AndroidRepo => &["repo"],
+        }
+    }
+}
+
+/// How much of the version control info is detected.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum Detail {
+    /// Only the commit and the branch, as set by `DetectOptions::quick()`.
+    Quick,
+    /// Everything which the backend can record, as set by `DetectOptions::full()`.
+    #[default]
+    Full,
+}
+
+/// Options for `generate_version_control_info_with()`, which choose what is generated.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct GenerateOptions {
+    tag_encoding: TagEncoding,
+    integrity_checksum: bool,
+    json: bool,
+    source_map: bool,
+    channel_rules: Option<ChannelRules>,
+    error_message: Option<String>,
+    redaction: Redaction,
+    failure_policy: FailurePolicy,
+    reproducible: bool,
+    compression_threshold: Option<usize>,
+    report: bool,
+}
+
+impl GenerateOptions {
+    /// The default options.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How the tags are written into the generated code.
+    #[inline]
+    #[must_use]
+    pub fn tag_encoding(mut self, tag_encoding: TagEncoding) -> Self {
+        self.tag_encoding = tag_encoding;
+        self
+    }
+
+    /// Writes a checksum of the generated info, which can be checked at runtime with
+    /// `version_control_info::debug_assert_integrity!()`.
+    #[inline]
+    #[must_use]
+    pub fn integrity_checksum(mut self, integrity_checksum: bool) -> Self {
+        self.integrity_checksum = integrity_checksum;
+        self
+    }
+
+    /// Writes the info to a `version_control_info.json` sidecar file in `OUT_DIR`, which
+    /// can be embedded with `version_control_info::get_json!()`, or copied next to the
+    /// built artifacts.
+    #[inline]
+    #[must_use]
+    pub fn json(mut self, json: bool) -> Self {
+        self.json = json;
+        self
+    }
+
+    /// Writes the path of the crate in its repository, and the path mappings of the
+    /// build, which can be read with `version_control_info::source_map!()`. They are only
+    /// written for dev profile builds, as they contain paths of the build machine.
+    #[inline]
+    #[must_use]
+    pub fn source_map(mut self, source_map: bool) -> Self {
+        self.source_map = source_map;
+        self
+    }
+
+    /// Compresses the large payloads of the info, such as the submodules, notes and dirty
+    /// files of a git repository, with DEFLATE when they are at least `threshold` bytes
+    /// long, to reduce the size of the binary. They are decompressed when they are read,
+    /// see `version_control_info::compression`.
+    #[inline]
+    #[must_use]
+    pub fn compression_threshold(mut self, threshold: usize) -> Self {
+        self.compression_threshold = Some(threshold);
+        self
+    }
+
+    /// Sets the target and the build time which `version_control_info::build_report!()`
+    /// reads. The build time is taken from `SOURCE_DATE_EPOCH` if it is set, and is the
+    /// current time otherwise, which makes every build different, so this is off by
+    /// default.
+    #[inline]
+    #[must_use]
+    pub fn report(mut self, report: bool) -> Self {
+        self.report = report;
+        self
+    }
+
+    /// Classifies the build into a release channel with the given rules, which can be
+    /// read from `version_control_info::Info::channel`.
+    #[inline]
+    #[must_use]
+    pub fn channel_rules(mut self, channel_rules: ChannelRules) -> Self {
+        self.channel_rules = Some(channel_rules);
+        self
+    }
+
+    /// Replaces the message of the compile error which `version_control_info::get!()`
+    /// fails with when there is no info, e.g. to tell developers how to fix their
+    /// checkout. `{reason}` in the message is replaced with the message which would
+    /// have been shown, and the message is escaped, so it may contain any text.
+    #[inline]
+    #[must_use]
+    pub fn error_message<S: Into<String>>(mut self, message: S) -> Self {
+        self.error_message = Some(message.into());
+        self
+    }
+
+    /// Leaves the given fields out of the generated info, and out of the JSON sidecar.
+    #[inline]
+    #[must_use]
+    pub fn redaction(mut self, redaction: Redaction) -> Self {
+        self.redaction = redaction;
+        self
+    }
+
+    /// Sets what is generated when no version control info is detected, or the
+    /// repository has no commits.
+    #[inline]
+    #[must_use]
+    pub fn failure_policy(mut self, failure_policy: FailurePolicy) -> Self {
+        self.failure_policy = failure_policy;
+        self
+    }
+
+    /// Makes the generated files identical for every build of the same commit, so that
+    /// builds are reproducible: timestamps are clamped to `SOURCE_DATE_EPOCH`, tags are
+    /// sorted, and paths of the build machine are left out, so the source map isn't
+    /// written.
+    #[inline]
+    #[must_use]
+    pub fn reproducible(mut self, reproducible: bool) -> Self {
+        self.reproducible = reproducible;
+        self
+    }
+
+    // The channel of the info, which is classified before it is redacted, so that the
+    // rules can match a redacted branch.
+    fn channel(&self, info: &Info) -> Option<Channel> {
+        self.channel_rules
+            .as_ref()
+            .map(|channel_rules| channel_rules.classify(info))
+    }
+
+    // The info which is written to the generated files.
+    fn prepare<'i>(&self, info: &'i Info) -> io::Result<Cow<'i, Info>> {
+        let mut info = self.redaction.redact(info);
+        if self.reproducible {
+            info = reproducible::normalize(info)?;
+        }
+        match self.compression_threshold {
+            Some(threshold) => Ok(compression::compress(info, threshold)),
+            None => Ok(info),
+        }
+    }
+}
+
+/// What is generated when no version control info is detected. `try_get!()` returns
+/// the error with every policy but `Unknown`.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum FailurePolicy {
+    /// `version_control_info::get!()` fails to compile.
+    #[default]
+    CompileError,
+    /// A warning is emitted, and `version_control_info::get!()` panics when it is
+    /// evaluated, so that crates which only use it at runtime still build.
+    Warn,
+    /// A warning is emitted, and placeholder info is embedded, which has
+    /// `Source::Unknown` and git's null hash as its commit.
+    Unknown,
+}
+
+/// Classifies builds into release channels by the branch and the tags of their commit,
+/// e.g. `ChannelRules::new().tag("v*", Channel::Stable).branch("beta/*", Channel::Beta)`.
+/// The rules are checked in the order in which they were added, and the first rule which
+/// matches decides the channel. A build which no rule matches is `Channel::Dev`.
+///
+/// In the patterns, `*` matches any run of characters, including `/`, and every other
+/// character only matches itself.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct ChannelRules {
+    rules: Vec<ChannelRule>,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum ChannelRule {
+    Branch { pattern: String, channel: Channel },
+    Tag { pattern: String, channel: Channel },
+}
+
+impl ChannelRules {
+    /// Rules which don't classify any builds.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Classifies builds of a branch which matches `pattern` as `channel`.
+    #[inline]
+    #[must_use]
+    pub fn branch<S: Into<String>>(mut self, pattern: S, channel: Channel) -> Self {
+        self.rules.push(ChannelRule::Branch {
+            pattern: pattern.into(),
+            channel,
+        });
+        self
+    }
+
+    /// Classifies builds of a commit which has a tag matching `pattern` as `channel`.
+    #[inline]
+    #[must_use]
+    pub fn tag<S: Into<String>>(mut self, pattern: S, channel: Channel) -> Self {
+        self.rules.push(ChannelRule::Tag {
+            pattern: pattern.into(),
+            channel,
+        });
+        self
+    }
+
+    fn classify(&self, info: &Info) -> Channel {
+        let branch = info.specific.branch();
+        let tags = info.specific.tags().unwrap_or_default();
+        self.rules
+            .iter()
+            .find_map(|rule| match *rule {
+                ChannelRule::Branch {
+                    ref pattern,
+                    channel,
+                } => branch
+                    .is_some_and(|branch| util::matches_pattern(pattern, branch))
+                    .then_some(channel),
+                ChannelRule::Tag {
+                    ref pattern,
+                    channel,
+                } => tags
+                    .iter()
+                    .any(|tag| util::matches_pattern(pattern, tag))
+                    .then_some(channel),
+            })
+            .unwrap_or(Channel::Dev)
+    }
+}
+
+/// Which fields are left out of the generated info, e.g. of binaries which are published,
+/// as the names of branches and tags may contain the names of internal projects. Unlike
+/// `generate_redacted_version_control_info()`, the commit is still embedded.
+///
+/// The release channel is classified before the info is redacted, so it can still depend
+/// on the branch and the tags.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Redaction {
+    branch: bool,
+    tags: bool,
+}
+
+impl Redaction {
+    /// Redacts nothing.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            branch: false,
+            tags: false,
+        }
+    }
+
+    /// Redacts the branch, and its closest equivalents, such as bookmarks and the
+    /// upstream branch. Fields which can't be left out are made empty.
+    #[inline]
+    #[must_use]
+    pub const fn branch(mut self, branch: bool) -> Self {
+        self.branch = branch;
+        self
+    }
+
+    /// Redacts the tags, and the fields which contain the name of a tag, such as the
+    /// output of `git describe`.
+    #[inline]
+    #[must_use]
+    pub const fn tags(mut self, tags: bool) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    fn redact<'i>(&self, info: &'i Info) -> Cow<'i, Info> {
+        if !self.branch && !self.tags {
+            return Cow::Borrowed(info);
+        }
+
+        let mut info = info.clone();
+        match info.specific {
+            SpecificInfo::Git {
+                extra: Some(ref mut extra),
+                ..
+            } => {
+                if self.branch {
+                    extra.branch.clear();
+                    extra.head_ref = None;
+                    extra.upstream = None;
+                }
+                if self.tags {
+                    extra.tags = None;
+                    extra.describe = None;
+                    extra.nearest_tag = None;
+                    extra.tag_version = None;
+                    extra.tag_targets = None;
+                    extra.tag_signatures = None;
+                    extra.exact_tag = None;
+                }
+            }
+            SpecificInfo::Mercurial {
+                extra: Some(ref mut extra),
+                ..
+            } => {
+                if self.branch {
+                    extra.branch.clear();
+                    extra.bookmarks.clear();
+                    extra.active_bookmark = None;
+                    extra.topic = None;
+                }
+                if self.tags {
+                    extra.tags.clear();
+                }
+            }
+            SpecificInfo::Jujutsu {
+                extra: Some(ref mut extra),
+                ..
+            } => {
+                if self.branch {
+                    extra.bookmarks.clear();
+                }
+                if self.tags {
+                    extra.tags.clear();
+                }
+            }
+            SpecificInfo::Fossil {
+                extra: Some(ref mut extra),
+                ..
+            } => {
+                if self.branch {
+                    extra.branch.clear();
+                }
+                if self.tags {
+                    extra.tags.clear();
+                }
+            }
+            SpecificInfo::Pijul {
+                extra: Some(ref mut extra),
+                ..
+            } if self.branch => extra.channel.clear(),
+            SpecificInfo::Bazaar {
+                extra: Some(ref mut extra),
+                ..
+            } if self.branch => extra.nick.clear(),
+            SpecificInfo::Sapling {
+                extra: Some(ref mut extra),
+                ..
+            } if self.branch => extra.bookmarks.clear(),
+            SpecificInfo::Perforce {
+                extra: Some(ref mut extra),
+                ..
+            } if self.branch => extra.stream = None,
+            SpecificInfo::PlasticScm {
+                extra: Some(ref mut extra),
+                ..
+            } if self.branch => extra.branch.clear(),
+            _ => {}
+        }
+        Cow::Owned(info)
+    }
+}
+
+/// The environment which cargo sets for build scripts. Anything which is not set here is
+/// read from the environment of the process, so drivers other than cargo, and tests, can
+/// run detection and generation without changing the environment of the process.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct BuildEnv {
+    out_dir: Option<PathBuf>,
+    cargo: Option<PathBuf>,
+    manifest_dir: Option<PathBuf>,
+}
+
+impl BuildEnv {
+    /// Reads everything from the environment which cargo sets for build scripts.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            out_dir: None,
+            cargo: None,
+            manifest_dir: None,
+        }
+    }
+
+    /// The directory which the generated files are written to, instead of `OUT_DIR`. The
+    /// shared cache is kept next to it, in the same way as next to `OUT_DIR`.
+    #[inline]
+    #[must_use]
+    pub fn out_dir<P: Into<PathBuf>>(mut self, out_dir: P) -> Self {
+        self.out_dir = Some(out_dir.into());
+        self
+    }
+
+    /// The cargo program which is run to locate the workspace, instead of `CARGO`.
+    #[inline]
+    #[must_use]
+    pub fn cargo<P: Into<PathBuf>>(mut self, cargo: P) -> Self {
+        self.cargo = Some(cargo.into());
+        self
+    }
+
+    /// The directory containing the `Cargo.toml` of the crate being built, instead of
+    /// `CARGO_MANIFEST_DIR`. The workspace is also located from this directory, rather
+    /// than from the current directory.
+    #[inline]
+    #[must_use]
+    pub fn manifest_dir<P: Into<PathBuf>>(mut self, manifest_dir: P) -> Self {
+        self.manifest_dir = Some(manifest_dir.into());
+        self
+    }
+
+    fn resolve_out_dir(&self) -> io::Result<PathBuf> {
+        self.out_dir
+            .clone()
+            .or_else(|| env::var_os("OUT_DIR").map(PathBuf::from))
+            .ok_or_else(|| io::Error::other("could not get out directory"))
+    }
+
+    fn resolve_manifest_dir(&self) -> Option<PathBuf> {
+        self.manifest_dir
+            .clone()
+            .or_else(|| env::var_os("CARGO_MANIFEST_DIR").map(PathBuf::from))
+    }
+}
+
+/// How a version control program is run, for build environments where it isn't in `PATH`,
+/// such as Nix or distroless images, or where it needs extra arguments or environment
+/// variables. The arguments come before those used for detection, and the environment
+/// variables which detection depends on can't be overridden.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct ToolCommand {
+    program: Option<PathBuf>,
+    args: Vec<OsString>,
+    envs: Vec<(OsString, OsString)>,
+}
+
+impl ToolCommand {
+    /// Runs the program from `PATH`, with no extra arguments or environment variables.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            program: None,
+            args: Vec::new(),
+            envs: Vec::new(),
+        }
+    }
+
+    /// The path of the program, instead of looking it up in `PATH`.
+    #[inline]
+    #[must_use]
+    pub fn program<P: Into<PathBuf>>(mut self, program: P) -> Self {
+        self.program = Some(program.into());
+        self
+    }
+
+    /// Adds an argument, which comes before the arguments used for detection.
+    #[inline]
+    #[must_use]
+    pub fn arg<S: Into<OsString>>(mut self, arg: S) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    /// Sets an environment variable for the program.
+    #[inline]
+    #[must_use]
+    pub fn env<K: Into<OsString>, V: Into<OsString>>(mut self, key: K, value: V) -> Self {
+        self.envs.push((key.into(), value.into()));
+        self
+    }
+
+    // Returns the command, running `default_program` from `PATH` if there is no program.
+    fn command(&self, default_program: &str) -> process::Command {
+        let program = match self.program {
+            Some(ref program) => program.as_os_str(),
+            None => OsStr::new(default_program),
+        };
+        let mut cmnd = process::Command::new(program);
+        cmnd.args(&self.args)
+            .envs(self.envs.iter().map(|(key, value)| (key, value)));
+        cmnd
+    }
+}
+
+/// How the tags are written into the generated code.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum TagEncoding {
+    /// An array of string literals.
+    #[default]
+    Literals,
+    /// A single string literal, which is sliced into each tag at compile time. This
+    /// makes the generated code smaller when there are many tags.
+    Interned,
+}
+
+/// The outcome of `emit_default()`.
+///
+/// The outcome is printed as the last line of the build script output, e.g.
+/// `version_control_info:outcome=found-repo`, so that build systems wrapping cargo
+/// can act on it. The names, and the exit codes, will not change between versions.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Outcome {
+    /// A repository was found, and its info was generated.
+    FoundRepository,
+    /// No repository was found, and the info was read from a fallback, such as
+    /// `.cargo_vcs_info.json`.
+    FallbackUsed,
+    /// `VERSION_CONTROL_INFO_REDACT` was set, and redacted info was generated.
+    Redacted,
+    /// Detection failed, and the error message was generated instead.
+    FailedSoftly,
+}
+
+impl Outcome {
+    /// The prefix of the line which the outcome is printed on.
+    pub const MARKER_PREFIX: &str = "version_control_info:outcome=";
+
+    /// The name of the outcome, e.g. `found-repo`.
+    #[inline]
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match *self {
+            Self::FoundRepository => "found-repo",
+            Self::FallbackUsed => "fallback-used",
+            Self::Redacted => "redacted",
+            Self::FailedSoftly => "failed-softly",
+        }
+    }
+
+    /// For wrappers which run detection outside of cargo. A build script should not
+    /// exit with these, as cargo fails the build on a non-zero exit code.
+    #[inline]
+    #[must_use]
+    pub const fn exit_code(&self) -> i32 {
+        match *self {
+            Self::FoundRepository => 0,
+            Self::FallbackUsed => 10,
+            Self::Redacted => 11,
+            Self::FailedSoftly => 12,
+        }
+    }
+}
+
+impl fmt::Display for Outcome {
+    #[inline]
+    fn fmt(&self, fmtr: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmtr.write_str(self.as_str())
+    }
+}
+
+/// Detects the version control info of the crate being built, with the default
+/// `DetectOptions`.
+#[inline]
+pub fn detect() -> Result<VersionControlDetection, Box<dyn Error + Send + Sync + 'static>> {
+    detect_with(&DetectOptions::default())
+}
+
+/// Detects the version control info with `DetectOptions::quick()`, which only records
+/// the commit and the branch, running as few subprocesses as possible.
+#[inline]
+pub fn detect_quick() -> Result<VersionControlDetection, Box<dyn Error + Send + Sync + 'static>> {
+    detect_with(&DetectOptions::quick())
+}
+
+/// Detects the version control info with `DetectOptions::full()`, which also records
+/// the tags, the description, the remote, the timestamp and whether the working tree
+/// is dirty. This is the same as `detect()`.
+#[inline]
+pub fn detect_full() -> Result<VersionControlDetection, Box<dyn Error + Send + Sync + 'static>> {
+    detect_with(&DetectOptions::full())
+}
+
+/// The `VERSION_CONTROL_INFO_COMMIT`, `_BRANCH`, `_TAGS` (comma separated), `_DIRTY` and
+/// `_TIMESTAMP` environment variables override the detected git info, so that builds
+/// without the repository metadata can still provide it.
+pub fn detect_with(
+    options: &DetectOptions,
+) -> Result<VersionControlDetection, Box<dyn Error + Send + Sync + 'static>> {
+    writeln!(stdout(), "cargo::rustc-cfg=VERSION_CONTROL_INFO_BUILD")?;
+    writeln!(stdout(), "cargo:rerun-if-env-changed=GIT_DIR")?;
+    let ci_vars = ci::providers(options).flat_map(|provider| provider.env_vars());
+    for var in ci_vars.chain(&overrides::VARS) {
+        writeln!(stdout(), "cargo:rerun-if-env-changed={}", var)?;
+    }
+
+    let workspace_dir = util::locate_project(&options.env)?;
+
+    // relative git directories are relative to the project, as git would be run from there
+    let git_dir = options
+        .git_dir
+        .clone()
+        .or_else(|| env::var_os("GIT_DIR").map(PathBuf::from))
+        .map(|git_dir| workspace_dir.join(git_dir));
+    let options = &DetectOptions {
+        git_dir: git_dir.clone(),
+        ..options.clone()
+    };
+
+    let manifest_dir = options
+        .env
+        .resolve_manifest_dir()
+        .unwrap_or_else(|| workspace_dir.clone());
+    let origin = util::package_origin(&options.env, &manifest_dir);
+
+    let vendored = match (origin, options.vendor_lookup) {
+        (Origin::Vendored, Some(VendorLookup(vendor_lookup))) => vendor_lookup(&manifest_dir)?,
+        _ => None,
+    };
+    let (project_dir, backends, detected, cached_remote_url) = match vendored {
+        Some(specific) => {
+            let detected = DetectedInfo::VersionControl(Info {
+                specific,
+                source: Source::VendorManifest,
+            });
+            (workspace_dir.clone(), vec![], Ok(detected), None)
+        }
+        None => {
+            // the repository is only searched for when it is used, so that a repository
+            // which isn't trusted can't make detection fail
+            let uses_repository = options.detection_order.contains(&DetectionStep::Repository);
+            let (project_dir, backends) = match uses_repository {
+                true => find_repository(workspace_dir.clone(), options)?,
+                false => (workspace_dir.clone(), vec![]),
+            };
+            let backend = backends.first().copied();
+            let detected = detect_in_order(&project_dir, backend, origin, options);
+            let (detected, remote_url) = match detected {
+                Ok((detected, remote_url)) => (Ok(detected), remote_url),
+                Err(e) => (Err(e), None),
+            };
+            (project_dir, backends, detected, remote_url)
+        }
+    };
+    let detected = match detected {
+        Ok(detected) => detected,
+        Err(e) => match e.downcast::<EmptyRepositoryError>() {
+            Ok(empty) => DetectedInfo::EmptyRepository {
+                metadata_dir: empty.metadata_dir,
+            },
+            // the commit from the environment doesn't need the repository, so a failure
+            // to detect it is only reported
+            Err(e) if env::var_os(overrides::COMMIT_VAR).is_some() => {
+                writeln!(
+                    stdout(),
+                    "cargo::warning=could not detect version control info, so `{}` is used: {}",
+                    overrides::COMMIT_VAR,
+                    e
+                )?;
+                DetectedInfo::NotFound
+            }
+            Err(e) => return Err(e),
+        },
+    };
+    let detected = overrides::apply(detected, options.short_hash_length)?;
+
+    // prefer the remote which the repository was cloned from, falling back to
+    // the `repository` key in the package manifest. The remote is cached along with the
+    // info when a cache is used, and is part of the full git info.
+    let remote_url = match (cached_remote_url, &detected) {
+        (Some(remote_url), _) => remote_url,
+        (None, DetectedInfo::VersionControl(info))
+            if matches!(
+                info.source,
+                Source::GitHubActions | Source::GitLabCi | Source::Ci
+            ) =>
+        {
+            match info.specific {
+                SpecificInfo::Git {
+                    extra: Some(ref extra),
+                    ..
+                } => extra.remote_url.clone(),
+                _ => None,
+            }
+        }
+        (None, DetectedInfo::VersionControl(info)) if info.source == Source::Repository => {
+            match info.specific {
+                SpecificInfo::Git {
+                    extra: Some(ref extra),
+                    ..
+                } => extra.remote_url.clone(),
+                SpecificInfo::Git { extra: None, .. } => git::remote_url(&project_dir, options)?,
+                _ => None,
+            }
+        }
+        _ => None,
+    };
+    let host = remote_url
+        .or_else(|| env::var("CARGO_PKG_REPOSITORY").ok())
+        .filter(|url| !url.is_empty())
+        .map(|url| util::classify_host(&url));
+
+    // a colocated backend which can't be detected shouldn't stop the primary info
+    // from being used, so failures are only reported.
+    let mut colocated_info = vec![];
+    if let DetectedInfo::VersionControl(_) = detected {
+        for &backend in backends.iter().skip(1) {
+            match detect_info(&project_dir, backend, options) {
+                Ok(info) => colocated_info.push(info),
+                Err(e) if e.is::<EmptyRepositoryError>() => {}
+                Err(e) => writeln!(
+                    stdout(),
+                    "cargo::warning=could not detect colocated {:?} info: {}",
+                    backend,
+                    e
+                )?,
+            }
+        }
+    }
+
+    // a commit which was read from a file is only asserted, but it can be checked
+    // against a repository which contains the project, if there is one
+    let commit_verified = match detected {
+        DetectedInfo::VersionControl(ref info)
+            if options.verify_commit
+                && info.source != Source::Repository
+                && matches!(info.specific, SpecificInfo::Git { .. }) =>
+        {
+            git::commit_exists(&project_dir, options, info.commit())?
+        }
+        _ => false,
+    };
 
-#[derive(Debug)]
-pub struct VersionControlDetection {
-    detected: DetectedInfo,
-    project_dir: PathBuf,
+    // only a crate which was detected from its repository has a path in it. git finds
+    // the repository from any directory in its work tree, so it may be further up.
+    let source_map = match detected {
+        DetectedInfo::VersionControl(ref info) if info.source == Source::Repository => {
+            let repo_root = match info.specific {
+                SpecificInfo::Git { .. } if git_dir.is_none() => git::work_tree_root(&project_dir),
+                _ => None,
+            };
+            let repo_root = repo_root.as_deref().unwrap_or(&project_dir);
+            source_map::detect(repo_root, &workspace_dir, &manifest_dir)
+        }
+        _ => None,
+    };
+
+    Ok(VersionControlDetection {
+        detected,
+        project_dir,
+        origin,
+        host,
+        git_dir,
+        colocated: backends.into_iter().skip(1).collect(),
+        colocated_info,
+        commit_verified,
+        source_map,
+        env: options.env.clone(),
+    })
 }
 
-impl VersionControlDetection {
-    #[inline]
-    #[must_use]
-    pub fn version_control_info(&self) -> Option<&Info> {
-        match self.detected {
-            DetectedInfo::VersionControl(ref info) => Some(info),
-            _ => None,
+// The order in which backends are checked when no backend is preferred.
+const DEFAULT_PRECEDENCE: [Backend; 12] = [
+    // a colocated jj repository leaves git with a detached `HEAD`, so
+    // prefer asking jj when it is in use.
+    Backend::Jujutsu,
+    Backend::Pijul,
+    // sapling can check out git repositories, so check for it before git.
+    Backend::Sapling,
+    // every project in a repo client is a git repository, but the client describes
+    // the whole checkout. `prefer_backend(Backend::Git)` restores the project's info.
+    Backend::AndroidRepo,
+    // prefer using the git folder directly if available, as it is probably
+    // more correct.
+    Backend::Git,
+    Backend::Mercurial,
+    Backend::Fossil,
+    Backend::Bazaar,
+    Backend::Darcs,
+    Backend::Subversion,
+    Backend::PlasticScm,
+    Backend::Perforce,
+];
+
+// Returns the closest directory to the workspace which has any backends in use, along
+// with those backends, searching as many parent directories as the options allow.
+//
+// If no backends are found, the workspace directory is returned, so that the
+// `.cargo_vcs_info.json` file can be checked.
+fn find_repository(
+    workspace_dir: PathBuf,
+    options: &DetectOptions,
+) -> io::Result<(PathBuf, Vec<Backend>)> {
+    for dir in workspace_dir.ancestors().take(options.search_depth + 1) {
+        let backends = find_backends(dir, options)?;
+        if !backends.is_empty() {
+            return Ok((dir.to_path_buf(), backends));
         }
     }
 
-    #[inline]
-    #[must_use]
-    pub fn project_dir(&self) -> &Path {
-        self.project_dir.as_path()
-    }
+    Ok((workspace_dir, vec![]))
 }
 
-pub fn detect() -> Result<VersionControlDetection, Box<dyn Error + Send + Sync + 'static>> {
-    writeln!(stdout(), "cargo::rustc-cfg=VERSION_CONTROL_INFO_BUILD")?;
+// Returns the backends which are in use in the project directory, in order of precedence.
+//
+// Unless colocated backends are recorded, only the first backend is returned.
+fn find_backends(project_dir: &Path, options: &DetectOptions) -> io::Result<Vec<Backend>> {
+    let preferred = options.preferred_backend;
+    let precedence = preferred.into_iter().chain(
+        DEFAULT_PRECEDENCE
+            .into_iter()
+            .filter(|&backend| Some(backend) != preferred),
+    );
 
-    let project_dir = util::locate_project()?;
+    let mut backends = vec![];
+    for backend in precedence {
+        let is_present = match backend {
+            // without the program, a backend is assumed to be in use if its metadata
+            // directory exists, so that detection reports the missing program
+            Backend::Jujutsu => match has_jj_folder(project_dir) {
+                Err(e) if is_missing_tool(backend, &e) => true,
+                result => result?,
+            },
+            Backend::Pijul => has_pijul_folder(project_dir),
+            Backend::Sapling => sapling::has_sl_folder(project_dir),
+            Backend::Git if options.read_git_files => {
+                options.git_dir.is_some() || git::find_dot_git(project_dir).is_some()
+            }
+            Backend::Git => match has_git_folder(project_dir, options) {
+                Err(e) if is_missing_tool(backend, &e) => {
+                    options.git_dir.is_some() || git::find_dot_git(project_dir).is_some()
+                }
+                result => result?,
+            },
+            Backend::Mercurial => has_hg_folder(project_dir),
+            Backend::Fossil => fossil::checkout_file(project_dir).is_some(),
+            Backend::Bazaar => bazaar::has_bzr_folder(project_dir),
+            Backend::Darcs => darcs::has_darcs_folder(project_dir),
+            Backend::Subversion => subversion::working_copy_root(project_dir).is_some(),
+            Backend::PlasticScm => plastic::workspace_root(project_dir).is_some(),
+            Backend::Perforce => perforce::client_root(project_dir)?.is_some(),
+            Backend::AndroidRepo => android_repo::client_root(project_dir).is_some(),
+        };
 
-    // prefer using the git folder directly if available, as it is probably
-    // more correct.
-    if has_git_folder(&project_dir)? {
-        return VersionControlDetection::detect_git_directory(&project_dir);
+        if is_present {
+            backends.push(backend);
+            if !options.record_colocated {
+                break;
+            }
+        }
     }
 
-    let vcs_info_file = project_dir.join(CargoVcsInfo::FILE_NAME);
-    if vcs_info_file.exists() {
-        let file = fs::File::open(&vcs_info_file).map(io::BufReader::new)?;
-        let vcs_info: CargoVcsInfo = serde_json::from_reader(file)?;
+    Ok(backends)
+}
 
-        if let Some(ref git_info) = vcs_info.git {
-            return Ok(VersionControlDetection {
-                detected: DetectedInfo::VersionControl(Info {
+// The remote url of the repository, if it was cached along with the info.
+type CachedRemoteUrl = Option<Option<String>>;
+
+// Tries each step of the detection order until one of them finds the info.
+fn detect_in_order(
+    project_dir: &Path,
+    backend: Option<Backend>,
+    origin: Origin,
+    options: &DetectOptions,
+) -> Result<(DetectedInfo, CachedRemoteUrl), Box<dyn Error + Send + Sync + 'static>> {
+    for &step in options.detection_order {
+        let info = match (step, backend) {
+            (DetectionStep::Repository, Some(backend)) => {
+                match detect_repository(project_dir, backend, options) {
+                    Ok((info, remote_url)) => {
+                        return Ok((DetectedInfo::VersionControl(info), remote_url));
+                    }
+                    Err(e) => match e.downcast_ref::<MissingToolError>() {
+                        Some(missing) if options.fallback_on_missing_tool => {
+                            writeln!(
+                                stdout(),
+                                "cargo::warning=could not find the `{}` program, so the {:?} \
+                                 repository at {} was not detected. Install it and make sure \
+                                 that it is in `PATH`.",
+                                missing.program(),
+                                missing.backend,
+                                missing.project_dir.display()
+                            )?;
+                            None
+                        }
+                        _ => return Err(e),
+                    },
+                }
+            }
+            (DetectionStep::Repository, None) => None,
+            (DetectionStep::CargoVcsInfoFile, _) => read_cargo_vcs_info_file(project_dir)?,
+            (DetectionStep::GitArchive, _) => {
+                git::read_archive_commit(project_dir)?.map(|(commit_hash, hash_algorithm)| Info {
                     specific: SpecificInfo::Git {
-                        commit_hash: git_info.sha1.clone(),
+                        commit_hash,
+                        hash_algorithm,
                         extra: None,
                     },
-                    source: Source::CargoVcsInfoFile,
-                }),
-                project_dir,
-            });
+                    source: Source::GitArchive,
+                })
+            }
+            // the CI job describes the checkout of the workspace, so it only applies to
+            // crates in it
+            (DetectionStep::CiEnvironment, _) if origin == Origin::Path => ci::detect(options)?,
+            (DetectionStep::CiEnvironment, _) => None,
+            (DetectionStep::SnapshotFile, _) => snapshot::read(project_dir)?,
+        };
+
+        if let Some(info) = info {
+            return Ok((DetectedInfo::VersionControl(info), None));
         }
     }
 
-    Ok(VersionControlDetection {
-        detected: DetectedInfo::NotFound,
-        project_dir,
-    })
+    Ok((DetectedInfo::NotFound, None))
 }
 
-macro_rules! writeln_indented {
-    ($indent:expr, $out:expr, $msg:literal $(,)?) => {
-        writeln!($out, concat!("{__space:__indent$}", $msg), __space = ' ', __indent = $indent.num_chars())
+fn detect_repository(
+    project_dir: &Path,
+    backend: Backend,
+    options: &DetectOptions,
+) -> Result<(Info, CachedRemoteUrl), Box<dyn Error + Send + Sync + 'static>> {
+    match backend {
+        // reading the files is as quick as reading the cache
+        Backend::Git
+            if (options.shared_cache || options.persistent_cache) && !options.read_git_files =>
+        {
+            let (info, remote_url) = cache::detect_git(project_dir, options, |options| {
+                let info = detect_info(project_dir, Backend::Git, options)?;
+                let remote_url = git::remote_url(project_dir, options)?;
+                Ok((info, remote_url))
+            })?;
+            Ok((info, Some(remote_url)))
+        }
+        _ => Ok((detect_info(project_dir, backend, options)?, None)),
+    }
+}
+
+fn detect_info(
+    project_dir: &Path,
+    backend: Backend,
+    options: &DetectOptions,
+) -> Result<Info, Box<dyn Error + Send + Sync + 'static>> {
+    detect_backend_info(project_dir, backend, options)
+        .map_err(|e| missing_tool_error(backend, project_dir, e))
+}
+
+fn detect_backend_info(
+    project_dir: &Path,
+    backend: Backend,
+    options: &DetectOptions,
+) -> Result<Info, Box<dyn Error + Send + Sync + 'static>> {
+    match backend {
+        Backend::Jujutsu => jujutsu::detect_jj_directory(project_dir, options),
+        Backend::Pijul => pijul::detect_pijul_directory(project_dir, options),
+        Backend::Sapling => sapling::detect_sl_directory(project_dir, options),
+        Backend::Git if options.read_git_files => git_files::detect(project_dir, options),
+        Backend::Git => git::detect_git_directory(project_dir, options),
+        Backend::Mercurial => mercurial::detect_hg_directory(project_dir, options),
+        Backend::Fossil => fossil::detect_fossil_checkout(project_dir, options),
+        Backend::Bazaar => bazaar::detect_bzr_directory(project_dir, options),
+        Backend::Darcs => darcs::detect_darcs_directory(project_dir, options),
+        Backend::Subversion => subversion::detect_svn_working_copy(project_dir, options),
+        Backend::PlasticScm => plastic::detect_plastic_workspace(project_dir, options),
+        Backend::Perforce => perforce::detect_p4_workspace(project_dir, options),
+        Backend::AndroidRepo => android_repo::detect_repo_client(project_dir, options),
+    }
+}
+
+fn read_cargo_vcs_info_file(
+    project_dir: &Path,
+) -> Result<Option<Info>, Box<dyn Error + Send + Sync + 'static>> {
+    let vcs_info_file = project_dir.join(CargoVcsInfo::FILE_NAME);
+    if !vcs_info_file.exists() {
+        return Ok(None);
+    }
+
+    let file = fs::File::open(&vcs_info_file).map(io::BufReader::new)?;
+    let vcs_info: CargoVcsInfo = serde_json::from_reader(file)?;
+    let Some(git_info) = vcs_info.git else {
+        return Ok(None);
     };
-    ($indent:expr, $out:expr, $msg:literal, $($arg:tt)+ $(,)?) => {
-        writeln!($out, concat!("{__space:__indent$}", $msg), $($arg)+, __space = ' ', __indent = $indent.num_chars())
+    let hash_algorithm = GitHashAlgorithm::of(&git_info.sha1).ok_or_else(|| {
+        let msg = format!("invalid commit hash in {}", vcs_info_file.display());
+        io::Error::new(io::ErrorKind::InvalidData, msg)
+    })?;
+    Ok(Some(Info {
+        specific: SpecificInfo::Git {
+            commit_hash: git_info.sha1,
+            hash_algorithm,
+            extra: None,
+        },
+        source: Source::CargoVcsInfoFile,
+    }))
+}
+
+/// Detects and generates version control info, without failing the build when
+/// detection fails. Setting the `VERSION_CONTROL_INFO_REDACT` environment variable
+/// generates redacted info instead.
+#[inline]
+pub fn emit_default() -> io::Result<Outcome> {
+    emit_default_with(&BuildEnv::new(), &GenerateOptions::default())
+}
+
+/// The same as `emit_default()`, with the given build environment and options.
+pub fn emit_default_with(env: &BuildEnv, options: &GenerateOptions) -> io::Result<Outcome> {
+    writeln!(stdout(), "cargo:rerun-if-env-changed={}", REDACT_VAR)?;
+
+    let outcome = if env::var_os(REDACT_VAR).is_some() {
+        generate_redacted_version_control_info_with(env, options)?;
+        Outcome::Redacted
+    } else {
+        match detect_with(&DetectOptions::default().env(env.clone())) {
+            Ok(detection) => {
+                generate_version_control_info_with(&detection, options)?;
+                match detection.version_control_info() {
+                    Some(Info {
+                        source: Source::Repository,
+                        ..
+                    }) => Outcome::FoundRepository,
+                    Some(_) => Outcome::FallbackUsed,
+                    None => Outcome::FailedSoftly,
+                }
+            }
+            Err(e) => {
+                writeln!(
+                    stdout(),
+                    "cargo::warning=could not detect version control info: {}",
+                    e
+                )?;
+                let out_dir = env.resolve_out_dir()?;
+                let mut get_info_file = create_get_vcs_info_file(&out_dir)?;
+                let mut try_get_info_file = create_try_get_vcs_info_file(&out_dir)?;
+                let reason = "version control info could not be detected";
+                generate_error(
+                    &mut get_info_file,
+                    &mut try_get_info_file,
+                    reason,
+                    &format!("Other {{ reason: {:?} }}", reason),
+                    false,
+                    options,
+                )?;
+                get_info_file.finish()?;
+                try_get_info_file.finish()?;
+                generate_without_info(&out_dir)?;
+                Outcome::FailedSoftly
+            }
+        }
     };
+
+    writeln!(stdout(), "{}{}", Outcome::MARKER_PREFIX, outcome)?;
+    Ok(outcome)
 }
 
+const REDACT_VAR: &str = "VERSION_CONTROL_INFO_REDACT";
+
+/// Generates redacted info, which fails to compile with `get!()`, and is an error with
+/// `try_get!()`.
+#[inline]
 pub fn generate_redacted_version_control_info() -> io::Result<()> {
-    let indent = Indenter::new(0);
-    let indent = indent.auto_indent();
+    generate_redacted_version_control_info_with(&BuildEnv::new(), &GenerateOptions::default())
+}
 
-    {
-        let mut bindings_file = create_get_vcs_info_file()?;
+/// The same as `generate_redacted_version_control_info()`, with the given build
+/// environment and options.
+pub fn generate_redacted_version_control_info_with(
+    env: &BuildEnv,
+    options: &GenerateOptions,
+) -> io::Result<()> {
+    if options.report {
+        emit_report_env()?;
+    }
+
+    let out_dir = env.resolve_out_dir()?;
+    let mut get_info_file = create_get_vcs_info_file(&out_dir)?;
+    let mut try_get_info_file = create_try_get_vcs_info_file(&out_dir)?;
+    generate_error(
+        &mut get_info_file,
+        &mut try_get_info_file,
+        "version control info has been redacted. use the `try_get!()` macro to fallibly \
+         access version control info.",
+        "Redacted",
+        false,
+        options,
+    )?;
+    get_info_file.finish()?;
+    try_get_info_file.finish()?;
 
-        write_header_comment(&mut bindings_file)?;
+    generate_without_info(&out_dir)
+}
+
+// Writes the files which can still be generated when there is no version control
+// info: an empty list for `get_all!()`, and no checksum or source map.
+fn generate_without_info(out_dir: &Path) -> io::Result<()> {
+    let mut get_all_info_file = create_get_all_vcs_info_file(out_dir)?;
+    write_header_comment(&mut get_all_info_file)?;
+    writeln!(
+        get_all_info_file,
+        "{{ const ALL: &[version_control_info::Info<'static>] = &[]; ALL }}"
+    )?;
+    get_all_info_file.finish()?;
+
+    let mut checksum_file = create_checksum_file(out_dir)?;
+    generate_checksum(&mut checksum_file, None)?;
+    checksum_file.finish()?;
+
+    let mut source_map_file = create_source_map_file(out_dir)?;
+    generate_source_map(&mut source_map_file, None)?;
+    source_map_file.finish()
+}
+
+/// Generates the files for the `version_control_info` macros from a detection, into
+/// `OUT_DIR`.
+#[inline]
+pub fn generate_version_control_info(detection: &VersionControlDetection) -> io::Result<()> {
+    generate_version_control_info_with(detection, &GenerateOptions::default())
+}
 
-        writeln_indented!(indent, bindings_file, "const {{")?;
+/// The same as `generate_version_control_info()`, with the given options.
+pub fn generate_version_control_info_with(
+    detection: &VersionControlDetection,
+    options: &GenerateOptions,
+) -> io::Result<()> {
+    fn generate_vcs_info(
+        file: &mut dyn Write,
+        info: &Info,
+        detection: &VersionControlDetection,
+        options: &GenerateOptions,
+        indent: &AutoIndent<'_>,
+        suffix: &str,
+    ) -> io::Result<()> {
+        writeln_indented!(indent, file, "version_control_info::Info {{")?;
         {
             let _indent = indent.increment();
-            writeln_indented!(indent, bindings_file, "compile_error!(concat!(")?;
-            {
-                let _indent = indent.increment();
-                writeln_indented!(
+            match options.prepare(info)?.specific {
+                SpecificInfo::Git {
+                    ref commit_hash,
+                    hash_algorithm,
+                    ref extra,
+                } => git::write_specific_info(
+                    file,
+                    commit_hash,
+                    hash_algorithm,
+                    extra.as_deref(),
+                    options,
                     indent,
-                    bindings_file,
-                    "\"version control info has been redacted. use the `try_get!()` macro\","
-                )?;
-                writeln_indented!(
+                )?,
+                SpecificInfo::Mercurial {
+                    ref global_revision,
+                    ref extra,
+                } => mercurial::write_specific_info(
+                    file,
+                    global_revision,
+                    extra.as_deref(),
+                    options,
                     indent,
-                    bindings_file,
-                    "\"to fallibly acces version control info.\","
-                )?;
+                )?,
+                SpecificInfo::Jujutsu {
+                    ref commit_id,
+                    ref change_id,
+                    ref extra,
+                } => jujutsu::write_specific_info(
+                    file,
+                    commit_id,
+                    change_id,
+                    extra.as_ref(),
+                    options,
+                    indent,
+                )?,
+                SpecificInfo::Pijul {
+                    ref state,
+                    ref extra,
+                } => pijul::write_specific_info(file, state, extra.as_ref(), options, indent)?,
+                SpecificInfo::Fossil {
+                    ref checkout_hash,
+                    ref extra,
+                } => fossil::write_specific_info(
+                    file,
+                    checkout_hash,
+                    extra.as_ref(),
+                    options,
+                    indent,
+                )?,
+                SpecificInfo::Bazaar {
+                    ref revision_id,
+                    ref extra,
+                } => {
+                    bazaar::write_specific_info(file, revision_id, extra.as_ref(), options, indent)?
+                }
+                SpecificInfo::Darcs {
+                    ref patch_hash,
+                    ref extra,
+                } => darcs::write_specific_info(file, patch_hash, extra.as_ref(), options, indent)?,
+                SpecificInfo::Subversion {
+                    ref revision,
+                    ref extra,
+                } => subversion::write_specific_info(
+                    file,
+                    revision,
+                    extra.as_ref(),
+                    options,
+                    indent,
+                )?,
+                SpecificInfo::Sapling {
+                    ref commit_hash,
+                    ref extra,
+                } => sapling::write_specific_info(
+                    file,
+                    commit_hash,
+                    extra.as_ref(),
+                    options,
+                    indent,
+                )?,
+                SpecificInfo::Perforce {
+                    ref changelist,
+                    ref extra,
+                } => perforce::write_specific_info(
+                    file,
+                    changelist,
+                    extra.as_ref(),
+                    options,
+                    indent,
+                )?,
+                SpecificInfo::PlasticScm {
+                    ref changeset,
+                    ref extra,
+                } => {
+                    plastic::write_specific_info(file, changeset, extra.as_ref(), options, indent)?
+                }
+                SpecificInfo::AndroidRepo {
+                    ref manifest_revision,
+                    ref extra,
+                } => android_repo::write_specific_info(
+                    file,
+                    manifest_revision,
+                    extra.as_ref(),
+                    options,
+                    indent,
+                )?,
+            }
+
+            writeln_indented!(
+                indent,
+                file,
+                "source: version_control_info::Source::{},",
+                info.source.name()
+            )?;
+            writeln_indented!(
+                indent,
+                file,
+                "origin: version_control_info::Origin::{},",
+                detection.origin.name()
+            )?;
+            match detection.host {
+                Some(host) => writeln_indented!(
+                    indent,
+                    file,
+                    "host: Some(version_control_info::RepoHost::{}),",
+                    host.name()
+                )?,
+                None => writeln_indented!(indent, file, "host: None,")?,
+            }
+            writeln_indented!(indent, file, "verified: {},", detection.is_verified(info))?;
+            match options.channel(info) {
+                Some(channel) => writeln_indented!(
+                    indent,
+                    file,
+                    "channel: Some(version_control_info::Channel::{}),",
+                    channel.name()
+                )?,
+                None => writeln_indented!(indent, file, "channel: None,")?,
             }
-            writeln_indented!(indent, bindings_file, "{:<4}))", ' ')?;
         }
-        writeln_indented!(indent, bindings_file, "}}")?;
+        writeln_indented!(indent, file, "}}{}", suffix)?;
+        Ok(())
+    }
+
+    fn generate_get(
+        get_info_file: &mut dyn Write,
+        info: &Info,
+        detection: &VersionControlDetection,
+        options: &GenerateOptions,
+    ) -> io::Result<()> {
+        write_header_comment(get_info_file)?;
+        let indent = Indenter::new(0);
+        let indent = indent.auto_indent();
 
-        bindings_file.flush()?;
+        writeln_indented!(indent, get_info_file, "const {{")?;
+        {
+            let _indent = indent.increment();
+            generate_vcs_info(get_info_file, info, detection, options, &indent, "")?;
+        }
+        writeln_indented!(indent, get_info_file, "}}")?;
+        Ok(())
     }
 
-    {
-        let mut bindings_file = create_try_get_vcs_info_file()?;
+    fn generate_try_get(
+        try_get_info_file: &mut dyn Write,
+        info: &Info,
+        detection: &VersionControlDetection,
+        options: &GenerateOptions,
+    ) -> io::Result<()> {
+        write_header_comment(try_get_info_file)?;
+        let indent = Indenter::new(0);
+        let indent = indent.auto_indent();
 
-        writeln_indented!(indent, bindings_file, "const {{")?;
+        writeln_indented!(indent, try_get_info_file, "const {{")?;
         {
             let _indent = indent.increment();
-            writeln_indented!(indent, bindings_file, "::core::result::Result::<")?;
+            writeln_indented!(indent, try_get_info_file, "::core::result::Result::<")?;
             {
                 let _indent = indent.increment();
-                writeln_indented!(indent, bindings_file, "version_control_info::Info<'_>,")?;
-                writeln_indented!(indent, bindings_file, "version_control_info::Error,")?;
+                writeln_indented!(indent, try_get_info_file, "version_control_info::Info<'_>,")?;
+                writeln_indented!(indent, try_get_info_file, "version_control_info::Error,")?;
             }
-            writeln_indented!(
-                indent,
-                bindings_file,
-                ">::Err(version_control_info::Error::Redacted)",
-            )?;
+            writeln_indented!(indent, try_get_info_file, ">::Ok(")?;
+            {
+                let _indent = indent.increment();
+                generate_vcs_info(try_get_info_file, info, detection, options, &indent, "")?;
+            }
+            writeln_indented!(indent, try_get_info_file, ")")?;
         }
-        writeln_indented!(indent, bindings_file, "}}")?;
+        writeln_indented!(indent, try_get_info_file, "}}")?;
 
-        bindings_file.flush()?;
+        Ok(())
     }
 
-    Ok(())
-}
+    fn vcs_info_paths(
+        detection: &VersionControlDetection,
+        info: &Info,
+    ) -> io::Result<Vec<PathBuf>> {
+        let project_dir = &detection.project_dir;
+        let paths = match info.source {
+            Source::Repository => match info.specific {
+                SpecificInfo::Git { ref extra, .. } => {
+                    let git_dir = detection.git_dir.as_deref();
+                    let mut paths = git::rerun_paths(project_dir, git_dir)?;
+                    let upstream = extra.as_deref().and_then(|extra| extra.upstream.as_ref());
+                    if let Some(upstream) = upstream {
+                        paths.extend(git::upstream_rerun_paths(project_dir, git_dir, upstream)?);
+                    }
+                    let submodules = extra
+                        .as_deref()
+                        .and_then(|extra| extra.submodules.as_deref());
+                    if let Some(submodules) = submodules {
+                        paths.extend(git::submodule_rerun_paths(project_dir, submodules)?);
+                    }
+                    paths
+                }
+                SpecificInfo::Mercurial { .. } => mercurial::rerun_paths(project_dir)?,
+                SpecificInfo::Jujutsu { .. } => vec![jujutsu::op_heads_dir(project_dir)],
+                SpecificInfo::Pijul { .. } => vec![pijul::pristine_dir(project_dir)],
+                SpecificInfo::Fossil { .. } => {
+                    fossil::checkout_file(project_dir).into_iter().collect()
+                }
+                SpecificInfo::Bazaar { .. } => bazaar::rerun_paths(project_dir),
+                SpecificInfo::Darcs { .. } => vec![darcs::inventory_file(project_dir)],
+                SpecificInfo::Subversion { .. } => subversion::working_copy_root(project_dir)
+                    .map(|root| subversion::wc_db_file(&root))
+                    .into_iter()
+                    .collect(),
+                SpecificInfo::Sapling { .. } => sapling::rerun_paths(project_dir),
+                // the have list is kept on the server, so there are no local files
+                // to watch. cargo will check the whole package instead.
+                SpecificInfo::Perforce { .. } => vec![],
+                SpecificInfo::PlasticScm { .. } => plastic::workspace_root(project_dir)
+                    .map(|root| plastic::rerun_paths(&root))
+                    .unwrap_or_default(),
+                SpecificInfo::AndroidRepo { ref extra, .. } => {
+                    match android_repo::client_root(project_dir) {
+                        Some(root) => android_repo::rerun_paths(&root, extra.as_ref())?,
+                        None => vec![],
+                    }
+                }
+            },
+            Source::CargoVcsInfoFile => vec![project_dir.join(CargoVcsInfo::FILE_NAME)],
+            Source::VendorManifest => vec![project_dir.join(util::VENDOR_CHECKSUM_FILE)],
+            Source::GitArchive => vec![project_dir.join(git::ARCHIVE_COMMIT_FILE)],
+            Source::SnapshotFile => vec![project_dir.join(snapshot::FILE_NAME)],
+            // the variables are already watched
+            Source::Environment
+            | Source::GitHubActions
+            | Source::GitLabCi
+            | Source::Ci
+            | Source::Unknown => vec![],
+        };
+        Ok(paths)
+    }
 
-pub fn generate_version_control_info(detection: &VersionControlDetection) -> io::Result<()> {
-    fn generate_git_vcs_info(
-        file: &mut dyn Write,
-        commit: &str,
-        extra: Option<&GitExtraInfo>,
-        source: &Source,
-        indent: &AutoIndent<'_>,
+    fn generate_get_all(
+        get_all_info_file: &mut dyn Write,
+        detection: &VersionControlDetection,
+        options: &GenerateOptions,
     ) -> io::Result<()> {
-        writeln_indented!(indent, file, "version_control_info::Info {{")?;
+        write_header_comment(get_all_info_file)?;
+        let indent = Indenter::new(0);
+        let indent = indent.auto_indent();
+
+        writeln_indented!(indent, get_all_info_file, "{{")?;
         {
             let _indent = indent.increment();
             writeln_indented!(
                 indent,
-                file,
-                "specific: version_control_info::SpecificInfo::Git {{"
+                get_all_info_file,
+                "const ALL: &[version_control_info::Info<'static>] = &["
             )?;
             {
                 let _indent = indent.increment();
-                writeln_indented!(indent, file, "commit_hash: \"{}\",", commit)?;
-                match extra {
-                    Some(extra) => {
-                        writeln_indented!(
-                            indent,
-                            file,
-                            "extra: Some(&version_control_info::git::ExtraData {{"
-                        )?;
-                        {
-                            let _indent = indent.increment();
-                            writeln_indented!(indent, file, "branch: \"{}\",", extra.branch)?;
-                            writeln_indented!(indent, file, "tags: &[")?;
-                            {
-                                let _indent = indent.increment();
-                                for tag in &extra.tags {
-                                    writeln_indented!(indent, file, "\"{}\",", tag)?;
-                                }
-                            }
-                            writeln_indented!(indent, file, "],")?;
-                        }
-                        writeln_indented!(indent, file, "}}),")?;
-                    }
-                    None => {
-                        writeln_indented!(indent, file, "extra: None,",)?;
-                    }
+                for info in detection.all_version_control_info() {
+                    generate_vcs_info(get_all_info_file, info, detection, options, &indent, ",")?;
                 }
             }
-            writeln_indented!(indent, file, "}},")?;
+            writeln_indented!(indent, get_all_info_file, "];")?;
+            writeln_indented!(indent, get_all_info_file, "ALL")?;
+        }
+        writeln_indented!(indent, get_all_info_file, "}}")?;
+
+        Ok(())
+    }
+
+    let out_dir = detection.env.resolve_out_dir()?;
+    let mut get_info_file = create_get_vcs_info_file(&out_dir)?;
+    let mut try_get_info_file = create_try_get_vcs_info_file(&out_dir)?;
+    let mut get_all_info_file = create_get_all_vcs_info_file(&out_dir)?;
+    let mut checksum_file = create_checksum_file(&out_dir)?;
+    let mut source_map_file = create_source_map_file(&out_dir)?;
+
+    if options.reproducible {
+        writeln!(stdout(), "cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH")?;
+    }
+    if options.report {
+        emit_report_env()?;
+    }
 
-            let source = match *source {
-                Source::Repository => "Repository",
-                Source::CargoVcsInfoFile => "CargoVcsInfoFile",
+    let failure = match detection.detected {
+        DetectedInfo::NotFound => {
+            let project_dir = detection.project_dir.display();
+            if options.failure_policy != FailurePolicy::CompileError {
+                writeln!(
+                    stdout(),
+                    "cargo::warning=could not find version control info for {}",
+                    project_dir
+                )?;
+            }
+            // the path of the crate differs between machines
+            let message = match env::var("CARGO_PKG_NAME") {
+                Ok(name) if options.reproducible => {
+                    format!("could not find version control info for `{}`", name)
+                }
+                Err(_) if options.reproducible => {
+                    String::from("could not find version control info for the crate")
+                }
+                _ => format!("could not find version control info for {}", project_dir),
             };
-            writeln_indented!(
-                indent,
-                file,
-                "source: version_control_info::Source::{},",
-                source
+            Some((message, "NoVersionControl"))
+        }
+        DetectedInfo::EmptyRepository { ref metadata_dir } => {
+            // cargo will check the whole directory when the first commit is made
+            writeln!(
+                stdout(),
+                "cargo:rerun-if-changed={}",
+                metadata_dir.display()
             )?;
+            writeln!(
+                stdout(),
+                "cargo::warning=the repository at {} has no commits, so version control info \
+                 will not be available until the first commit is made",
+                detection.project_dir.display()
+            )?;
+            Some((
+                String::from("the repository has no commits"),
+                "EmptyRepository",
+            ))
+        }
+        DetectedInfo::VersionControl(ref vcs_info) => {
+            for info in detection.all_version_control_info() {
+                for vcs_info_path in &vcs_info_paths(detection, info)? {
+                    rerun_cargo_if_changed(vcs_info_path)?;
+                }
+            }
+            generate_get(&mut get_info_file, vcs_info, detection, options)?;
+            generate_try_get(&mut try_get_info_file, vcs_info, detection, options)?;
+            let redacted = options.prepare(vcs_info)?;
+            let (verified, channel) = (detection.is_verified(vcs_info), options.channel(vcs_info));
+            let checksum = options.integrity_checksum.then(|| {
+                integrity::checksum(
+                    &redacted,
+                    detection.origin,
+                    detection.host,
+                    verified,
+                    channel,
+                )
+            });
+            generate_checksum(&mut checksum_file, checksum)?;
+            let source_map = detection
+                .source_map
+                .as_ref()
+                .filter(|_| options.source_map && !options.reproducible);
+            generate_source_map(&mut source_map_file, source_map)?;
+            let json = options.json.then(|| {
+                json::to_json(
+                    &redacted,
+                    detection.origin,
+                    detection.host,
+                    verified,
+                    channel,
+                )
+            });
+            generate_json_sidecar(&out_dir, json.as_deref())?;
+            None
+        }
+    };
+
+    match (failure, options.failure_policy) {
+        (None, _) => {}
+        (Some(_), FailurePolicy::Unknown) => {
+            writeln!(
+                stdout(),
+                "cargo::warning=placeholder version control info is embedded, as there is none"
+            )?;
+            let unknown = Info {
+                specific: SpecificInfo::Git {
+                    commit_hash: "0".repeat(40),
+                    hash_algorithm: GitHashAlgorithm::Sha1,
+                    extra: None,
+                },
+                source: Source::Unknown,
+            };
+            generate_get(&mut get_info_file, &unknown, detection, options)?;
+            generate_try_get(&mut try_get_info_file, &unknown, detection, options)?;
+            let unknown = options.prepare(&unknown)?;
+            let (verified, channel) = (detection.is_verified(&unknown), options.channel(&unknown));
+            let checksum = options.integrity_checksum.then(|| {
+                integrity::checksum(
+                    &unknown,
+                    detection.origin,
+                    detection.host,
+                    verified,
+                    channel,
+                )
+            });
+            generate_checksum(&mut checksum_file, checksum)?;
+            generate_source_map(&mut source_map_file, None)?;
+            let json = options.json.then(|| {
+                json::to_json(
+                    &unknown,
+                    detection.origin,
+                    detection.host,
+                    verified,
+                    channel,
+                )
+            });
+            generate_json_sidecar(&out_dir, json.as_deref())?;
+        }
+        (Some((message, error)), policy) => {
+            let at_runtime = policy == FailurePolicy::Warn;
+            generate_error(
+                &mut get_info_file,
+                &mut try_get_info_file,
+                &message,
+                error,
+                at_runtime,
+                options,
+            )?;
+            generate_checksum(&mut checksum_file, None)?;
+            generate_source_map(&mut source_map_file, None)?;
+            generate_json_sidecar(&out_dir, None)?;
         }
-        writeln_indented!(indent, file, "}}")?;
-        Ok(())
     }
 
-    fn generate_git_get(
-        get_info_file: &mut dyn Write,
-        commit: &str,
-        extra: Option<&GitExtraInfo>,
-        source: &Source,
-    ) -> io::Result<()> {
-        write_header_comment(get_info_file)?;
-        let indent = Indenter::new(0);
-        let indent = indent.auto_indent();
+    generate_get_all(&mut get_all_info_file, detection, options)?;
 
-        writeln_indented!(indent, get_info_file, "const {{")?;
+    get_info_file.finish()?;
+    try_get_info_file.finish()?;
+    get_all_info_file.finish()?;
+    checksum_file.finish()?;
+    source_map_file.finish()?;
+
+    Ok(())
+}
+
+// Writes the error for `try_get!()`, and makes `get!()` either fail to compile, or panic
+// when it is evaluated if `at_runtime` is set.
+fn generate_error(
+    get_info_file: &mut dyn Write,
+    try_get_info_file: &mut dyn Write,
+    message: &str,
+    error: &str,
+    at_runtime: bool,
+    options: &GenerateOptions,
+) -> io::Result<()> {
+    let indent = Indenter::new(0);
+    let indent = indent.auto_indent();
+
+    // the message is written as an escaped literal, so it can't break out of the macro
+    let message = match options.error_message {
+        Some(ref custom) => custom.replace("{reason}", message),
+        None => message.to_owned(),
+    };
+
+    write_header_comment(get_info_file)?;
+    if at_runtime {
+        writeln_indented!(
+            indent,
+            get_info_file,
+            "version_control_info::__private::missing_info({:?})",
+            message
+        )?;
+    } else {
+        writeln_indented!(
+            indent,
+            get_info_file,
+            "{{ compile_error!({:?}); }}",
+            message
+        )?;
+    }
+
+    write_header_comment(try_get_info_file)?;
+    writeln_indented!(indent, try_get_info_file, "const {{")?;
+    {
+        let _indent = indent.increment();
+        writeln_indented!(indent, try_get_info_file, "::core::result::Result::<")?;
         {
             let _indent = indent.increment();
-            generate_git_vcs_info(get_info_file, commit, extra, source, &indent)?;
+            writeln_indented!(indent, try_get_info_file, "version_control_info::Info<'_>,")?;
+            writeln_indented!(indent, try_get_info_file, "version_control_info::Error,")?;
         }
-        writeln_indented!(indent, get_info_file, "}}")?;
-        Ok(())
+        writeln_indented!(
+            indent,
+            try_get_info_file,
+            ">::Err(version_control_info::Error::{})",
+            error
+        )?;
     }
+    writeln_indented!(indent, try_get_info_file, "}}")
+}
 
-    fn generate_git_try_get(
-        try_get_info_file: &mut dyn Write,
-        commit: &str,
-        extra: Option<&GitExtraInfo>,
-        source: &Source,
-    ) -> io::Result<()> {
-        write_header_comment(try_get_info_file)?;
-        let indent = Indenter::new(0);
-        let indent = indent.auto_indent();
+fn generate_checksum(checksum_file: &mut dyn Write, checksum: Option<u64>) -> io::Result<()> {
+    write_header_comment(checksum_file)?;
+    match checksum {
+        Some(checksum) => writeln!(
+            checksum_file,
+            "::core::option::Option::<u64>::Some(0x{:016x})",
+            checksum
+        ),
+        None => writeln!(checksum_file, "::core::option::Option::<u64>::None"),
+    }
+}
 
-        writeln_indented!(indent, try_get_info_file, "const {{")?;
-        {
-            let _indent = indent.increment();
-            writeln_indented!(indent, try_get_info_file, "::core::result::Result::<")?;
-            {
-                let _indent = indent.increment();
-                writeln_indented!(
-                    indent,
-                    try_get_info_file,
-                    "version_control_info::Info<'_>,"
-                )?;
-                writeln_indented!(indent, try_get_info_file, "version_control_info::Error,")?;
-            }
-            writeln_indented!(indent, try_get_info_file, ">::Ok(")?;
+// Writes the source map for `version_control_info::source_map!()`, which is `None` if
+// there is no source map to embed.
+fn generate_source_map(
+    source_map_file: &mut dyn Write,
+    source_map: Option<&source_map::SourceMap>,
+) -> io::Result<()> {
+    write_header_comment(source_map_file)?;
+    source_map::write(source_map_file, source_map)
+}
+
+// Writes the JSON sidecar, or removes a stale one if there is no JSON to write.
+fn generate_json_sidecar(out_dir: &Path, json: Option<&str>) -> io::Result<()> {
+    let path = out_dir.join(json::FILE_NAME);
+    match json {
+        Some(json) => atomic::write(&path, json.as_bytes()),
+        None => match fs::remove_file(path) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        },
+    }
+}
+
+fn write_extra_data<T>(
+    file: &mut dyn Write,
+    extra: Option<&T>,
+    type_path: &str,
+    indent: &AutoIndent<'_>,
+    write_fields: impl FnOnce(&mut dyn Write, &T, &AutoIndent<'_>) -> io::Result<()>,
+) -> io::Result<()> {
+    match extra {
+        Some(extra) => {
+            writeln_indented!(indent, file, "extra: Some(&{} {{", type_path)?;
             {
                 let _indent = indent.increment();
-                generate_git_vcs_info(try_get_info_file, commit, extra, source, &indent)?;
+                write_fields(file, extra, indent)?;
             }
-            writeln_indented!(indent, try_get_info_file, ")")?;
+            writeln_indented!(indent, file, "}}),")
         }
-        writeln_indented!(indent, try_get_info_file, "}}")?;
-
-        Ok(())
+        None => writeln_indented!(indent, file, "extra: None,"),
     }
+}
 
-    let mut get_info_file = create_get_vcs_info_file()?;
-    let mut try_get_info_file = create_try_get_vcs_info_file()?;
+#[inline]
+fn write_str_slice(
+    file: &mut dyn Write,
+    field: &str,
+    items: &[String],
+    indent: &AutoIndent<'_>,
+) -> io::Result<()> {
+    let prefix = format!("{}: ", field);
+    write_str_slice_with(file, &prefix, items, ",", TagEncoding::Literals, indent)
+}
 
-    match detection.detected {
-        DetectedInfo::NotFound => {
-            write_header_comment(&mut get_info_file)?;
-            writeln!(
-                get_info_file,
-                "{{ compile_error!(\"could not find version control info for {}\"); }}",
-                detection.project_dir.display()
-            )?;
+#[inline]
+fn write_tags(
+    file: &mut dyn Write,
+    tags: &[String],
+    options: &GenerateOptions,
+    indent: &AutoIndent<'_>,
+) -> io::Result<()> {
+    write_str_slice_with(file, "tags: ", tags, ",", options.tag_encoding, indent)
+}
+
+fn write_optional_tags(
+    file: &mut dyn Write,
+    tags: Option<&[String]>,
+    options: &GenerateOptions,
+    indent: &AutoIndent<'_>,
+) -> io::Result<()> {
+    match tags {
+        Some(tags) => write_str_slice_with(
+            file,
+            "tags: Some(",
+            tags,
+            "),",
+            options.tag_encoding,
+            indent,
+        ),
+        None => writeln_indented!(indent, file, "tags: None,"),
+    }
+}
 
-            write_header_comment(&mut try_get_info_file)?;
-            writeln!(try_get_info_file, "const {{")?;
+fn write_str_slice_with(
+    file: &mut dyn Write,
+    prefix: &str,
+    items: &[String],
+    suffix: &str,
+    encoding: TagEncoding,
+    indent: &AutoIndent<'_>,
+) -> io::Result<()> {
+    match encoding {
+        TagEncoding::Literals => {
+            writeln_indented!(indent, file, "{}&[", prefix)?;
             {
-                writeln!(get_info_file, "{:<4}::core::result::Result::<", ' ')?;
-                {
-                    writeln!(get_info_file, "{:<8}version_control_info::Info<'_>,", ' ')?;
-                    writeln!(get_info_file, "{:<8}version_control_info::Error,", ' ')?;
+                let _indent = indent.increment();
+                for item in items {
+                    writeln_indented!(indent, file, "{:?},", item)?;
                 }
-                writeln!(
-                    get_info_file,
-                    "{:<4}>::Err(version_control_info::Error::NoVersionControl)",
-                    ' '
-                )?;
             }
-            writeln!(try_get_info_file, "}}")?;
+            writeln_indented!(indent, file, "]{}", suffix)
         }
-        DetectedInfo::VersionControl(ref vcs_info) => match vcs_info.specific {
-            SpecificInfo::Git {
-                ref commit_hash,
-                ref extra,
-            } => {
-                let vcs_info_path = {
-                    let final_comp = match vcs_info.source {
-                        Source::Repository => ".git",
-                        Source::CargoVcsInfoFile => CargoVcsInfo::FILE_NAME,
-                    };
-                    detection.project_dir.join(final_comp)
-                };
-                rerun_cargo_if_changed(&vcs_info_path)?;
-                generate_git_get(
-                    &mut get_info_file,
-                    &commit_hash,
-                    extra.as_ref(),
-                    &vcs_info.source,
-                )?;
-                generate_git_try_get(
-                    &mut try_get_info_file,
-                    &commit_hash,
-                    extra.as_ref(),
-                    &vcs_info.source,
-                )?;
+        TagEncoding::Interned => {
+            // store every item in a single string, and slice it up at compile time
+            writeln_indented!(indent, file, "{}{{", prefix)?;
+            {
+                let _indent = indent.increment();
+                writeln_indented!(indent, file, "const DATA: &str = {:?};", items.concat())?;
+                writeln_indented!(indent, file, "const ITEMS: &[&str] = &[")?;
+                {
+                    let _indent = indent.increment();
+                    let mut start = 0;
+                    for item in items {
+                        let end = start + item.len();
+                        writeln_indented!(
+                            indent,
+                            file,
+                            "version_control_info::__private::substr(DATA, {}, {}),",
+                            start,
+                            end
+                        )?;
+                        start = end;
+                    }
+                }
+                writeln_indented!(indent, file, "];")?;
+                writeln_indented!(indent, file, "ITEMS")?;
             }
-        },
+            writeln_indented!(indent, file, "}}{}", suffix)
+        }
     }
+}
 
-    get_info_file.flush()?;
-    try_get_info_file.flush()?;
+/// Appends a line correlating the artifact at `artifact_path` with `info` to the
+/// manifest at `manifest_path`, creating it if necessary. Each line has the form
+/// `<artifact-name> <sha256> <commit> <branch> <timestamp>`.
+#[inline]
+pub fn append_artifact_manifest<P: ?Sized + AsRef<Path>, Q: ?Sized + AsRef<Path>>(
+    manifest_path: &P,
+    artifact_path: &Q,
+    info: &Info,
+) -> io::Result<()> {
+    manifest::append(manifest_path.as_ref(), artifact_path.as_ref(), info)
+}
+
+/// Writes a `.vcs_info_snapshot.json` file into the workspace directory, for maintainers
+/// to commit. When a build has no version control metadata, e.g. when building from a
+/// tarball or in a sandbox, detection falls back to the snapshot.
+///
+/// The snapshot records the commit which it was generated from, so it is one commit
+/// behind once it has been committed. Nothing is written unless the info was read
+/// from a repository, so this can be called from every build.
+pub fn generate_snapshot(detection: &VersionControlDetection) -> io::Result<()> {
+    match detection.version_control_info() {
+        Some(
+            info @ Info {
+                source: Source::Repository,
+                ..
+            },
+        ) => snapshot::write(&util::locate_project(&detection.env)?, info),
+        _ => Ok(()),
+    }
+}
 
+/// Sets the `VERGEN_GIT_SHA`, `VERGEN_GIT_BRANCH` and `VERGEN_GIT_DESCRIBE` environment
+/// variables which the `vergen` crate sets, so that code which reads them with `env!()`
+/// keeps working while it is migrated. The first tag is used in place of the output of
+/// `git describe` if it was not collected, and the branch and describe variables are
+/// skipped if they are unknown.
+pub fn emit_vergen_env(info: &Info) -> io::Result<()> {
+    let mut stdout = stdout();
+    writeln!(stdout, "cargo:rustc-env=VERGEN_GIT_SHA={}", info.commit())?;
+    if let Some(branch) = info.specific.branch() {
+        writeln!(stdout, "cargo:rustc-env=VERGEN_GIT_BRANCH={}", branch)?;
+    }
+    let describe = match info.specific {
+        SpecificInfo::Git {
+            extra: Some(ref extra),
+            ..
+        } if extra.describe.is_some() => extra.describe.as_ref(),
+        _ => info.specific.tags().and_then(<[String]>::first),
+    };
+    if let Some(describe) = describe {
+        writeln!(stdout, "cargo:rustc-env=VERGEN_GIT_DESCRIBE={}", describe)?;
+    }
     Ok(())
 }
 
+// Sets the variables which `version_control_info::build_report!()` reads the target
+// and build time from.
+fn emit_report_env() -> io::Result<()> {
+    let mut stdout = stdout();
+    writeln!(stdout, "cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH")?;
+    if let Ok(target) = env::var("TARGET") {
+        writeln!(
+            stdout,
+            "cargo:rustc-env=VERSION_CONTROL_INFO_TARGET={}",
+            target
+        )?;
+    }
+    writeln!(
+        stdout,
+        "cargo:rustc-env=VERSION_CONTROL_INFO_BUILD_TIMESTAMP={}",
+        util::timestamp()?
+    )
+}
+
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 enum DetectedInfo {
     NotFound,
+    EmptyRepository { metadata_dir: PathBuf },
     VersionControl(Info),
 }
 
+#[derive(Debug)]
+struct EmptyRepositoryError {
+    metadata_dir: PathBuf,
+}
+
+impl EmptyRepositoryError {
+    #[inline]
+    fn new<P: Into<PathBuf>>(metadata_dir: P) -> Self {
+        Self {
+            metadata_dir: metadata_dir.into(),
+        }
+    }
+}
+
+impl fmt::Display for EmptyRepositoryError {
+    #[inline]
+    fn fmt(&self, fmtr: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            fmtr,
+            "the repository at {} has no commits",
+            self.metadata_dir.display()
+        )
+    }
+}
+
+impl Error for EmptyRepositoryError {}
+
+/// The program which is needed to detect a backend isn't installed, or isn't in `PATH`.
+#[derive(Debug)]
+pub struct MissingToolError {
+    backend: Backend,
+    project_dir: PathBuf,
+}
+
+impl MissingToolError {
+    #[inline]
+    fn new<P: Into<PathBuf>>(backend: Backend, project_dir: P) -> Self {
+        Self {
+            backend,
+            project_dir: project_dir.into(),
+        }
+    }
+
+    /// The backend whose program is missing.
+    #[inline]
+    #[must_use]
+    pub const fn backend(&self) -> Backend {
+        self.backend
+    }
+
+    /// The name of the program which could not be found.
+    #[inline]
+    #[must_use]
+    pub const fn program(&self) -> &'static str {
+        self.backend.programs()[0]
+    }
+
+    /// The directory which the backend was found in.
+    #[inline]
+    #[must_use]
+    pub fn project_dir(&self) -> &Path {
+        &self.project_dir
+    }
+}
+
+impl fmt::Display for MissingToolError {
+    #[inline]
+    fn fmt(&self, fmtr: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            fmtr,
+            "could not find the `{}` program, which is needed to detect the {:?} repository \
+             at {}. Install it and make sure that it is in `PATH`, or use \
+             `DetectOptions::fallback_on_missing_tool()` to fall back to the \
+             `.cargo_vcs_info.json` file, a git archive, or a snapshot",
+            self.program(),
+            self.backend,
+            self.project_dir.display()
+        )
+    }
+}
+
+impl Error for MissingToolError {}
+
+// A program which can't be spawned causes a `NotFound` error, but so does a missing
+// file, so the program is looked for in `PATH` to tell them apart.
+fn is_missing_tool(backend: Backend, error: &io::Error) -> bool {
+    error.kind() == io::ErrorKind::NotFound
+        && !backend
+            .programs()
+            .iter()
+            .any(|program| util::is_in_path(program))
+}
+
+fn missing_tool_error(
+    backend: Backend,
+    project_dir: &Path,
+    error: Box<dyn Error + Send + Sync + 'static>,
+) -> Box<dyn Error + Send + Sync + 'static> {
+    match error.downcast_ref::<io::Error>() {
+        Some(e) if is_missing_tool(backend, e) => {
+            Box::new(MissingToolError::new(backend, project_dir))
+        }
+        _ => error,
+    }
+}
+
+/// The detected version control info, which mirrors `version_control_info::Info`.
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct Info {
+    /// The version control system, and what was recorded about the commit.
     pub specific: SpecificInfo,
+    /// Where the info was read from.
     pub source: Source,
 }
 
 impl Info {
+    /// The identifier of the commit, as with `SpecificInfo::commit()`.
     #[inline]
     #[must_use]
     pub fn commit(&self) -> &str {
@@ -344,68 +2839,743 @@ impl Info {
     }
 }
 
+/// Where the sources of the crate were obtained from, as `version_control_info::Origin`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Origin {
+    /// The crate was downloaded from a registry.
+    Registry,
+    /// The crate was checked out from a git repository by cargo.
+    Git,
+    /// The crate was built from a local path.
+    Path,
+    /// The crate was vendored with `cargo vendor`.
+    Vendored,
+    /// The crate replaced another dependency through a `[patch]` section.
+    Patched,
+}
+
+runtime_names!(Origin {
+    Registry,
+    Git,
+    Path,
+    Vendored,
+    Patched,
+});
+
+/// The service which hosts the upstream repository, as
+/// `version_control_info::RepoHost`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum RepoHost {
+    /// GitHub, or a GitHub Enterprise server.
+    GitHub,
+    /// GitLab, or a self-managed GitLab server.
+    GitLab,
+    /// Bitbucket.
+    Bitbucket,
+    /// Gitea or Forgejo, including Codeberg.
+    Gitea,
+    /// Azure DevOps.
+    AzureDevOps,
+    /// A service which couldn't be identified.
+    Other,
+}
+
+runtime_names!(RepoHost {
+    GitHub,
+    GitLab,
+    Bitbucket,
+    Gitea,
+    AzureDevOps,
+    Other,
+});
+
+/// The release channel which the build was classified into, as
+/// `version_control_info::Channel`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Channel {
+    /// A release build.
+    Stable,
+    /// A release candidate, or another build which is tested before a release.
+    Beta,
+    /// A regular build of the development branch.
+    Nightly,
+    /// Any other build.
+    Dev,
+}
+
+runtime_names!(Channel {
+    Stable,
+    Beta,
+    Nightly,
+    Dev,
+});
+
+/// Where the version control info was read from, as `version_control_info::Source`.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum Source {
-    CargoVcsInfoFile,
+    /// The repository was read with its version control system.
     Repository,
+    /// The `.cargo_vcs_info.json` file of a published crate.
+    CargoVcsInfoFile,
+    /// The lookup function which was set with `DetectOptions::vendor_lookup()`.
+    VendorManifest,
+    /// The `.git-commit-id` file of a `git archive`.
+    GitArchive,
+    /// A snapshot written by `generate_snapshot()`.
+    SnapshotFile,
+    /// The `VERSION_CONTROL_INFO_*` environment variables.
+    Environment,
+    /// The environment of a GitHub Actions job.
+    GitHubActions,
+    /// The environment of a GitLab CI job.
+    GitLabCi,
+    /// The environment of another CI provider.
+    Ci,
+    /// The source isn't known.
+    Unknown,
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+runtime_names!(Source {
+    Repository,
+    CargoVcsInfoFile,
+    VendorManifest,
+    GitArchive,
+    SnapshotFile,
+    Environment,
+    GitHubActions,
+    GitLabCi,
+    Ci,
+    Unknown,
+});
+
+/// The info which is specific to each version control system, as
+/// `version_control_info::SpecificInfo`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub enum SpecificInfo {
+    /// A git repository.
     Git {
+        /// The full hash of the commit.
+        commit_hash: String,
+        /// The hash algorithm of the repository.
+        // snapshots written before the algorithm was recorded were all SHA-1
+        #[serde(default)]
+        hash_algorithm: GitHashAlgorithm,
+        /// What else was recorded, which is `None` if only the commit is known.
+        // boxed, as it is much larger than the extra info of the other backends
+        extra: Option<Box<GitExtraInfo>>,
+    },
+    /// A Mercurial repository.
+    Mercurial {
+        /// The full hash of the changeset.
+        global_revision: String,
+        /// What else was recorded, which is `None` if only the changeset is known.
+        // boxed for the same reason as the extra info of git
+        extra: Option<Box<MercurialExtraInfo>>,
+    },
+    /// A Jujutsu repository.
+    Jujutsu {
+        /// The full commit id of the working copy commit.
+        commit_id: String,
+        /// The full change id of the working copy commit.
+        change_id: String,
+        /// The bookmarks and tags of the commit.
+        extra: Option<JujutsuExtraInfo>,
+    },
+    /// A Pijul repository.
+    Pijul {
+        /// The state hash of the current channel.
+        state: String,
+        /// The channel and its most recent change.
+        extra: Option<PijulExtraInfo>,
+    },
+    /// A Fossil repository.
+    Fossil {
+        /// The hash of the checked out check-in.
+        checkout_hash: String,
+        /// The branch and tags of the check-in.
+        extra: Option<FossilExtraInfo>,
+    },
+    /// A Subversion working copy.
+    Subversion {
+        /// The revision of the working copy.
+        revision: String,
+        /// The url of the working copy.
+        extra: Option<SubversionExtraInfo>,
+    },
+    /// A Bazaar or Breezy branch.
+    Bazaar {
+        /// The id of the revision.
+        revision_id: String,
+        /// The revision number and the nickname of the branch.
+        extra: Option<BazaarExtraInfo>,
+    },
+    /// A Darcs repository.
+    Darcs {
+        /// The hash of the latest patch.
+        patch_hash: String,
+        /// The name of the repository.
+        extra: Option<DarcsExtraInfo>,
+    },
+    /// A Sapling repository.
+    Sapling {
+        /// The full hash of the commit.
         commit_hash: String,
-        extra: Option<GitExtraInfo>,
+        /// The bookmarks of the commit.
+        extra: Option<SaplingExtraInfo>,
+    },
+    /// A Perforce workspace.
+    Perforce {
+        /// The number of the most recent changelist synced to the workspace.
+        changelist: String,
+        /// The client and stream of the workspace.
+        extra: Option<PerforceExtraInfo>,
+    },
+    /// A Plastic SCM workspace.
+    PlasticScm {
+        /// The number of the changeset loaded in the workspace.
+        changeset: String,
+        /// The branch and repository of the workspace.
+        extra: Option<PlasticScmExtraInfo>,
+    },
+    /// An Android `repo` client.
+    AndroidRepo {
+        /// The commit of the manifest repository.
+        manifest_revision: String,
+        /// The commit of each project in the client.
+        extra: Option<AndroidRepoExtraInfo>,
     },
 }
 
 impl SpecificInfo {
+    /// The identifier of the commit, in whichever form the version control system uses.
     #[inline]
     pub fn commit(&self) -> &str {
         match *self {
             SpecificInfo::Git {
                 ref commit_hash, ..
-            } => &commit_hash,
+            } => commit_hash,
+            SpecificInfo::Mercurial {
+                ref global_revision,
+                ..
+            } => global_revision,
+            SpecificInfo::Jujutsu { ref commit_id, .. } => commit_id,
+            SpecificInfo::Pijul { ref state, .. } => state,
+            SpecificInfo::Fossil {
+                ref checkout_hash, ..
+            } => checkout_hash,
+            SpecificInfo::Subversion { ref revision, .. } => revision,
+            SpecificInfo::Bazaar {
+                ref revision_id, ..
+            } => revision_id,
+            SpecificInfo::Darcs { ref patch_hash, .. } => patch_hash,
+            SpecificInfo::Sapling {
+                ref commit_hash, ..
+            } => commit_hash,
+            SpecificInfo::Perforce { ref changelist, .. } => changelist,
+            SpecificInfo::PlasticScm { ref changeset, .. } => changeset,
+            SpecificInfo::AndroidRepo {
+                ref manifest_revision,
+                ..
+            } => manifest_revision,
+        }
+    }
+
+    // Returns the closest equivalent to a git branch for the version control system.
+    // A branch which wasn't recorded, or was redacted, is empty.
+    pub(crate) fn branch(&self) -> Option<&str> {
+        let branch = match *self {
+            SpecificInfo::Git { ref extra, .. } => extra.as_ref().map(|extra| &*extra.branch),
+            SpecificInfo::Mercurial { ref extra, .. } => extra.as_ref().map(|extra| &*extra.branch),
+            SpecificInfo::Pijul { ref extra, .. } => extra.as_ref().map(|extra| &*extra.channel),
+            SpecificInfo::Fossil { ref extra, .. } => extra.as_ref().map(|extra| &*extra.branch),
+            SpecificInfo::Bazaar { ref extra, .. } => extra.as_ref().map(|extra| &*extra.nick),
+            SpecificInfo::Perforce { ref extra, .. } => {
+                extra.as_ref().and_then(|extra| extra.stream.as_deref())
+            }
+            SpecificInfo::PlasticScm { ref extra, .. } => {
+                extra.as_ref().map(|extra| &*extra.branch)
+            }
+            SpecificInfo::Jujutsu { .. }
+            | SpecificInfo::Subversion { .. }
+            | SpecificInfo::Darcs { .. }
+            | SpecificInfo::Sapling { .. }
+            | SpecificInfo::AndroidRepo { .. } => None,
+        };
+        branch.filter(|branch| !branch.is_empty())
+    }
+
+    // Returns the tags of the commit, for the version control systems which have them.
+    pub(crate) fn tags(&self) -> Option<&[String]> {
+        match *self {
+            SpecificInfo::Git { ref extra, .. } => {
+                extra.as_ref().and_then(|extra| extra.tags.as_deref())
+            }
+            SpecificInfo::Mercurial { ref extra, .. } => extra.as_ref().map(|extra| &*extra.tags),
+            SpecificInfo::Jujutsu { ref extra, .. } => extra.as_ref().map(|extra| &*extra.tags),
+            SpecificInfo::Fossil { ref extra, .. } => extra.as_ref().map(|extra| &*extra.tags),
+            SpecificInfo::Pijul { .. }
+            | SpecificInfo::Subversion { .. }
+            | SpecificInfo::Bazaar { .. }
+            | SpecificInfo::Darcs { .. }
+            | SpecificInfo::Sapling { .. }
+            | SpecificInfo::Perforce { .. }
+            | SpecificInfo::PlasticScm { .. }
+            | SpecificInfo::AndroidRepo { .. } => None,
+        }
+    }
+
+    // The same as `SpecificInfo::dirty()` in the runtime crate.
+    pub(crate) fn dirty(&self) -> Option<bool> {
+        match *self {
+            SpecificInfo::Git {
+                extra: Some(ref extra),
+                ..
+            } => extra.dirty,
+            SpecificInfo::Mercurial {
+                extra: Some(ref extra),
+                ..
+            } => Some(extra.dirty),
+            _ => None,
+        }
+    }
+
+    // The same as `SpecificInfo::timestamp()` in the runtime crate.
+    pub(crate) fn timestamp(&self) -> Option<i64> {
+        match *self {
+            SpecificInfo::Git {
+                extra: Some(ref extra),
+                ..
+            } => extra.commit_timestamp,
+            SpecificInfo::Mercurial {
+                extra: Some(ref extra),
+                ..
+            } => extra.commit_timestamp,
+            _ => None,
         }
     }
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// What was recorded about the commit of a git repository, as
+/// `version_control_info::git::ExtraData`.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub struct GitExtraInfo {
+    /// The name of the branch, which is empty if it wasn't recorded.
+    pub branch: String,
+    /// The full name of the ref which `HEAD` points to.
+    #[serde(default)]
+    pub head_ref: Option<String>,
+    /// The tags which point to the commit.
+    pub tags: Option<Vec<String>>,
+    /// The commit of the superproject, if the repository is a submodule.
+    pub superproject_commit: Option<String>,
+    /// The commit which `HEAD` was split from with `git subtree split`.
+    pub subtree_split: Option<String>,
+    /// Whether the working tree had uncommitted changes.
+    // snapshots written before the working tree was checked may not have this
+    #[serde(default)]
+    pub dirty: Option<bool>,
+    /// The committer date, in seconds since the Unix epoch.
+    #[serde(default)]
+    pub commit_timestamp: Option<i64>,
+    /// The author of the commit.
+    pub author: Option<GitIdentity>,
+    /// The committer of the commit.
+    pub committer: Option<GitIdentity>,
+    /// The trailers at the end of the commit message.
+    pub trailers: Option<Vec<GitTrailer>>,
+    /// The first line of the commit message.
+    pub subject: Option<String>,
+    /// The tree of the index, if it differed from the tree of the commit.
+    pub index_tree: Option<String>,
+    /// The output of `git describe`.
+    pub describe: Option<String>,
+    /// The abbreviated hash of the commit.
+    #[serde(default)]
+    pub short_hash: String,
+    /// The url of the remote, without any credentials.
+    pub remote_url: Option<String>,
+    /// How far `HEAD` had diverged from its upstream branch.
+    pub upstream: Option<GitUpstream>,
+    /// Whether the commit was signed, and whether the signature was valid.
+    pub signature: Option<GitSignatureStatus>,
+    /// Whether each of the tags was signed, and whether the signature was valid.
+    pub tag_signatures: Option<Vec<GitTagSignature>>,
+    /// The commit which each of the tags pointed to.
+    pub tag_targets: Option<Vec<GitTagTarget>>,
+    /// The nearest tag which the commit is descended from.
+    pub nearest_tag: Option<GitNearestTag>,
+    /// The most recent version tag, parsed as a semantic version.
+    pub tag_version: Option<GitTagVersion>,
+    /// Whether the repository was a shallow clone.
+    #[serde(default)]
+    pub shallow: bool,
+    /// The submodules of the repository.
+    pub submodules: Option<Vec<GitSubmodule>>,
+    /// The notes which were attached to the commit.
+    pub notes: Option<String>,
+    /// The number of commits which `HEAD` is reachable from.
+    pub commit_count: Option<u64>,
+    /// The hash of the tree of the commit.
+    // snapshots written before the tree was recorded don't have this
+    pub tree_hash: Option<String>,
+    /// The hashes of the parents of the commit.
+    // snapshots written before the parents were recorded don't have this
+    pub parents: Option<Vec<String>>,
+    /// The commits in the history which have no parents.
+    pub root_commits: Option<Vec<String>>,
+    /// Whether the working tree was a sparse checkout.
+    pub sparse_checkout: Option<GitSparseCheckout>,
+    /// The paths of the files which made the working tree dirty.
+    pub dirty_files: Option<Vec<String>>,
+    /// The tag which `HEAD` was exactly at.
+    pub exact_tag: Option<GitExactTag>,
+    /// The payloads which were compressed instead of being recorded in full.
+    #[serde(default)]
+    pub compressed: Option<GitCompressed>,
+}
+
+/// The variants must have the same names as `git::HashAlgorithm` in the runtime crate.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum GitHashAlgorithm {
+    /// SHA-1, which has 40 digit hashes.
+    #[default]
+    Sha1,
+    /// SHA-256, which has 64 digit hashes.
+    Sha256,
+}
+
+impl GitHashAlgorithm {
+    /// Returns the algorithm of a full hash, by its length, or `None` if `hash` is not a
+    /// full hash in hex.
+    pub fn of(hash: &str) -> Option<Self> {
+        let algorithm = match hash.len() {
+            40 => GitHashAlgorithm::Sha1,
+            64 => GitHashAlgorithm::Sha256,
+            _ => return None,
+        };
+        hash.bytes()
+            .all(|b| b.is_ascii_hexdigit())
+            .then_some(algorithm)
+    }
+}
+
+runtime_names!(GitHashAlgorithm { Sha1, Sha256 });
+
+/// The author or committer of a commit.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct GitIdentity {
+    /// The name of the person.
+    pub name: String,
+    /// The email address of the person.
+    pub email: String,
+}
+
+/// A `key: value` trailer at the end of a commit message.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct GitTrailer {
+    /// The key of the trailer, e.g. `Change-Id`.
+    pub key: String,
+    /// The value of the trailer.
+    pub value: String,
+}
+
+/// The upstream branch of `HEAD`, and how far they had diverged.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct GitUpstream {
+    /// The name of the upstream, e.g. `origin/main`.
+    pub name: String,
+    /// The remote of the upstream, which is `None` for a local branch.
+    pub remote: Option<String>,
+    /// The number of commits which are only in `HEAD`.
+    pub ahead: u32,
+    /// The number of commits which are only in the upstream.
+    pub behind: u32,
+}
+
+/// The variants must have the same names as `git::SignatureStatus` in the runtime crate.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum GitSignatureStatus {
+    /// The object wasn't signed.
+    Unsigned,
+    /// The signature was valid.
+    GoodSignature,
+    /// The signature was invalid.
+    BadSignature,
+    /// The signature couldn't be checked, as the key wasn't known.
+    UnknownKey,
+}
+
+/// The signature of a tag.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct GitTagSignature {
+    /// The name of the tag.
+    pub tag: String,
+    /// Whether the tag was signed, and whether the signature was valid.
+    pub status: GitSignatureStatus,
+}
+
+/// The commit which a tag pointed to.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct GitTagTarget {
+    /// The name of the tag.
+    pub tag: String,
+    /// The commit which the tag pointed to.
+    pub commit: String,
+}
+
+/// A submodule of a git repository.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct GitSubmodule {
+    /// The path of the submodule, relative to the root of the work tree.
+    pub path: String,
+    /// The commit which was checked out in the submodule.
+    pub commit: String,
+    /// Whether the commit differed from the one which the superproject records.
+    pub dirty: bool,
+}
+
+/// The payloads of the git info which were compressed.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct GitCompressed {
+    /// The submodules, as a line per submodule.
+    pub submodules: Option<Deflated>,
+    /// The notes of the commit.
+    pub notes: Option<Deflated>,
+    /// The paths of the dirty files, as a line per path.
+    pub dirty_files: Option<Deflated>,
+}
+
+/// Data compressed with raw DEFLATE, as `version_control_info::compression::Deflated`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct Deflated {
+    /// The compressed bytes.
+    pub bytes: Vec<u8>,
+    /// The length of the data once it is decompressed.
+    pub len: usize,
+}
+
+/// How the working tree was sparsely checked out.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct GitSparseCheckout {
+    /// Whether the sparse checkout was in cone mode.
+    pub cone: bool,
+    /// Whether the directory of the crate was in the sparse checkout.
+    pub crate_included: bool,
+}
+
+/// The nearest tag which a commit is descended from.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct GitNearestTag {
+    /// The name of the tag.
+    pub name: String,
+    /// The number of commits since the tag.
+    pub distance: u32,
+}
+
+/// The tag which `HEAD` was exactly at.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct GitExactTag {
+    /// The full name of the ref of the tag.
+    pub ref_name: String,
+    /// Whether the tag was annotated.
+    pub annotated: bool,
+}
+
+/// A version tag, parsed as a semantic version.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct GitTagVersion {
+    /// The name of the tag, e.g. `v1.4.2`.
+    pub tag: String,
+    /// The major version.
+    pub major: u64,
+    /// The minor version.
+    pub minor: u64,
+    /// The patch version.
+    pub patch: u64,
+    /// The pre-release identifiers, e.g. `rc.1`.
+    pub pre: Option<String>,
+    /// The build metadata.
+    pub build: Option<String>,
+}
+
+/// What was recorded about the changeset of a Mercurial repository.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct MercurialExtraInfo {
+    /// The revision number of the changeset, which is local to the repository.
+    pub local_revision: String,
+    /// The named branch of the changeset.
+    pub branch: String,
+    /// The tags of the changeset.
+    pub tags: Vec<String>,
+    /// The bookmarks of the changeset.
+    pub bookmarks: Vec<String>,
+    /// Whether the working directory had uncommitted changes.
+    // snapshots written before the working directory was checked don't have this
+    #[serde(default)]
+    pub dirty: bool,
+    /// The phase of the changeset.
+    // snapshots written before the phase was recorded don't have this
+    pub phase: Option<MercurialPhase>,
+    /// The active bookmark, if there is one.
+    pub active_bookmark: Option<String>,
+    /// The date of the changeset, in seconds since the Unix epoch.
+    // snapshots written before the date was recorded don't have this
+    pub commit_timestamp: Option<i64>,
+    /// The author of the changeset.
+    pub author: Option<GitIdentity>,
+    /// The topic of the changeset, with the `topic` extension.
+    pub topic: Option<String>,
+}
+
+/// The variants must have the same names as `mercurial::Phase` in the runtime crate.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum MercurialPhase {
+    /// The changeset was published.
+    Public,
+    /// The changeset wasn't published yet.
+    Draft,
+    /// The changeset is never exchanged.
+    Secret,
+}
+
+runtime_names!(MercurialPhase {
+    Public,
+    Draft,
+    Secret,
+});
+
+/// What was recorded about the commit of a Jujutsu repository.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct JujutsuExtraInfo {
+    /// The bookmarks of the commit.
+    pub bookmarks: Vec<String>,
+    /// The tags of the commit.
+    pub tags: Vec<String>,
+}
+
+/// What was recorded about the channel of a Pijul repository.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct PijulExtraInfo {
+    /// The name of the channel.
+    pub channel: String,
+    /// The hash of the most recent change applied to the channel.
+    pub change: String,
+}
+
+/// What was recorded about the check-in of a Fossil repository.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct FossilExtraInfo {
+    /// The branch of the check-in.
     pub branch: String,
+    /// The tags of the check-in.
     pub tags: Vec<String>,
 }
 
+/// What was recorded about a Subversion working copy.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct SubversionExtraInfo {
+    /// The url of the working copy in the repository.
+    pub url: String,
+}
+
+/// What was recorded about the revision of a Bazaar branch.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct BazaarExtraInfo {
+    /// The revision number of the tree in the branch.
+    pub revno: String,
+    /// The nickname of the branch.
+    pub nick: String,
+}
+
+/// What was recorded about a Darcs repository.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct DarcsExtraInfo {
+    /// The name of the repository, which is the name of its root directory.
+    pub repository_name: String,
+}
+
+/// What was recorded about the commit of a Sapling repository.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct SaplingExtraInfo {
+    /// The bookmarks of the commit.
+    pub bookmarks: Vec<String>,
+}
+
+/// What was recorded about a Perforce workspace.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct PerforceExtraInfo {
+    /// The name of the client workspace.
+    pub client: String,
+    /// The stream of the workspace, if it uses streams.
+    pub stream: Option<String>,
+}
+
+/// What was recorded about a Plastic SCM workspace.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct PlasticScmExtraInfo {
+    /// The full name of the branch of the changeset, e.g. `/main/task001`.
+    pub branch: String,
+    /// The name of the repository which the workspace is connected to.
+    pub repository: String,
+}
+
+/// What was recorded about an Android `repo` client.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct AndroidRepoExtraInfo {
+    /// The projects of the client, in the order of the manifest.
+    pub projects: Vec<AndroidRepoProject>,
+}
+
+/// A project of an Android `repo` client.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct AndroidRepoProject {
+    /// The name of the project in the manifest.
+    pub name: String,
+    /// The path of the project in the client.
+    pub path: String,
+    /// The commit which was checked out in the project.
+    pub commit: String,
+}
+
+#[inline]
+fn create_get_vcs_info_file(out_dir: &Path) -> io::Result<AtomicFile> {
+    create_bindings_file(out_dir, "version_control_info_get_generated")
+}
+
 #[inline]
-fn create_get_vcs_info_file() -> io::Result<io::BufWriter<File>> {
-    create_bindings_file("version_control_info_get_generated")
+fn create_try_get_vcs_info_file(out_dir: &Path) -> io::Result<AtomicFile> {
+    create_bindings_file(out_dir, "version_control_info_try_get_generated")
 }
 
 #[inline]
-fn create_try_get_vcs_info_file() -> io::Result<io::BufWriter<File>> {
-    create_bindings_file("version_control_info_try_get_generated")
+fn create_get_all_vcs_info_file(out_dir: &Path) -> io::Result<AtomicFile> {
+    create_bindings_file(out_dir, "version_control_info_get_all_generated")
 }
 
 #[inline]
+fn create_checksum_file(out_dir: &Path) -> io::Result<AtomicFile> {
+    create_bindings_file(out_dir, "version_control_info_checksum_generated")
+}
+
+#[inline]
+fn create_source_map_file(out_dir: &Path) -> io::Result<AtomicFile> {
+    create_bindings_file(out_dir, "version_control_info_source_map_generated")
+}
+
 fn create_bindings_file<S: ?Sized + AsRef<OsStr>>(
+    out_dir: &Path,
     file_name: &S,
-) -> io::Result<io::BufWriter<File>> {
+) -> io::Result<AtomicFile> {
     #[inline(never)]
-    fn inner(file_name: &OsStr) -> io::Result<io::BufWriter<File>> {
-        fn out_dir() -> io::Result<PathBuf> {
-            env::var_os("OUT_DIR")
-                .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "could not get out directory"))
-                .map(PathBuf::from)
-        }
-
-        let out_dir = out_dir()?;
-        fs::create_dir_all(&out_dir)?;
+    fn inner(out_dir: &Path, file_name: &OsStr) -> io::Result<AtomicFile> {
+        fs::create_dir_all(out_dir)?;
 
         let path = out_dir.join(file_name).with_extension("rs");
 
-        File::create(&path).map(io::BufWriter::new)
+        AtomicFile::create(path)
     }
 
-    inner(file_name.as_ref())
+    inner(out_dir, file_name.as_ref())
 }
 
 fn write_header_comment(file: &mut dyn Write) -> io::Result<()> {
@@ -435,9 +3605,7 @@ impl Indenter {
 
     #[inline]
     fn auto_indent(&self) -> AutoIndent<'_> {
-        AutoIndent {
-            indenter: self,
-        }
+        AutoIndent { indenter: self }
     }
 
     #[must_use]
@@ -454,7 +3622,9 @@ struct AutoIndent<'a> {
 impl<'a> AutoIndent<'a> {
     #[inline]
     fn increment(&self) -> Self {
-        self.indenter.indent.set(self.indenter.indent.get().saturating_add(1));
+        self.indenter
+            .indent
+            .set(self.indenter.indent.get().saturating_add(1));
         Self {
             indenter: self.indenter,
         }
@@ -462,7 +3632,9 @@ impl<'a> AutoIndent<'a> {
 
     #[inline]
     fn decrement(&self) {
-        self.indenter.indent.set(self.indenter.indent.get().saturating_sub(1));
+        self.indenter
+            .indent
+            .set(self.indenter.indent.get().saturating_sub(1));
     }
 
     #[must_use]
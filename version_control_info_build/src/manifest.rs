@@ -0,0 +1,54 @@
+use crate::{Info, util};
+use sha2::{Digest, Sha256};
+use std::{
+    fmt::Write as _,
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::Path,
+};
+
+// Appends a line to the manifest at `manifest_path`, of the form:
+//
+// <artifact-name> <sha256> <commit> <branch> <timestamp>
+//
+// The manifest is locked while the line is written, so that concurrent builds of
+// several artifacts can share the same manifest.
+pub(crate) fn append(manifest_path: &Path, artifact_path: &Path, info: &Info) -> io::Result<()> {
+    let name = artifact_path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .ok_or_else(|| io::Error::other("the artifact path has no file name"))?;
+    if name.contains(char::is_whitespace) {
+        let msg = format!("the artifact name `{}` contains whitespace", name);
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, msg));
+    }
+
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(artifact_path)?, &mut hasher)?;
+    let digest = hasher
+        .finalize()
+        .iter()
+        .fold(String::new(), |mut hex, byte| {
+            let _ = write!(hex, "{:02x}", byte);
+            hex
+        });
+
+    let line = format!(
+        "{} {} {} {} {}\n",
+        name,
+        digest,
+        info.commit(),
+        info.specific.branch().unwrap_or("-"),
+        util::timestamp()?
+    );
+
+    let mut manifest = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(manifest_path)?;
+    manifest.lock()?;
+    // the whole line is written at once, so that readers which don't take the
+    // lock never see a partial line
+    manifest.write_all(line.as_bytes())?;
+    manifest.flush()
+}
@@ -0,0 +1,258 @@
+use crate::{
+    AutoIndent, DetectOptions, EmptyRepositoryError, GenerateOptions, GitIdentity, Info,
+    MercurialExtraInfo, MercurialPhase, Source, SpecificInfo, git::write_identity,
+    write_extra_data, write_str_slice, write_tags,
+};
+use std::{
+    error::Error,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{Child, Command, Output, Stdio},
+};
+
+#[inline(always)]
+pub(crate) fn has_hg_folder<P: ?Sized + AsRef<Path>>(project_path: &P) -> bool {
+    #[inline(never)]
+    fn inner(project_path: &Path) -> bool {
+        project_path.join(".hg").is_dir()
+    }
+
+    inner(project_path.as_ref())
+}
+
+// Returns the `.hg` directory which holds the store for the repository at `project_dir`.
+//
+// For a working directory created with `hg share`, this is the `.hg` directory of the
+// source repository, as recorded in `.hg/sharedpath`.
+pub(crate) fn shared_hg_dir(project_dir: &Path) -> io::Result<PathBuf> {
+    let hg_dir = project_dir.join(".hg");
+    match fs::read_to_string(hg_dir.join("sharedpath")) {
+        Ok(shared_path) => {
+            let shared_path = Path::new(shared_path.trim_end());
+            // relative shares (`hg share --relative`) are relative to the `.hg` directory
+            Ok(hg_dir.join(shared_path))
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(hg_dir),
+        Err(e) => Err(e),
+    }
+}
+
+// Returns the files which should trigger regeneration when they change.
+//
+// The `.hg` directory is not walked, as it may contain very large caches,
+// such as those created by the `largefiles` extension.
+pub(crate) fn rerun_paths(project_dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let hg_dir = project_dir.join(".hg");
+    let shared_dir = shared_hg_dir(project_dir)?;
+
+    let mut paths = vec![
+        // the parent revision and branch of the working directory
+        hg_dir.join("dirstate"),
+        hg_dir.join("branch"),
+        hg_dir.join("bookmarks.current"),
+        hg_dir.join("bookmarks"),
+        // the active topic of the `topic` extension
+        hg_dir.join("topic"),
+        // new commits, tags and bookmarks in the (possibly shared) store
+        shared_dir.join("store").join("00changelog.i"),
+        shared_dir.join("localtags"),
+        shared_dir.join("bookmarks"),
+        // changes when revisions are pushed, and become public
+        shared_dir.join("store").join("phaseroots"),
+    ];
+    paths.dedup();
+    paths.retain(|path| path.is_file());
+    Ok(paths)
+}
+
+pub(crate) fn detect_hg_directory(
+    project_dir: &Path,
+    options: &DetectOptions,
+) -> Result<Info, Box<dyn Error + Send + Sync + 'static>> {
+    fn handle_output(output: Output) -> Result<String, Box<dyn Error + Send + Sync + 'static>> {
+        if !output.status.success() {
+            let msg = format!("hg failed: {}", String::from_utf8_lossy(&output.stderr));
+            return Err(From::from(io::Error::other(msg)));
+        }
+
+        let mut stdout = String::from_utf8(output.stdout)?;
+        stdout.truncate(stdout.trim_end().len());
+        Ok(stdout)
+    }
+
+    #[inline]
+    fn wait_for_child(child: Child) -> Result<String, Box<dyn Error + Send + Sync + 'static>> {
+        child
+            .wait_with_output()
+            .map_err(From::from)
+            .and_then(handle_output)
+    }
+
+    // the fields of the working directory parent are printed by one command, as
+    // starting `hg` is slow. they are separated by nul bytes, and the lists of tags and
+    // bookmarks by newlines, as names can't contain either. the date is the unix
+    // timestamp, followed by the timezone offset, e.g. `1700000000 -3600`, and the
+    // active bookmark is empty if no bookmark is active.
+    let mut template = String::from(
+        "{node}\\0{rev}\\0{branch}\\0{join(tags, '\\n')}\\0{join(bookmarks, '\\n')}\\0\
+         {phase}\\0{activebookmark}\\0{date|hgdate}",
+    );
+    if options.record_identities {
+        template.push_str("\\0{person(author)}\\0{email(author)}");
+    }
+    let log = hg(project_dir, options)
+        .args(["log", "-r", ".", "-T", &template])
+        .spawn()?;
+    // the short hash of the working directory parent, followed by `+` if there are
+    // uncommitted changes, e.g. `1a2b3c4d5e6f+`. Unknown files are ignored.
+    let identify = hg(project_dir, options)
+        .args(["identify", "--id"])
+        .spawn()?;
+    // the `{topic}` keyword only exists when the `topic` extension is enabled, so this
+    // fails without it, and isn't part of the template above
+    let topic = hg(project_dir, options)
+        .args(["log", "-r", ".", "-T", "{topic}"])
+        .spawn()?;
+
+    let (log, identify) = (wait_for_child(log)?, wait_for_child(identify)?);
+    let topic = wait_for_child(topic).ok().filter(|topic| !topic.is_empty());
+    let mut fields = log.split('\0').map(String::from);
+    let mut next_field = || {
+        fields
+            .next()
+            .ok_or_else(|| io::Error::other(format!("hg printed an invalid log: {:?}", log)))
+    };
+    let (global_revision, local_revision, branch, tags, bookmarks) = (
+        next_field()?,
+        next_field()?,
+        next_field()?,
+        next_field()?,
+        next_field()?,
+    );
+    let (phase, active_bookmark, date) = (next_field()?, next_field()?, next_field()?);
+    let author = match options.record_identities {
+        true => Some(GitIdentity {
+            name: next_field()?,
+            email: next_field()?,
+        }),
+        false => None,
+    };
+
+    // the working directory parent is the null revision before the first commit
+    if global_revision.bytes().all(|b| b == b'0') {
+        return Err(From::from(EmptyRepositoryError::new(
+            project_dir.join(".hg"),
+        )));
+    }
+
+    Ok(Info {
+        specific: SpecificInfo::Mercurial {
+            global_revision,
+            extra: Some(Box::new(MercurialExtraInfo {
+                local_revision,
+                branch,
+                tags: tags.lines().map(String::from).collect(),
+                bookmarks: bookmarks.lines().map(String::from).collect(),
+                dirty: identify.ends_with('+'),
+                phase: parse_phase(&phase),
+                active_bookmark: Some(active_bookmark).filter(|name| !name.is_empty()),
+                commit_timestamp: Some(parse_date(&date)?),
+                author,
+                topic,
+            })),
+        },
+        source: Source::Repository,
+    })
+}
+
+// The timezone offset is ignored, as the timestamp is already in UTC.
+fn parse_date(date: &str) -> Result<i64, Box<dyn Error + Send + Sync + 'static>> {
+    let timestamp = date.split_whitespace().next().unwrap_or_default();
+    timestamp.parse().map_err(|_| {
+        let msg = format!("hg printed an invalid date: {}", date);
+        From::from(io::Error::other(msg))
+    })
+}
+
+#[inline]
+fn parse_phase(phase: &str) -> Option<MercurialPhase> {
+    match phase {
+        "public" => Some(MercurialPhase::Public),
+        "draft" => Some(MercurialPhase::Draft),
+        "secret" => Some(MercurialPhase::Secret),
+        _ => None,
+    }
+}
+
+pub(crate) fn write_specific_info(
+    file: &mut dyn Write,
+    global_revision: &str,
+    extra: Option<&MercurialExtraInfo>,
+    options: &GenerateOptions,
+    indent: &AutoIndent<'_>,
+) -> io::Result<()> {
+    writeln_indented!(
+        indent,
+        file,
+        "specific: version_control_info::SpecificInfo::Mercurial {{"
+    )?;
+    {
+        let _indent = indent.increment();
+        writeln_indented!(indent, file, "global_revision: {:?},", global_revision)?;
+        write_extra_data(
+            file,
+            extra,
+            "version_control_info::mercurial::ExtraData",
+            indent,
+            |file, extra, indent| {
+                writeln_indented!(indent, file, "local_revision: {:?},", extra.local_revision)?;
+                writeln_indented!(indent, file, "branch: {:?},", extra.branch)?;
+                write_tags(file, &extra.tags, options, indent)?;
+                write_str_slice(file, "bookmarks", &extra.bookmarks, indent)?;
+                writeln_indented!(indent, file, "dirty: {},", extra.dirty)?;
+                match extra.phase {
+                    Some(phase) => writeln_indented!(
+                        indent,
+                        file,
+                        "phase: Some(version_control_info::mercurial::Phase::{}),",
+                        phase.name()
+                    )?,
+                    None => writeln_indented!(indent, file, "phase: None,")?,
+                }
+                writeln_indented!(
+                    indent,
+                    file,
+                    "active_bookmark: {:?},",
+                    extra.active_bookmark.as_deref()
+                )?;
+                writeln_indented!(
+                    indent,
+                    file,
+                    "commit_timestamp: {:?},",
+                    extra.commit_timestamp
+                )?;
+                write_identity(file, "author", extra.author.as_ref(), indent)?;
+                writeln_indented!(indent, file, "topic: {:?},", extra.topic.as_deref())
+            },
+        )?;
+    }
+    writeln_indented!(indent, file, "}},")
+}
+
+#[inline]
+fn hg<P: ?Sized + AsRef<Path>>(cwd: &P, options: &DetectOptions) -> Command {
+    #[inline(never)]
+    fn inner(cwd: &Path, options: &DetectOptions) -> Command {
+        let mut cmnd = options.hg_command.command("hg");
+        // ignore any user configuration which could change the output format
+        cmnd.env("HGPLAIN", "1")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .current_dir(cwd);
+        cmnd
+    }
+
+    inner(cwd.as_ref(), options)
+}
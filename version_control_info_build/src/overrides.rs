@@ -0,0 +1,119 @@
+use crate::{DetectedInfo, GitExtraInfo, GitHashAlgorithm, Info, Source, SpecificInfo, git};
+use std::{env, io};
+
+pub(crate) const COMMIT_VAR: &str = "VERSION_CONTROL_INFO_COMMIT";
+const BRANCH_VAR: &str = "VERSION_CONTROL_INFO_BRANCH";
+const TAGS_VAR: &str = "VERSION_CONTROL_INFO_TAGS";
+const DIRTY_VAR: &str = "VERSION_CONTROL_INFO_DIRTY";
+const TIMESTAMP_VAR: &str = "VERSION_CONTROL_INFO_TIMESTAMP";
+
+pub(crate) const VARS: [&str; 5] = [COMMIT_VAR, BRANCH_VAR, TAGS_VAR, DIRTY_VAR, TIMESTAMP_VAR];
+
+// Applies the overrides in the environment to the detected info.
+//
+// A commit which differs from the detected one replaces the detected info, as the rest
+// of it describes another commit. The other variables replace the fields of git info,
+// or fill them in when only the commit is known, in which case a branch must be given.
+pub(crate) fn apply(
+    detected: DetectedInfo,
+    short_hash_length: Option<usize>,
+) -> io::Result<DetectedInfo> {
+    let mut info = match (var(COMMIT_VAR)?, detected) {
+        (Some(commit_hash), DetectedInfo::VersionControl(info)) if info.commit() == commit_hash => {
+            info
+        }
+        (Some(commit_hash), _) => {
+            let hash_algorithm = GitHashAlgorithm::of(&commit_hash)
+                .ok_or_else(|| invalid_var(COMMIT_VAR, "not a full commit hash"))?;
+            Info {
+                specific: SpecificInfo::Git {
+                    commit_hash,
+                    hash_algorithm,
+                    extra: None,
+                },
+                source: Source::Environment,
+            }
+        }
+        (None, DetectedInfo::VersionControl(info)) => info,
+        (None, detected) => return Ok(detected),
+    };
+
+    let SpecificInfo::Git {
+        ref commit_hash,
+        ref mut extra,
+        ..
+    } = info.specific
+    else {
+        return Ok(DetectedInfo::VersionControl(info));
+    };
+
+    let branch = var(BRANCH_VAR)?;
+    let extra = match (extra, branch) {
+        (Some(extra), branch) => {
+            if let Some(branch) = branch {
+                extra.head_ref = git::head_ref(&branch);
+                extra.branch = branch;
+            }
+            extra
+        }
+        (extra @ None, Some(branch)) => extra.insert(Box::new(git_extra_info(
+            commit_hash,
+            branch,
+            short_hash_length,
+        )?)),
+        (None, None) => return Ok(DetectedInfo::VersionControl(info)),
+    };
+
+    if let Some(tags) = var(TAGS_VAR)? {
+        let tags = tags.split(',').map(str::trim).filter(|tag| !tag.is_empty());
+        extra.tags = Some(tags.map(String::from).collect());
+    }
+    if let Some(dirty) = var(DIRTY_VAR)? {
+        extra.dirty = match &*dirty {
+            "1" | "true" => Some(true),
+            "0" | "false" => Some(false),
+            _ => return Err(invalid_var(DIRTY_VAR, "expected `true` or `false`")),
+        };
+    }
+    if let Some(timestamp) = var(TIMESTAMP_VAR)? {
+        extra.commit_timestamp =
+            Some(timestamp.trim().parse().map_err(|_| {
+                invalid_var(TIMESTAMP_VAR, "expected seconds since the Unix epoch")
+            })?);
+    }
+
+    Ok(DetectedInfo::VersionControl(info))
+}
+
+// The extra info of a commit whose details weren't read from the repository, e.g. as it
+// was provided by the environment, so that only its branch is known.
+pub(crate) fn git_extra_info(
+    commit_hash: &str,
+    branch: String,
+    short_hash_length: Option<usize>,
+) -> io::Result<GitExtraInfo> {
+    Ok(GitExtraInfo {
+        head_ref: git::head_ref(&branch),
+        branch,
+        short_hash: git::abbreviate(commit_hash, short_hash_length),
+        // reproducible builds set this to the time of the commit, and otherwise it is
+        // unknown, as is whether the working tree is dirty
+        commit_timestamp: var("SOURCE_DATE_EPOCH")?.and_then(|epoch| epoch.trim().parse().ok()),
+        ..GitExtraInfo::default()
+    })
+}
+
+// Returns the value of the variable, treating a variable which isn't unicode as an error
+// rather than as unset, so that a broken override isn't silently ignored.
+pub(crate) fn var(name: &str) -> io::Result<Option<String>> {
+    match env::var(name) {
+        Ok(value) => Ok(Some(value)),
+        Err(env::VarError::NotPresent) => Ok(None),
+        Err(env::VarError::NotUnicode(_)) => Err(invalid_var(name, "not valid unicode")),
+    }
+}
+
+pub(crate) fn invalid_var(name: &str, reason: &str) -> io::Error {
+    let msg = format!("invalid value of `{}`: {}", name, reason);
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
@@ -0,0 +1,159 @@
+use crate::{
+    AutoIndent, DetectOptions, EmptyRepositoryError, GenerateOptions, Info, PerforceExtraInfo,
+    Source, SpecificInfo, write_extra_data,
+};
+use std::{
+    env,
+    error::Error,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{Command, Output, Stdio},
+};
+
+// Perforce workspaces have no metadata directory, and the server may be slow to respond,
+// so `p4` is only asked about the workspace if it has been configured through either a
+// `P4CONFIG` file or the `P4CLIENT` variable.
+fn is_p4_configured(project_dir: &Path) -> bool {
+    let has_config_file = env::var_os("P4CONFIG")
+        .map(|config| {
+            project_dir
+                .ancestors()
+                .any(|dir| dir.join(&config).is_file())
+        })
+        .unwrap_or_default();
+
+    has_config_file || env::var_os("P4CLIENT").is_some()
+}
+
+// Returns the root of the client workspace which contains `project_dir`, if there is one.
+pub(crate) fn client_root(project_dir: &Path) -> io::Result<Option<PathBuf>> {
+    if !is_p4_configured(project_dir) {
+        return Ok(None);
+    }
+
+    let output = match p4(project_dir).arg("info").spawn() {
+        Ok(child) => child.wait_with_output()?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    let info = String::from_utf8_lossy(&output.stdout);
+    let root = tagged_field(&info, "clientRoot")
+        .map(PathBuf::from)
+        .filter(|root| project_dir.starts_with(root));
+    Ok(root)
+}
+
+pub(crate) fn detect_p4_workspace(
+    project_dir: &Path,
+    _options: &DetectOptions,
+) -> Result<Info, Box<dyn Error + Send + Sync + 'static>> {
+    fn handle_output(output: Output) -> Result<String, Box<dyn Error + Send + Sync + 'static>> {
+        if !output.status.success() {
+            let msg = format!("p4 failed: {}", String::from_utf8_lossy(&output.stderr));
+            return Err(From::from(io::Error::other(msg)));
+        }
+
+        String::from_utf8(output.stdout).map_err(From::from)
+    }
+
+    // the most recent changelist which has been synced to the workspace, for the
+    // files in the project directory
+    let changes = p4(project_dir)
+        .args(["changes", "-m1", "...#have"])
+        .spawn()?;
+    let info = p4(project_dir).arg("info").spawn()?;
+
+    let (changes, info) = (
+        handle_output(changes.wait_with_output()?)?,
+        handle_output(info.wait_with_output()?)?,
+    );
+
+    let changelist = match tagged_field(&changes, "change") {
+        Some(changelist) => changelist.to_owned(),
+        None => {
+            let root = tagged_field(&info, "clientRoot").unwrap_or_default();
+            return Err(From::from(EmptyRepositoryError::new(root)));
+        }
+    };
+
+    let client = tagged_field(&info, "clientName")
+        .map(String::from)
+        .ok_or_else(|| io::Error::other("could not find the perforce client name"))?;
+    let stream = tagged_field(&info, "clientStream").map(String::from);
+
+    Ok(Info {
+        specific: SpecificInfo::Perforce {
+            changelist,
+            extra: Some(PerforceExtraInfo { client, stream }),
+        },
+        source: Source::Repository,
+    })
+}
+
+pub(crate) fn write_specific_info(
+    file: &mut dyn Write,
+    changelist: &str,
+    extra: Option<&PerforceExtraInfo>,
+    _options: &GenerateOptions,
+    indent: &AutoIndent<'_>,
+) -> io::Result<()> {
+    writeln_indented!(
+        indent,
+        file,
+        "specific: version_control_info::SpecificInfo::Perforce {{"
+    )?;
+    {
+        let _indent = indent.increment();
+        writeln_indented!(indent, file, "changelist: {:?},", changelist)?;
+        write_extra_data(
+            file,
+            extra,
+            "version_control_info::perforce::ExtraData",
+            indent,
+            |file, extra, indent| {
+                writeln_indented!(indent, file, "client: {:?},", extra.client)?;
+                match extra.stream {
+                    Some(ref stream) => {
+                        writeln_indented!(indent, file, "stream: Some({:?}),", stream)
+                    }
+                    None => writeln_indented!(indent, file, "stream: None,"),
+                }
+            },
+        )?;
+    }
+    writeln_indented!(indent, file, "}},")
+}
+
+// `p4 -ztag` prints a `... key value` pair on each line, e.g.
+//
+// ... clientName jane-main
+// ... clientStream //depot/main
+fn tagged_field<'a>(output: &'a str, key: &str) -> Option<&'a str> {
+    output.lines().find_map(|line| {
+        let (line_key, value) = line.strip_prefix("... ")?.split_once(' ')?;
+        (line_key == key).then(|| value.trim())
+    })
+}
+
+#[inline]
+fn p4<P: ?Sized + AsRef<Path>>(cwd: &P) -> Command {
+    #[inline(never)]
+    fn inner(cwd: &Path) -> Command {
+        let mut cmnd = Command::new("p4");
+        // use the tagged output format, which is stable between versions. `p4` reads
+        // the working directory from `PWD`, so that has to be set as well.
+        cmnd.arg("-ztag")
+            .env("PWD", cwd)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .current_dir(cwd);
+        cmnd
+    }
+
+    inner(cwd.as_ref())
+}
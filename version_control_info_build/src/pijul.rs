@@ -0,0 +1,125 @@
+use crate::{
+    AutoIndent, DetectOptions, EmptyRepositoryError, GenerateOptions, Info, PijulExtraInfo, Source,
+    SpecificInfo, write_extra_data,
+};
+use serde::Deserialize;
+use std::{
+    error::Error,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{Command, Output, Stdio},
+};
+
+#[derive(Deserialize)]
+struct LogEntry {
+    hash: String,
+    state: Option<String>,
+}
+
+#[inline(always)]
+pub(crate) fn has_pijul_folder<P: ?Sized + AsRef<Path>>(project_path: &P) -> bool {
+    #[inline(never)]
+    fn inner(project_path: &Path) -> bool {
+        project_path.join(".pijul").is_dir()
+    }
+
+    inner(project_path.as_ref())
+}
+
+#[inline]
+pub(crate) fn pristine_dir(project_dir: &Path) -> PathBuf {
+    project_dir.join(".pijul").join("pristine")
+}
+
+pub(crate) fn detect_pijul_directory(
+    project_dir: &Path,
+    _options: &DetectOptions,
+) -> Result<Info, Box<dyn Error + Send + Sync + 'static>> {
+    fn handle_output(output: Output) -> Result<String, Box<dyn Error + Send + Sync + 'static>> {
+        if !output.status.success() {
+            let msg = format!("pijul failed: {}", String::from_utf8_lossy(&output.stderr));
+            return Err(From::from(io::Error::other(msg)));
+        }
+
+        String::from_utf8(output.stdout).map_err(From::from)
+    }
+
+    let log = pijul(project_dir)
+        .args(["log", "--state", "--limit", "1", "--output-format", "json"])
+        .spawn()?;
+    let channels = pijul(project_dir).arg("channel").spawn()?;
+
+    let (log, channels) = (
+        handle_output(log.wait_with_output()?)?,
+        handle_output(channels.wait_with_output()?)?,
+    );
+
+    let entries: Vec<LogEntry> = serde_json::from_str(&log)?;
+    let entry = entries
+        .into_iter()
+        .next()
+        .ok_or_else(|| EmptyRepositoryError::new(project_dir.join(".pijul")))?;
+    let (state, change) = entry
+        .state
+        .map(|state| (state, entry.hash))
+        .ok_or_else(|| io::Error::other("pijul did not report the channel state"))?;
+
+    // the current channel is marked with a `*`
+    let channel = channels
+        .lines()
+        .find_map(|line| line.strip_prefix("* "))
+        .map(|channel| channel.trim().to_owned())
+        .ok_or_else(|| io::Error::other("could not determine the current pijul channel"))?;
+
+    Ok(Info {
+        specific: SpecificInfo::Pijul {
+            state,
+            extra: Some(PijulExtraInfo { channel, change }),
+        },
+        source: Source::Repository,
+    })
+}
+
+pub(crate) fn write_specific_info(
+    file: &mut dyn Write,
+    state: &str,
+    extra: Option<&PijulExtraInfo>,
+    _options: &GenerateOptions,
+    indent: &AutoIndent<'_>,
+) -> io::Result<()> {
+    writeln_indented!(
+        indent,
+        file,
+        "specific: version_control_info::SpecificInfo::Pijul {{"
+    )?;
+    {
+        let _indent = indent.increment();
+        writeln_indented!(indent, file, "state: {:?},", state)?;
+        write_extra_data(
+            file,
+            extra,
+            "version_control_info::pijul::ExtraData",
+            indent,
+            |file, extra, indent| {
+                writeln_indented!(indent, file, "channel: {:?},", extra.channel)?;
+                writeln_indented!(indent, file, "change: {:?},", extra.change)
+            },
+        )?;
+    }
+    writeln_indented!(indent, file, "}},")
+}
+
+#[inline]
+fn pijul<P: ?Sized + AsRef<Path>>(cwd: &P) -> Command {
+    #[inline(never)]
+    fn inner(cwd: &Path) -> Command {
+        let mut cmnd = Command::new("pijul");
+        cmnd.stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .current_dir(cwd);
+        cmnd
+    }
+
+    inner(cwd.as_ref())
+}
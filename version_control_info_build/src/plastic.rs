@@ -0,0 +1,132 @@
+use crate::{
+    AutoIndent, DetectOptions, EmptyRepositoryError, GenerateOptions, Info, PlasticScmExtraInfo,
+    Source, SpecificInfo, write_extra_data,
+};
+use std::{
+    error::Error,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+// Only the root of a workspace contains a `.plastic` directory, so the project
+// may be in a sub-directory of the workspace.
+#[inline(always)]
+pub(crate) fn workspace_root<P: ?Sized + AsRef<Path>>(project_path: &P) -> Option<PathBuf> {
+    #[inline(never)]
+    fn inner(project_path: &Path) -> Option<PathBuf> {
+        project_path
+            .ancestors()
+            .find(|dir| dir.join(".plastic").is_dir())
+            .map(Path::to_path_buf)
+    }
+
+    inner(project_path.as_ref())
+}
+
+// Returns the files which should trigger regeneration when they change.
+pub(crate) fn rerun_paths(workspace_root: &Path) -> Vec<PathBuf> {
+    let plastic_dir = workspace_root.join(".plastic");
+
+    let mut paths = vec![
+        // the branch or changeset which the workspace is switched to
+        plastic_dir.join("plastic.selector"),
+        // the loaded changeset, which is rewritten on every update
+        plastic_dir.join("plastic.wktree"),
+    ];
+    paths.retain(|path| path.is_file());
+    paths
+}
+
+pub(crate) fn detect_plastic_workspace(
+    project_dir: &Path,
+    _options: &DetectOptions,
+) -> Result<Info, Box<dyn Error + Send + Sync + 'static>> {
+    let output = cm(project_dir)
+        .args(["status", "--header", "--machinereadable"])
+        .spawn()?
+        .wait_with_output()?;
+
+    if !output.status.success() {
+        let msg = format!("cm failed: {}", String::from_utf8_lossy(&output.stderr));
+        return Err(From::from(io::Error::other(msg)));
+    }
+
+    // the header is a single line, of the form:
+    //
+    // STATUS <changeset> <branch> <repository> <server>
+    let stdout = String::from_utf8(output.stdout)?;
+    let mut fields = stdout
+        .lines()
+        .find_map(|line| line.strip_prefix("STATUS "))
+        .ok_or_else(|| io::Error::other("unexpected output from cm status"))?
+        .split_whitespace();
+    let mut next_field = || {
+        fields
+            .next()
+            .map(String::from)
+            .ok_or_else(|| io::Error::other("unexpected output from cm status"))
+    };
+
+    let changeset = next_field()?;
+    let branch = next_field()?;
+    let repository = next_field()?;
+
+    // changeset 0 is the empty root changeset, which every repository starts with
+    if changeset == "0" {
+        let root = workspace_root(project_dir).unwrap_or_else(|| project_dir.to_path_buf());
+        return Err(From::from(EmptyRepositoryError::new(root.join(".plastic"))));
+    }
+
+    Ok(Info {
+        specific: SpecificInfo::PlasticScm {
+            changeset,
+            extra: Some(PlasticScmExtraInfo { branch, repository }),
+        },
+        source: Source::Repository,
+    })
+}
+
+pub(crate) fn write_specific_info(
+    file: &mut dyn Write,
+    changeset: &str,
+    extra: Option<&PlasticScmExtraInfo>,
+    _options: &GenerateOptions,
+    indent: &AutoIndent<'_>,
+) -> io::Result<()> {
+    writeln_indented!(
+        indent,
+        file,
+        "specific: version_control_info::SpecificInfo::PlasticScm {{"
+    )?;
+    {
+        let _indent = indent.increment();
+        writeln_indented!(indent, file, "changeset: {:?},", changeset)?;
+        write_extra_data(
+            file,
+            extra,
+            "version_control_info::plastic::ExtraData",
+            indent,
+            |file, extra, indent| {
+                writeln_indented!(indent, file, "branch: {:?},", extra.branch)?;
+                writeln_indented!(indent, file, "repository: {:?},", extra.repository)
+            },
+        )?;
+    }
+    writeln_indented!(indent, file, "}},")
+}
+
+#[inline]
+fn cm<P: ?Sized + AsRef<Path>>(cwd: &P) -> Command {
+    #[inline(never)]
+    fn inner(cwd: &Path) -> Command {
+        let mut cmnd = Command::new("cm");
+        cmnd.stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .current_dir(cwd);
+        cmnd
+    }
+
+    inner(cwd.as_ref())
+}
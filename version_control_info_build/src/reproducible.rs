@@ -0,0 +1,63 @@
+use crate::{Info, SpecificInfo, overrides};
+use std::{borrow::Cow, io};
+
+// Makes the info the same for every build of its commit: timestamps are clamped to
+// `SOURCE_DATE_EPOCH`, if it is set, and tags are sorted, as their order depends on the
+// tool and the repository.
+pub(crate) fn normalize(mut info: Cow<'_, Info>) -> io::Result<Cow<'_, Info>> {
+    let epoch = match overrides::var("SOURCE_DATE_EPOCH")? {
+        Some(epoch) => Some(epoch.trim().parse::<i64>().map_err(|_| {
+            overrides::invalid_var("SOURCE_DATE_EPOCH", "expected seconds since the Unix epoch")
+        })?),
+        None => None,
+    };
+    let clamp = |timestamp: i64| epoch.map_or(timestamp, |epoch| timestamp.min(epoch));
+
+    match info.to_mut().specific {
+        SpecificInfo::Git {
+            extra: Some(ref mut extra),
+            ..
+        } => {
+            extra.commit_timestamp = extra.commit_timestamp.map(clamp);
+            if let Some(ref mut tags) = extra.tags {
+                sort(tags);
+            }
+            if let Some(ref mut tag_signatures) = extra.tag_signatures {
+                tag_signatures.sort_by(|a, b| a.tag.cmp(&b.tag));
+            }
+            if let Some(ref mut tag_targets) = extra.tag_targets {
+                tag_targets.sort_by(|a, b| a.tag.cmp(&b.tag));
+            }
+        }
+        SpecificInfo::Mercurial {
+            extra: Some(ref mut extra),
+            ..
+        } => {
+            extra.commit_timestamp = extra.commit_timestamp.map(clamp);
+            sort(&mut extra.tags);
+            sort(&mut extra.bookmarks);
+        }
+        SpecificInfo::Jujutsu {
+            extra: Some(ref mut extra),
+            ..
+        } => {
+            sort(&mut extra.tags);
+            sort(&mut extra.bookmarks);
+        }
+        SpecificInfo::Fossil {
+            extra: Some(ref mut extra),
+            ..
+        } => sort(&mut extra.tags),
+        SpecificInfo::Sapling {
+            extra: Some(ref mut extra),
+            ..
+        } => sort(&mut extra.bookmarks),
+        _ => {}
+    }
+    Ok(info)
+}
+
+fn sort(names: &mut Vec<String>) {
+    names.sort_unstable();
+    names.dedup();
+}
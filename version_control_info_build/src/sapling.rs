@@ -0,0 +1,134 @@
+use crate::{
+    AutoIndent, DetectOptions, EmptyRepositoryError, GenerateOptions, Info, SaplingExtraInfo,
+    Source, SpecificInfo, write_extra_data, write_str_slice,
+};
+use std::{
+    error::Error,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{Child, Command, Output, Stdio},
+};
+
+#[inline(always)]
+pub(crate) fn has_sl_folder<P: ?Sized + AsRef<Path>>(project_path: &P) -> bool {
+    #[inline(never)]
+    fn inner(project_path: &Path) -> bool {
+        project_path.join(".sl").is_dir()
+    }
+
+    inner(project_path.as_ref())
+}
+
+// Returns the files which should trigger regeneration when they change.
+pub(crate) fn rerun_paths(project_dir: &Path) -> Vec<PathBuf> {
+    let sl_dir = project_dir.join(".sl");
+
+    let mut paths = vec![
+        // the parent commit of the working copy
+        sl_dir.join("dirstate"),
+        sl_dir.join("bookmarks.current"),
+        // bookmarks are kept in the store
+        sl_dir.join("store").join("bookmarks"),
+    ];
+    paths.retain(|path| path.is_file());
+    paths
+}
+
+pub(crate) fn detect_sl_directory(
+    project_dir: &Path,
+    _options: &DetectOptions,
+) -> Result<Info, Box<dyn Error + Send + Sync + 'static>> {
+    fn handle_output(output: Output) -> Result<String, Box<dyn Error + Send + Sync + 'static>> {
+        if !output.status.success() {
+            let msg = format!("sl failed: {}", String::from_utf8_lossy(&output.stderr));
+            return Err(From::from(io::Error::other(msg)));
+        }
+
+        let mut stdout = String::from_utf8(output.stdout)?;
+        stdout.truncate(stdout.trim_end().len());
+        Ok(stdout)
+    }
+
+    #[inline]
+    fn wait_for_child(child: Child) -> Result<String, Box<dyn Error + Send + Sync + 'static>> {
+        child
+            .wait_with_output()
+            .map_err(From::from)
+            .and_then(handle_output)
+    }
+
+    let commit_hash = sl(project_dir).arg("whereami").spawn()?;
+    let bookmarks = sl(project_dir)
+        .args(["bookmark", "-T", "{node} {bookmark}\\n"])
+        .spawn()?;
+
+    let (commit_hash, bookmarks) = (wait_for_child(commit_hash)?, wait_for_child(bookmarks)?);
+
+    // the working copy parent is the null commit before the first commit
+    if commit_hash.bytes().all(|b| b == b'0') {
+        return Err(From::from(EmptyRepositoryError::new(
+            project_dir.join(".sl"),
+        )));
+    }
+
+    // `sl bookmark` lists every bookmark, so only keep those which point at
+    // the working copy parent.
+    let bookmarks = bookmarks
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .filter(|&(node, _)| node == commit_hash)
+        .map(|(_, bookmark)| bookmark.to_owned())
+        .collect();
+
+    Ok(Info {
+        specific: SpecificInfo::Sapling {
+            commit_hash,
+            extra: Some(SaplingExtraInfo { bookmarks }),
+        },
+        source: Source::Repository,
+    })
+}
+
+pub(crate) fn write_specific_info(
+    file: &mut dyn Write,
+    commit_hash: &str,
+    extra: Option<&SaplingExtraInfo>,
+    _options: &GenerateOptions,
+    indent: &AutoIndent<'_>,
+) -> io::Result<()> {
+    writeln_indented!(
+        indent,
+        file,
+        "specific: version_control_info::SpecificInfo::Sapling {{"
+    )?;
+    {
+        let _indent = indent.increment();
+        writeln_indented!(indent, file, "commit_hash: {:?},", commit_hash)?;
+        write_extra_data(
+            file,
+            extra,
+            "version_control_info::sapling::ExtraData",
+            indent,
+            |file, extra, indent| write_str_slice(file, "bookmarks", &extra.bookmarks, indent),
+        )?;
+    }
+    writeln_indented!(indent, file, "}},")
+}
+
+#[inline]
+fn sl<P: ?Sized + AsRef<Path>>(cwd: &P) -> Command {
+    #[inline(never)]
+    fn inner(cwd: &Path) -> Command {
+        let mut cmnd = Command::new("sl");
+        // sapling inherits `HGPLAIN` from mercurial, which ignores any user
+        // configuration that could change the output format
+        cmnd.env("HGPLAIN", "1")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .current_dir(cwd);
+        cmnd
+    }
+
+    inner(cwd.as_ref())
+}
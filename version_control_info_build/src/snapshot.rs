@@ -0,0 +1,59 @@
+use crate::{Info, Source, SpecificInfo, atomic};
+use serde::{Deserialize, Serialize};
+use std::{
+    error::Error,
+    fs,
+    io::{self, BufReader},
+    path::Path,
+};
+
+pub(crate) const FILE_NAME: &str = ".vcs_info_snapshot.json";
+
+/// Example file
+///
+/// ```json
+/// {
+///   "specific": {
+///     "Git": {
+///       "commit_hash": "aac20b6e7e543e6dd4118b246c77225e3a3a1302",
+///       "extra": null
+///     }
+///   }
+/// }
+/// ```
+#[derive(Serialize, Deserialize, Debug)]
+struct Snapshot {
+    specific: SpecificInfo,
+}
+
+// Writes the snapshot into `dir`, unless it already contains the same info, so that
+// calling this on every build doesn't keep modifying the file.
+pub(crate) fn write(dir: &Path, info: &Info) -> io::Result<()> {
+    let snapshot = Snapshot {
+        specific: info.specific.clone(),
+    };
+    let mut contents = serde_json::to_string_pretty(&snapshot)?;
+    contents.push('\n');
+
+    let path = dir.join(FILE_NAME);
+    match fs::read_to_string(&path) {
+        Ok(existing) if existing == contents => Ok(()),
+        Ok(_) => atomic::write(&path, contents.as_bytes()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => atomic::write(&path, contents.as_bytes()),
+        Err(e) => Err(e),
+    }
+}
+
+pub(crate) fn read(dir: &Path) -> Result<Option<Info>, Box<dyn Error + Send + Sync + 'static>> {
+    let file = match fs::File::open(dir.join(FILE_NAME)) {
+        Ok(file) => BufReader::new(file),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(From::from(e)),
+    };
+
+    let snapshot: Snapshot = serde_json::from_reader(file)?;
+    Ok(Some(Info {
+        specific: snapshot.specific,
+        source: Source::SnapshotFile,
+    }))
+}
@@ -0,0 +1,104 @@
+use std::{
+    env,
+    io::{self, Write},
+    path::{Component, Path},
+};
+
+// The paths which resolve source files of the build to paths in the repository. These
+// are embedded as `version_control_info::source_map::SourceMap`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub(crate) struct SourceMap {
+    crate_path: String,
+    workspace_dir: String,
+    workspace_path: String,
+    remaps: Vec<(String, String)>,
+}
+
+// Returns the source map of the crate, if it is being built with a dev profile. Paths
+// which aren't inside the repository, or aren't valid UTF-8, can't be mapped.
+pub(crate) fn detect(
+    repo_root: &Path,
+    workspace_dir: &Path,
+    manifest_dir: &Path,
+) -> Option<SourceMap> {
+    // cargo sets `PROFILE` to `debug` for the dev profile and the profiles which inherit
+    // from it, which are the only ones that can contain absolute paths of the machine
+    if env::var("PROFILE").ok()? != "debug" {
+        return None;
+    }
+
+    Some(SourceMap {
+        crate_path: repo_path(repo_root, manifest_dir)?,
+        workspace_dir: workspace_dir.to_str()?.to_owned(),
+        workspace_path: repo_path(repo_root, workspace_dir)?,
+        remaps: remaps(),
+    })
+}
+
+// The `--remap-path-prefix` options which rustc is passed for the crate, in order.
+fn remaps() -> Vec<(String, String)> {
+    let rustflags = env::var("CARGO_ENCODED_RUSTFLAGS").unwrap_or_default();
+    let mut flags = rustflags.split('\x1f').filter(|flag| !flag.is_empty());
+
+    let mut remaps = vec![];
+    while let Some(flag) = flags.next() {
+        let remap = match flag.strip_prefix("--remap-path-prefix") {
+            Some("") => flags.next(),
+            Some(remap) => remap.strip_prefix('='),
+            None => None,
+        };
+        // rustc splits at the last `=`, as the prefix may contain one
+        if let Some((from, to)) = remap.and_then(|remap| remap.rsplit_once('=')) {
+            remaps.push((from.to_owned(), to.to_owned()));
+        }
+    }
+    remaps
+}
+
+// The path of `dir` relative to the root of the repository, with `/` separators.
+fn repo_path(repo_root: &Path, dir: &Path) -> Option<String> {
+    let relative = dir.strip_prefix(repo_root).ok()?;
+    let components = relative
+        .components()
+        .map(|component| match component {
+            Component::Normal(name) => name.to_str(),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(components.join("/"))
+}
+
+pub(crate) fn write(file: &mut dyn Write, source_map: Option<&SourceMap>) -> io::Result<()> {
+    let source_map = match source_map {
+        Some(source_map) => source_map,
+        None => {
+            return writeln!(
+                file,
+                "::core::option::Option::<version_control_info::source_map::SourceMap<'static>>::None"
+            );
+        }
+    };
+
+    writeln!(
+        file,
+        "::core::option::Option::<version_control_info::source_map::SourceMap<'static>>::Some("
+    )?;
+    writeln!(file, "    version_control_info::source_map::SourceMap {{")?;
+    writeln!(file, "        crate_path: {:?},", source_map.crate_path)?;
+    writeln!(
+        file,
+        "        workspace: version_control_info::source_map::PathRemap {{ from: {:?}, to: {:?} }},",
+        source_map.workspace_dir, source_map.workspace_path
+    )?;
+    writeln!(file, "        remaps: &[")?;
+    for (from, to) in &source_map.remaps {
+        writeln!(
+            file,
+            "            version_control_info::source_map::PathRemap {{ from: {:?}, to: {:?} }},",
+            from, to
+        )?;
+    }
+    writeln!(file, "        ],")?;
+    writeln!(file, "    }},")?;
+    writeln!(file, ")")
+}
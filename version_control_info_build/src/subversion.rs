@@ -0,0 +1,107 @@
+use crate::{
+    AutoIndent, DetectOptions, GenerateOptions, Info, Source, SpecificInfo, SubversionExtraInfo,
+    write_extra_data,
+};
+use std::{
+    error::Error,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+};
+
+// Since Subversion 1.7, only the root of a working copy contains a `.svn` directory,
+// so the project may be in a sub-directory of the working copy.
+#[inline(always)]
+pub(crate) fn working_copy_root<P: ?Sized + AsRef<Path>>(project_path: &P) -> Option<PathBuf> {
+    #[inline(never)]
+    fn inner(project_path: &Path) -> Option<PathBuf> {
+        project_path
+            .ancestors()
+            .find(|dir| dir.join(".svn").is_dir())
+            .map(Path::to_path_buf)
+    }
+
+    inner(project_path.as_ref())
+}
+
+#[inline]
+pub(crate) fn wc_db_file(working_copy_root: &Path) -> PathBuf {
+    working_copy_root.join(".svn").join("wc.db")
+}
+
+pub(crate) fn detect_svn_working_copy(
+    project_dir: &Path,
+    _options: &DetectOptions,
+) -> Result<Info, Box<dyn Error + Send + Sync + 'static>> {
+    fn wait_for_child(child: Child) -> Result<String, Box<dyn Error + Send + Sync + 'static>> {
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            let msg = format!("svn failed: {}", String::from_utf8_lossy(&output.stderr));
+            return Err(From::from(io::Error::other(msg)));
+        }
+
+        let stdout = String::from_utf8(output.stdout)?;
+        Ok(stdout.trim().to_owned())
+    }
+
+    let svn_info = |item: &str| {
+        let mut cmnd = svn(project_dir);
+        cmnd.args(["info", "--no-newline", "--show-item", item]);
+        cmnd
+    };
+
+    let revision = svn_info("revision").spawn()?;
+    let url = svn_info("url").spawn()?;
+
+    let (revision, url) = (wait_for_child(revision)?, wait_for_child(url)?);
+
+    Ok(Info {
+        specific: SpecificInfo::Subversion {
+            revision,
+            extra: Some(SubversionExtraInfo { url }),
+        },
+        source: Source::Repository,
+    })
+}
+
+pub(crate) fn write_specific_info(
+    file: &mut dyn Write,
+    revision: &str,
+    extra: Option<&SubversionExtraInfo>,
+    _options: &GenerateOptions,
+    indent: &AutoIndent<'_>,
+) -> io::Result<()> {
+    writeln_indented!(
+        indent,
+        file,
+        "specific: version_control_info::SpecificInfo::Subversion {{"
+    )?;
+    {
+        let _indent = indent.increment();
+        writeln_indented!(indent, file, "revision: {:?},", revision)?;
+        write_extra_data(
+            file,
+            extra,
+            "version_control_info::subversion::ExtraData",
+            indent,
+            |file, extra, indent| writeln_indented!(indent, file, "url: {:?},", extra.url),
+        )?;
+    }
+    writeln_indented!(indent, file, "}},")
+}
+
+#[inline]
+fn svn<P: ?Sized + AsRef<Path>>(cwd: &P) -> Command {
+    #[inline(never)]
+    fn inner(cwd: &Path) -> Command {
+        let mut cmnd = Command::new("svn");
+        cmnd.arg("--non-interactive")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .current_dir(cwd);
+        cmnd
+    }
+
+    inner(cwd.as_ref())
+}